@@ -0,0 +1,739 @@
+// Headless front end to the monitoring backend, for SSH sessions and lab scripts.
+//
+// Exit codes: 0 success, 1 a test failed or the safety watchdog aborted it, 2 invalid arguments,
+// 3 the command couldn't run (unreadable plan, I/O or sensor error), 130 interrupted with Ctrl-C.
+
+use clap::error::ErrorKind;
+use clap::{ArgGroup, Args, CommandFactory, Parser, Subcommand};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{System, SystemExt};
+
+use tempdetect_lib::engine::{self, RECORDER, SAMPLER, TEST_PLAN};
+use tempdetect_lib::gpu_stress::{GpuIntensity, GpuSelector};
+use tempdetect_lib::memory_stress::MemoryStressOptions;
+use tempdetect_lib::metrics;
+use tempdetect_lib::plan::{PlanEvent, TestPlan};
+use tempdetect_lib::recording::{self, MachineInfo, RecordingFormat};
+use tempdetect_lib::sampling::{self, SeriesStats, Snapshot};
+use tempdetect_lib::sensors;
+use tempdetect_lib::stress::{CpuStressOptions, CpuWorkload, StressSlot, StressState, StressStatus};
+use tempdetect_lib::watchdog::SafetyLimits;
+use tempdetect_lib::{GPU_STRESS_TEST, MEMORY_STRESS_TEST, STRESS_TEST};
+
+const EXIT_FAILED: u8 = 1;
+const EXIT_ERROR: u8 = 3;
+const EXIT_INTERRUPTED: u8 = 130;
+
+// How often waiting commands check for Ctrl-C
+const TICK: Duration = Duration::from_millis(100);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Read sensors, run stress tests and test plans without the GUI
+#[derive(Parser)]
+#[command(name = "tempdetect-cli", version)]
+struct Cli {
+    /// Print JSON instead of text; long-running commands print one object per line
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Replace the sensors with a simulated machine, optionally with this many cores (also TEMPDETECT_SIMULATE)
+    #[arg(long, global = true, value_name = "CORES", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+    simulate: Option<String>,
+
+    /// Serve Prometheus metrics while the command runs, on 127.0.0.1:9101 unless an address is given (also TEMPDETECT_METRICS)
+    #[arg(long, global = true, value_name = "ADDR", num_args = 0..=1, require_equals = true, default_missing_value = metrics::DEFAULT_BIND)]
+    metrics: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Read every sensor once
+    Sensors,
+    /// Print sensor readings until interrupted
+    Watch(WatchArgs),
+    /// Run CPU, GPU and/or memory stress tests for a fixed time
+    Stress(StressArgs),
+    /// Record every sensor to a CSV or JSONL file
+    Record(RecordArgs),
+    /// Scripted test plans
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum PlanCommand {
+    /// Run a TOML or JSON test plan and check its assertions
+    Run(PlanRunArgs),
+}
+
+#[derive(Args)]
+struct WatchArgs {
+    /// Seconds between readings
+    #[arg(long, default_value_t = 1.0, value_parser = parse_interval)]
+    interval: f64,
+
+    /// Stop after this many readings
+    #[arg(long)]
+    count: Option<u64>,
+
+    /// Show these channels instead of the default columns, e.g. core_temp_0 or cpu_usage_3
+    #[arg(long = "sensor", value_name = "ID")]
+    sensors: Vec<String>,
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("workloads").required(true).multiple(true).args(["cpu", "gpu", "memory"])))]
+struct StressArgs {
+    /// Load the CPU
+    #[arg(long)]
+    cpu: bool,
+
+    /// Load the GPU
+    #[arg(long)]
+    gpu: bool,
+
+    /// Load memory bandwidth
+    #[arg(long)]
+    memory: bool,
+
+    /// Test length in seconds
+    #[arg(long)]
+    duration: u64,
+
+    /// CPU workload: float, integer, simd, memory or cache
+    #[arg(long, value_parser = parse_workload)]
+    workload: Option<CpuWorkload>,
+
+    /// CPU worker threads (all logical CPUs by default)
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// CPU duty cycle in percent
+    #[arg(long = "load", value_name = "PERCENT")]
+    load_percent: Option<u32>,
+
+    /// GPU intensity: low, medium, high or extreme
+    #[arg(long, value_parser = parse_enum::<GpuIntensity>)]
+    gpu_intensity: Option<GpuIntensity>,
+
+    /// Share of total memory to allocate, 0 to 1
+    #[arg(long)]
+    memory_fraction: Option<f64>,
+
+    /// Abort when the CPU stays above this temperature (°C)
+    #[arg(long)]
+    cpu_temp_limit: Option<f64>,
+
+    /// Abort when the GPU stays above this temperature (°C)
+    #[arg(long)]
+    gpu_temp_limit: Option<f64>,
+
+    /// Seconds between progress lines
+    #[arg(long, default_value_t = 5.0, value_parser = parse_interval)]
+    interval: f64,
+}
+
+#[derive(Args)]
+struct RecordArgs {
+    /// Output file, or a directory to create a timestamped file in
+    path: PathBuf,
+
+    /// csv or jsonl; follows the file extension by default
+    #[arg(long, value_parser = parse_enum::<RecordingFormat>)]
+    format: Option<RecordingFormat>,
+
+    /// Stop after this many seconds instead of at Ctrl-C
+    #[arg(long)]
+    duration: Option<u64>,
+
+    /// Seconds between samples
+    #[arg(long, default_value_t = 1.0, value_parser = parse_interval)]
+    interval: f64,
+}
+
+#[derive(Args)]
+struct PlanRunArgs {
+    /// Test plan file (TOML, or JSON)
+    path: PathBuf,
+
+    /// Write the report as JSON
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+
+    /// Write the report as JUnit XML
+    #[arg(long, value_name = "PATH")]
+    junit: Option<PathBuf>,
+
+    /// Write a standalone HTML report with charts
+    #[arg(long, value_name = "PATH")]
+    html: Option<PathBuf>,
+}
+
+// How a command ended, when it ran at all
+enum Outcome {
+    Passed,
+    Failed,
+    Interrupted,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    // The first Ctrl-C winds the command down cleanly, a second one exits straight away
+    let _ = ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(EXIT_INTERRUPTED as i32);
+        }
+    });
+
+    let result = if cli.simulate.is_some() && !sensors::is_simulated() {
+        Err("The simulated machine could not be set up".to_string())
+    } else if let Err(e) = start_background(&cli) {
+        Err(e)
+    } else {
+        match cli.command {
+            Command::Sensors => sensors_command(cli.json),
+            Command::Watch(args) => watch_command(&args, cli.json),
+            Command::Stress(args) => stress_command(&args, cli.json),
+            Command::Record(args) => record_command(&args, cli.json),
+            Command::Plan { command: PlanCommand::Run(args) } => plan_run_command(&args, cli.json),
+        }
+    };
+
+    match result {
+        Ok(Outcome::Passed) => ExitCode::SUCCESS,
+        Ok(Outcome::Failed) => ExitCode::from(EXIT_FAILED),
+        Ok(Outcome::Interrupted) => ExitCode::from(EXIT_INTERRUPTED),
+        Err(e) => {
+            eprintln!("tempdetect-cli: {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+// Start the sampler for the commands that read through it (watch, record, and the watchdogs and
+// progress of stress tests and plans) or when metrics are served, then the metrics endpoint
+fn start_background(cli: &Cli) -> Result<(), String> {
+    let bind = cli.metrics.clone().or_else(|| metrics::requested(&[]));
+    let interval = match &cli.command {
+        Command::Watch(args) => Some(Duration::from_secs_f64(args.interval)),
+        Command::Record(args) => Some(Duration::from_secs_f64(args.interval)),
+        Command::Stress(_) | Command::Plan { .. } => Some(engine::SAMPLE_INTERVAL),
+        _ if bind.is_some() => Some(engine::SAMPLE_INTERVAL),
+        _ => None,
+    };
+    if let Some(interval) = interval {
+        engine::start_sampling(interval);
+    }
+
+    if let Some(bind) = bind {
+        let status = engine::start_metrics(&bind)?;
+        if !cli.json {
+            eprintln!("Serving metrics at http://{}/metrics", status.address);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SensorsOutput {
+    machine: MachineInfo,
+    snapshot: Snapshot,
+}
+
+fn sensors_command(json: bool) -> Result<Outcome, String> {
+    let machine = sensors::machine_info();
+    let snapshot = sensors::read_snapshot(&mut primed_system());
+
+    if json {
+        print_json(&SensorsOutput { machine, snapshot });
+        return Ok(Outcome::Passed);
+    }
+
+    print_machine(&machine);
+    println!();
+    let mut ids: Vec<&String> = snapshot.values.keys().collect();
+    ids.sort_by_key(|id| channel_order(id));
+    let width = ids.iter().map(|id| id.len()).max().unwrap_or(0);
+    for id in ids {
+        let (_, unit) = sampling::channel_kind(id);
+        let line = format!("{:<width$}  {:>9} {}", id, format_value(id, snapshot.values[id]), unit, width = width);
+        println!("{}", line.trim_end());
+    }
+    Ok(Outcome::Passed)
+}
+
+fn watch_command(args: &WatchArgs, json: bool) -> Result<Outcome, String> {
+    let (tx, rx) = mpsc::channel();
+    SAMPLER.subscribe(Arc::new(move |snapshot: &Snapshot| {
+        let _ = tx.send(snapshot.clone());
+    }));
+
+    let mut columns: Option<Vec<(String, String)>> = None;  // (header, channel id)
+    let mut shown = 0;
+    while !INTERRUPTED.load(Ordering::SeqCst) && args.count.is_none_or(|count| shown < count) {
+        let Ok(snapshot) = rx.recv_timeout(TICK) else {
+            continue;
+        };
+        shown += 1;
+        if json {
+            print_json(&snapshot);
+            continue;
+        }
+
+        // Columns follow the first reading, so fans and stress channels that exist show up
+        let columns = columns.get_or_insert_with(|| {
+            let columns = watch_columns(&args.sensors, &snapshot);
+            let headers: Vec<String> = columns.iter().map(|(header, _)| format!("{:>9}", header)).collect();
+            println!("{:<8}{}", "TIME", headers.join(" "));
+            columns
+        });
+        let values: Vec<String> = columns
+            .iter()
+            .map(|(_, id)| {
+                let value = snapshot.values.get(id).map_or("-".to_string(), |&value| format_value(id, value));
+                format!("{:>9}", value)
+            })
+            .collect();
+        println!("{:<8}{}", clock(snapshot.timestamp), values.join(" "));
+    }
+    // Ctrl-C is the normal way to end a watch
+    Ok(Outcome::Passed)
+}
+
+fn watch_columns(selected: &[String], snapshot: &Snapshot) -> Vec<(String, String)> {
+    if !selected.is_empty() {
+        return selected.iter().map(|id| (id.clone(), id.clone())).collect();
+    }
+
+    let mut columns: Vec<(String, String)> = [
+        ("CPU °C", "cpu_temp"),
+        ("GPU °C", "gpu_temp"),
+        ("CPU %", "cpu_usage"),
+        ("GPU %", "gpu_usage"),
+        ("CPU MHz", "cpu_freq"),
+        ("Limit %", "cpu_speed_limit"),
+    ]
+    .into_iter()
+    .filter(|(_, id)| snapshot.values.contains_key(*id))
+    .map(|(header, id)| (header.to_string(), id.to_string()))
+    .collect();
+    for id in snapshot.values.keys() {
+        if let Some(fan) = id.strip_prefix("fan_") {
+            columns.push((format!("Fan {}", fan), id.clone()));
+        }
+    }
+    for name in ["cpu", "gpu", "memory"] {
+        columns.push((format!("{} load", name), format!("{}_stress_running", name)));
+    }
+    columns
+}
+
+// Progress line while stress tests run
+#[derive(Serialize)]
+struct StressProgress {
+    elapsed_secs: f64,
+    cpu_temp: Option<f64>,
+    gpu_temp: Option<f64>,
+    cpu_freq_mhz: Option<f64>,
+    cpu_speed_limit: Option<f64>,
+    ops_per_sec: f64,
+}
+
+#[derive(Serialize)]
+struct StressSummary {
+    cpu: Option<StressStatus>,
+    gpu: Option<StressStatus>,
+    memory: Option<StressStatus>,
+    cpu_temp: SeriesStats,
+    gpu_temp: SeriesStats,
+    interrupted: bool,
+}
+
+fn stress_command(args: &StressArgs, json: bool) -> Result<Outcome, String> {
+    let duration = Some(Duration::from_secs(args.duration));
+    let defaults = SafetyLimits::default();
+    let limits = SafetyLimits {
+        cpu_temp_limit: args.cpu_temp_limit.or(defaults.cpu_temp_limit),
+        gpu_temp_limit: args.gpu_temp_limit.or(defaults.gpu_temp_limit),
+        ..defaults
+    };
+
+    // Out-of-range values are checked before anything starts, and reported like clap's own errors
+    let cpu_options = args
+        .cpu
+        .then(|| CpuStressOptions::new(args.workload.unwrap_or(CpuWorkload::Float), args.threads, args.load_percent, None))
+        .transpose()
+        .unwrap_or_else(|e| invalid_argument(e));
+    if args.memory {
+        MemoryStressOptions::validate(args.memory_fraction, None).unwrap_or_else(|e| invalid_argument(e));
+    }
+
+    // Stop whatever already started when a later workload fails to start
+    let mut running: Vec<(&str, &'static StressSlot)> = Vec::new();
+    let started = (|| {
+        if let Some(options) = &cpu_options {
+            engine::start_cpu_stress(options, duration, Some(limits.clone()), None, None, None)?;
+            running.push(("cpu", &STRESS_TEST));
+        }
+        if args.gpu {
+            let intensity = args.gpu_intensity.unwrap_or_default();
+            engine::start_gpu_stress(&GpuSelector::default(), intensity, duration, Some(limits.clone()), None)?;
+            running.push(("gpu", &GPU_STRESS_TEST));
+        }
+        if args.memory {
+            // Not enough free memory is a failure to run, not a bad argument
+            let options = MemoryStressOptions::new(args.memory_fraction, None)?;
+            engine::start_memory_stress(&options, duration, Some(limits.clone()), None)?;
+            running.push(("memory", &MEMORY_STRESS_TEST));
+        }
+        Ok::<(), String>(())
+    })();
+    if let Err(e) = started {
+        for (_, slot) in &running {
+            slot.stop();
+        }
+        return Err(e);
+    }
+
+    if !json {
+        let names: Vec<&str> = running.iter().map(|(name, _)| *name).collect();
+        eprintln!("Stressing {} for {} s; Ctrl-C to stop", names.join(" + "), args.duration);
+    }
+
+    let start = Instant::now();
+    let mut cpu_temp = SeriesStats::default();
+    let mut gpu_temp = SeriesStats::default();
+    let mut last_progress: Option<Instant> = None;
+    // A slot reads as idle while it joins its workers, so wait for the final state
+    let finishing = |slot: &StressSlot| matches!(slot.status().state, StressState::Running | StressState::Idle);
+    while running.iter().any(|(_, slot)| finishing(slot)) {
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            for (_, slot) in &running {
+                slot.stop();
+            }
+            while running.iter().any(|(_, slot)| finishing(slot)) {
+                thread::sleep(TICK);
+            }
+            break;
+        }
+
+        let sample = engine::read_sample();
+        cpu_temp.add_opt(sample.cpu_temp);
+        gpu_temp.add_opt(sample.gpu_temp);
+        if last_progress.is_none_or(|last| last.elapsed().as_secs_f64() >= args.interval) {
+            last_progress = Some(Instant::now());
+            let progress = StressProgress {
+                elapsed_secs: start.elapsed().as_secs_f64(),
+                cpu_temp: sample.cpu_temp,
+                gpu_temp: sample.gpu_temp,
+                cpu_freq_mhz: sample.cpu_freq_mhz,
+                cpu_speed_limit: sample.cpu_speed_limit,
+                ops_per_sec: running.iter().map(|(_, slot)| slot.status().ops_per_sec).fold(0.0, |a, b| a + b),
+            };
+            if json {
+                print_json(&progress);
+            } else {
+                println!(
+                    "{:>6}  CPU {:>7}  GPU {:>7}  {:>6} MHz  {} ops/s",
+                    duration_text(progress.elapsed_secs),
+                    temp_text(progress.cpu_temp),
+                    temp_text(progress.gpu_temp),
+                    progress.cpu_freq_mhz.map_or("-".to_string(), |mhz| format!("{:.0}", mhz)),
+                    si(progress.ops_per_sec)
+                );
+            }
+        }
+        sleep_interruptible(Duration::from_secs(1));
+    }
+
+    let status = |name: &str| running.iter().find(|(n, _)| *n == name).map(|(_, slot)| slot.status());
+    let summary = StressSummary {
+        cpu: status("cpu"),
+        gpu: status("gpu"),
+        memory: status("memory"),
+        cpu_temp,
+        gpu_temp,
+        interrupted: INTERRUPTED.load(Ordering::SeqCst),
+    };
+    let statuses: Vec<(&str, &StressStatus)> = [("CPU", &summary.cpu), ("GPU", &summary.gpu), ("Memory", &summary.memory)]
+        .into_iter()
+        .filter_map(|(name, status)| Some((name, status.as_ref()?)))
+        .collect();
+    let failed = statuses
+        .iter()
+        .any(|(_, status)| matches!(status.state, StressState::Aborted | StressState::Failed) || status.mismatches > 0);
+
+    if json {
+        print_json(&summary);
+    } else {
+        println!();
+        for (name, status) in &statuses {
+            let mut line = format!(
+                "{:<7} {:<10} {:>8}  {} ops/s  {} verified batches, {} mismatches",
+                name,
+                label(&status.state),
+                duration_text(status.elapsed_secs),
+                si(status.ops_per_sec),
+                status.verified_batches,
+                status.mismatches
+            );
+            if let Some(reason) = status.abort_reason.as_ref().or(status.error.as_ref()) {
+                line.push_str(&format!("  ({})", reason));
+            }
+            println!("{}", line);
+        }
+        println!("Peak    CPU {}  GPU {}", temp_text(summary.cpu_temp.max), temp_text(summary.gpu_temp.max));
+    }
+
+    Ok(if summary.interrupted {
+        Outcome::Interrupted
+    } else if failed {
+        Outcome::Failed
+    } else {
+        Outcome::Passed
+    })
+}
+
+fn record_command(args: &RecordArgs, json: bool) -> Result<Outcome, String> {
+    let status = engine::start_recording(&args.path, args.format)?;
+    if !json {
+        eprintln!("Recording to {}; Ctrl-C to stop", status.path);
+    }
+
+    let start = Instant::now();
+    while !INTERRUPTED.load(Ordering::SeqCst)
+        && args.duration.is_none_or(|secs| start.elapsed() < Duration::from_secs(secs))
+        && RECORDER.status().is_some_and(|status| status.active)
+    {
+        thread::sleep(TICK);
+    }
+
+    let status = RECORDER.stop().ok_or("The recording did not start")?;
+    if json {
+        print_json(&status);
+    } else {
+        println!("Recorded {} samples to {}", status.records, status.path);
+    }
+    match status.error {
+        Some(error) => Err(error),
+        None => Ok(Outcome::Passed),
+    }
+}
+
+fn plan_run_command(args: &PlanRunArgs, json: bool) -> Result<Outcome, String> {
+    let plan = TestPlan::load(&args.path)?;
+    let phases = plan.phases.len();
+
+    let (tx, rx) = mpsc::channel();
+    engine::run_test_plan(plan, Arc::new(move |event: PlanEvent| {
+        let _ = tx.send(event);
+    }))?;
+
+    let start = Instant::now();
+    let mut stopping = false;
+    loop {
+        if INTERRUPTED.load(Ordering::SeqCst) && !stopping {
+            stopping = true;
+            if !json {
+                eprintln!("Stopping the test plan...");
+            }
+            TEST_PLAN.stop();
+        }
+        let event = match rx.recv_timeout(TICK) {
+            Ok(event) => event,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        if json {
+            print_json(&event);
+        }
+        match event {
+            PlanEvent::PhaseStarted { index, name, .. } if !json => {
+                println!("{:>6}  [{}/{}] {}", duration_text(start.elapsed().as_secs_f64()), index + 1, phases, name);
+            }
+            PlanEvent::PhaseFinished { result, .. } if !json => {
+                let mut line = format!(
+                    "{:>6}        {} after {}, peak CPU {}, GPU {}",
+                    duration_text(start.elapsed().as_secs_f64()),
+                    label(&result.end),
+                    duration_text(result.elapsed_secs),
+                    temp_text(result.cpu_temp.max),
+                    temp_text(result.gpu_temp.max)
+                );
+                if !result.throttle_events.is_empty() {
+                    line.push_str(&format!(", {} throttling events", result.throttle_events.len()));
+                }
+                if let Some(message) = &result.message {
+                    line.push_str(&format!(" ({})", message));
+                }
+                println!("{}", line);
+            }
+            PlanEvent::PlanFinished { .. } => break,
+            _ => {}
+        }
+    }
+
+    let report = engine::test_plan_report(args.report.as_deref(), args.junit.as_deref(), args.html.as_deref())?;
+
+    if json {
+        print_json(&report);
+    } else {
+        println!();
+        for assertion in &report.assertions {
+            let result = match (assertion.skipped, assertion.passed) {
+                (true, _) => "SKIP",
+                (false, true) => "PASS",
+                (false, false) => "FAIL",
+            };
+            println!("{}  {:<16} {:<16} {}", result, assertion.phase, assertion.name, assertion.message);
+        }
+        let passed = report.assertions.iter().filter(|a| a.passed && !a.skipped).count();
+        let skipped = report.assertions.iter().filter(|a| a.skipped).count();
+        let verdict = if report.passed { "PASSED" } else { "FAILED" };
+        let skipped = if skipped > 0 { format!(", {} skipped", skipped) } else { String::new() };
+        println!("\n{} {}: {} of {} checks passed{}", report.plan, verdict, passed, report.assertions.len(), skipped);
+    }
+
+    Ok(if stopping {
+        Outcome::Interrupted
+    } else if report.passed {
+        Outcome::Passed
+    } else {
+        Outcome::Failed
+    })
+}
+
+// CPU usage is measured between two refreshes, so take the first one before reading
+fn primed_system() -> System {
+    let mut sys = System::new();
+    sys.refresh_cpu();
+    thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+    sys
+}
+
+fn sleep_interruptible(duration: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < duration && !INTERRUPTED.load(Ordering::SeqCst) {
+        thread::sleep(TICK.min(duration.saturating_sub(start.elapsed())));
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("tempdetect-cli: {}", e),
+    }
+}
+
+fn print_machine(machine: &MachineInfo) {
+    let text = |parts: &[&Option<String>]| {
+        let known: Vec<&str> = parts.iter().filter_map(|part| part.as_deref()).collect();
+        if known.is_empty() {
+            "-".to_string()
+        } else {
+            known.join(" ")
+        }
+    };
+    println!("Host    {}", text(&[&machine.hostname]));
+    println!("OS      {}", text(&[&machine.os]));
+    println!(
+        "CPU     {} ({} cores, {} threads)",
+        text(&[&machine.cpu_vendor, &machine.cpu_model]),
+        machine.cpu_cores.map_or("?".to_string(), |n| n.to_string()),
+        machine.cpu_threads.map_or("?".to_string(), |n| n.to_string())
+    );
+    println!("GPU     {}", text(&[&machine.gpu_vendor, &machine.gpu_model]));
+    if let Some(bytes) = machine.memory_bytes {
+        println!("Memory  {:.1} GiB", bytes as f64 / (1u64 << 30) as f64);
+    }
+}
+
+// Sort channels by kind, then by name with numeric suffixes in numeric order
+fn channel_order(id: &str) -> (usize, String, usize) {
+    const KINDS: [&str; 8] = ["temperature", "fan", "usage", "frequency", "throttle", "stress_state", "throughput", "other"];
+    let (kind, _) = sampling::channel_kind(id);
+    let rank = KINDS.iter().position(|&k| k == kind).unwrap_or(KINDS.len());
+    match id.rsplit_once('_').and_then(|(name, index)| Some((name, index.parse::<usize>().ok()?))) {
+        Some((name, index)) => (rank, name.to_string(), index + 1),
+        None => (rank, id.to_string(), 0),
+    }
+}
+
+fn format_value(id: &str, value: f64) -> String {
+    match sampling::channel_kind(id).0 {
+        "stress_state" => if value > 0.0 { "running" } else { "idle" }.to_string(),
+        "temperature" | "usage" => format!("{:.1}", value),
+        _ => format!("{:.0}", value),
+    }
+}
+
+fn temp_text(celsius: Option<f64>) -> String {
+    celsius.map_or("-".to_string(), |celsius| format!("{:.1} °C", celsius))
+}
+
+// "42s", "3m05s" or "1h02m"
+fn duration_text(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs / 60 % 60),
+    }
+}
+
+// UTC "HH:MM:SS" of a Unix timestamp in milliseconds
+fn clock(millis: u64) -> String {
+    recording::format_utc(millis)[11..19].to_string()
+}
+
+fn si(value: f64) -> String {
+    match value {
+        v if v >= 1e9 => format!("{:.2}G", v / 1e9),
+        v if v >= 1e6 => format!("{:.2}M", v / 1e6),
+        v if v >= 1e3 => format!("{:.1}k", v / 1e3),
+        v => format!("{:.0}", v),
+    }
+}
+
+// Enum variants as they appear in JSON, e.g. "duration_elapsed" as "duration elapsed"
+fn label<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => text.replace('_', " "),
+        _ => String::new(),
+    }
+}
+
+// Exit with code 2 for an argument value clap can't check on its own
+fn invalid_argument(message: String) -> ! {
+    Cli::command().error(ErrorKind::ValueValidation, message).exit()
+}
+
+fn parse_interval(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(secs) if secs >= 0.1 && secs.is_finite() => Ok(secs),
+        _ => Err("expected a number of seconds, at least 0.1".to_string()),
+    }
+}
+
+fn parse_workload(text: &str) -> Result<CpuWorkload, String> {
+    CpuWorkload::parse(Some(text))
+}
+
+// Lower-case enum names the way plan files spell them
+fn parse_enum<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(text.to_string())).map_err(|e| e.to_string())
+}
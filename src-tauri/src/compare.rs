@@ -0,0 +1,469 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::plan::{PhaseWorkload, PlanResult};
+use crate::recording::{self, MachineInfo, RecordingFormat};
+use crate::sampling::{self, SensorSample, SeriesStats, Snapshot};
+use crate::steady::{self, SteadyStateConfig};
+
+// How much worse than the baseline a phase may get before it counts as a regression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tolerances {
+    pub peak_temp_celsius: f64,
+    pub steady_temp_celsius: f64,
+    pub time_to_steady_percent: f64,  // Either way: a big change means the thermal behaviour changed
+    pub throttle_secs: f64,
+    pub avg_freq_percent: f64,
+}
+
+impl Default for Tolerances {
+    fn default() -> Self {
+        Tolerances {
+            peak_temp_celsius: 2.0,
+            steady_temp_celsius: 2.0,
+            time_to_steady_percent: 25.0,
+            throttle_secs: 5.0,
+            avg_freq_percent: 3.0,
+        }
+    }
+}
+
+// What one test phase looked like, enough to compare against later runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseMetrics {
+    pub name: String,
+    pub workload: PhaseWorkload,
+    pub duration_secs: f64,
+    pub peak_cpu_temp: Option<f64>,
+    pub peak_gpu_temp: Option<f64>,
+    pub steady_cpu_temp: Option<f64>,
+    pub steady_gpu_temp: Option<f64>,
+    pub cpu_time_to_steady_secs: Option<f64>,
+    pub gpu_time_to_steady_secs: Option<f64>,
+    pub throttle_secs: f64,
+    pub avg_cpu_freq_mhz: Option<f64>,
+}
+
+// Per-phase metrics of one session. Saved as JSON, this is a baseline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub name: String,
+    pub started_at: u64,  // Unix timestamp in milliseconds
+    pub machine: Option<MachineInfo>,
+    pub phases: Vec<PhaseMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub baseline: Option<f64>,
+    pub candidate: Option<f64>,
+    pub delta: Option<f64>,  // candidate - baseline
+    pub tolerance: f64,
+    pub regression: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseComparison {
+    pub phase: String,
+    pub workload: PhaseWorkload,
+    pub metrics: Vec<MetricDelta>,
+    pub regression: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub baseline: String,
+    pub candidate: String,
+    pub tolerances: Tolerances,
+    pub phases: Vec<PhaseComparison>,
+    pub unmatched_baseline: Vec<String>,   // Phases only the baseline ran
+    pub unmatched_candidate: Vec<String>,  // Phases only the candidate ran
+    pub regressions: usize,
+    pub inconclusive: bool,  // No phases matched, or some baseline phases are missing from the candidate
+    pub passed: bool,        // No regressions and not inconclusive
+}
+
+pub fn summarize_phase(name: &str, workload: PhaseWorkload, samples: &[SensorSample], config: &SteadyStateConfig) -> PhaseMetrics {
+    let mut cpu_temp = SeriesStats::default();
+    let mut gpu_temp = SeriesStats::default();
+    let mut cpu_freq = SeriesStats::default();
+    for sample in samples {
+        cpu_temp.add_opt(sample.cpu_temp);
+        gpu_temp.add_opt(sample.gpu_temp);
+        cpu_freq.add_opt(sample.cpu_freq_mhz);
+    }
+    let cpu_steady = steady::analyze(&steady::series(samples, "cpu_temp"), config);
+    let gpu_steady = steady::analyze(&steady::series(samples, "gpu_temp"), config);
+
+    let duration_secs = match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) => last.timestamp.saturating_sub(first.timestamp) as f64 / 1000.0,
+        _ => 0.0,
+    };
+
+    PhaseMetrics {
+        name: name.to_string(),
+        workload,
+        duration_secs,
+        peak_cpu_temp: cpu_temp.max,
+        peak_gpu_temp: gpu_temp.max,
+        steady_cpu_temp: cpu_steady.map(|s| s.mean),
+        steady_gpu_temp: gpu_steady.map(|s| s.mean),
+        cpu_time_to_steady_secs: cpu_steady.map(|s| s.time_to_steady_secs),
+        gpu_time_to_steady_secs: gpu_steady.map(|s| s.time_to_steady_secs),
+        throttle_secs: sampling::throttle_events(samples).iter().fold(0.0, |total, e| total + e.duration_secs()),
+        avg_cpu_freq_mhz: cpu_freq.avg,
+    }
+}
+
+pub fn summarize_plan(result: &PlanResult, config: &SteadyStateConfig) -> SessionSummary {
+    SessionSummary {
+        name: result.name.clone(),
+        started_at: result.started_at,
+        machine: None,
+        phases: result
+            .phases
+            .iter()
+            .map(|phase| summarize_phase(&phase.name, phase.workload, &phase.samples, config))
+            .collect(),
+    }
+}
+
+// A recording has no plan, so phases are cut wherever the stress state channels change
+pub fn summarize_recording(name: &str, machine: MachineInfo, snapshots: &[Snapshot], config: &SteadyStateConfig) -> SessionSummary {
+    let mut phases = Vec::new();
+    let mut current: Option<(PhaseWorkload, Vec<SensorSample>)> = None;
+
+    for snapshot in snapshots {
+        let workload = recorded_workload(snapshot);
+        match current.as_mut() {
+            Some((phase, samples)) if *phase == workload => samples.push(snapshot.into()),
+            _ => {
+                if let Some((phase, samples)) = current.take() {
+                    phases.push(summarize_phase(workload_name(phase), phase, &samples, config));
+                }
+                current = Some((workload, vec![snapshot.into()]));
+            }
+        }
+    }
+    if let Some((phase, samples)) = current {
+        phases.push(summarize_phase(workload_name(phase), phase, &samples, config));
+    }
+
+    SessionSummary {
+        name: name.to_string(),
+        started_at: snapshots.first().map_or(0, |s| s.timestamp),
+        machine: Some(machine),
+        phases,
+    }
+}
+
+fn recorded_workload(snapshot: &Snapshot) -> PhaseWorkload {
+    let running = |name: &str| snapshot.values.get(&format!("{}_stress_running", name)).is_some_and(|&v| v > 0.0);
+    match (running("cpu"), running("gpu"), running("memory")) {
+        (true, true, _) => PhaseWorkload::Combined,
+        (true, false, _) => PhaseWorkload::Cpu,
+        (false, true, _) => PhaseWorkload::Gpu,
+        (false, false, true) => PhaseWorkload::Memory,
+        (false, false, false) => PhaseWorkload::Idle,
+    }
+}
+
+fn workload_name(workload: PhaseWorkload) -> &'static str {
+    match workload {
+        PhaseWorkload::Idle => "idle",
+        PhaseWorkload::Cpu => "cpu",
+        PhaseWorkload::Gpu => "gpu",
+        PhaseWorkload::Combined => "combined",
+        PhaseWorkload::Memory => "memory",
+    }
+}
+
+// Just the parts of a saved plan result or report that the summary needs
+#[derive(Deserialize)]
+struct SavedPhase {
+    name: String,
+    workload: PhaseWorkload,
+    samples: Vec<SensorSample>,
+}
+
+#[derive(Deserialize)]
+struct SavedPlan {
+    name: String,
+    started_at: u64,
+    phases: Vec<SavedPhase>,
+}
+
+#[derive(Deserialize)]
+struct SavedReport {
+    result: SavedPlan,
+}
+
+// Summarise a session from a CSV/JSONL recording, a saved test report or plan result, or a saved baseline
+pub fn load_session(path: &Path, config: &SteadyStateConfig) -> Result<SessionSummary, String> {
+    let name = path.file_stem().map_or_else(|| path.display().to_string(), |s| s.to_string_lossy().to_string());
+    if RecordingFormat::from_path(path).is_some() {
+        let recording = recording::read(path)?;
+        return Ok(summarize_recording(&name, recording.header.machine, &recording.snapshots, config));
+    }
+
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let plan = serde_json::from_str::<SavedReport>(&text)
+        .map(|report| report.result)
+        .or_else(|_| serde_json::from_str::<SavedPlan>(&text));
+    if let Ok(plan) = plan {
+        return Ok(SessionSummary {
+            name: plan.name,
+            started_at: plan.started_at,
+            machine: None,
+            phases: plan
+                .phases
+                .iter()
+                .map(|phase| summarize_phase(&phase.name, phase.workload, &phase.samples, config))
+                .collect(),
+        });
+    }
+    serde_json::from_str(&text).map_err(|e| format!("{} is not a recording, report or baseline: {}", path.display(), e))
+}
+
+pub fn save_baseline(summary: &SessionSummary, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(summary).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Which way is worse for a metric
+#[derive(Clone, Copy)]
+enum Worse {
+    Higher,
+    Lower,
+    Either,
+}
+
+// Pair phases by name and occurrence, so "cpu", "idle", "cpu" lines up with the same sequence
+pub fn compare(baseline: &SessionSummary, candidate: &SessionSummary, tolerances: &Tolerances) -> Comparison {
+    let baseline_phases = keyed(baseline);
+    let candidate_phases = keyed(candidate);
+    let label = |(name, n): &(String, usize)| if *n > 1 { format!("{} #{}", name, n) } else { name.clone() };
+
+    let mut phases = Vec::new();
+    let mut unmatched_baseline = Vec::new();
+    for (key, base) in &baseline_phases {
+        match candidate_phases.iter().find(|(k, _)| k == key) {
+            Some((_, cand)) => phases.push(compare_phase(label(key), base, cand, tolerances)),
+            None => unmatched_baseline.push(label(key)),
+        }
+    }
+    let unmatched_candidate = candidate_phases
+        .iter()
+        .filter(|(key, _)| !baseline_phases.iter().any(|(k, _)| k == key))
+        .map(|(key, _)| label(key))
+        .collect();
+
+    let regressions = phases.iter().flat_map(|p| &p.metrics).filter(|m| m.regression).count();
+    // A candidate that skipped baseline phases, or shares none of their names, proves nothing
+    let inconclusive = phases.is_empty() || !unmatched_baseline.is_empty();
+    Comparison {
+        baseline: baseline.name.clone(),
+        candidate: candidate.name.clone(),
+        tolerances: tolerances.clone(),
+        phases,
+        unmatched_baseline,
+        unmatched_candidate,
+        regressions,
+        inconclusive,
+        passed: regressions == 0 && !inconclusive,
+    }
+}
+
+// Each phase keyed by (name, nth phase with that name)
+fn keyed(summary: &SessionSummary) -> Vec<((String, usize), &PhaseMetrics)> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    summary
+        .phases
+        .iter()
+        .map(|phase| {
+            let count = seen.entry(phase.name.as_str()).or_default();
+            *count += 1;
+            ((phase.name.clone(), *count), phase)
+        })
+        .collect()
+}
+
+fn compare_phase(phase: String, base: &PhaseMetrics, cand: &PhaseMetrics, t: &Tolerances) -> PhaseComparison {
+    let percent = |value: Option<f64>, pct: f64| value.map_or(0.0, |v| v.abs() * pct / 100.0);
+    let metrics = vec![
+        delta("peak_cpu_temp", base.peak_cpu_temp, cand.peak_cpu_temp, t.peak_temp_celsius, Worse::Higher),
+        delta("peak_gpu_temp", base.peak_gpu_temp, cand.peak_gpu_temp, t.peak_temp_celsius, Worse::Higher),
+        delta("steady_cpu_temp", base.steady_cpu_temp, cand.steady_cpu_temp, t.steady_temp_celsius, Worse::Higher),
+        delta("steady_gpu_temp", base.steady_gpu_temp, cand.steady_gpu_temp, t.steady_temp_celsius, Worse::Higher),
+        delta(
+            "cpu_time_to_steady_secs",
+            base.cpu_time_to_steady_secs,
+            cand.cpu_time_to_steady_secs,
+            percent(base.cpu_time_to_steady_secs, t.time_to_steady_percent),
+            Worse::Either,
+        ),
+        delta(
+            "gpu_time_to_steady_secs",
+            base.gpu_time_to_steady_secs,
+            cand.gpu_time_to_steady_secs,
+            percent(base.gpu_time_to_steady_secs, t.time_to_steady_percent),
+            Worse::Either,
+        ),
+        delta("throttle_secs", Some(base.throttle_secs), Some(cand.throttle_secs), t.throttle_secs, Worse::Higher),
+        delta(
+            "avg_cpu_freq_mhz",
+            base.avg_cpu_freq_mhz,
+            cand.avg_cpu_freq_mhz,
+            percent(base.avg_cpu_freq_mhz, t.avg_freq_percent),
+            Worse::Lower,
+        ),
+    ];
+
+    PhaseComparison {
+        phase,
+        workload: base.workload,
+        regression: metrics.iter().any(|m| m.regression),
+        metrics,
+    }
+}
+
+// A value the baseline had but the candidate lost (a sensor that stopped reporting, a phase that
+// no longer settles) is a regression; one only the candidate has is just reported
+fn delta(metric: &str, baseline: Option<f64>, candidate: Option<f64>, tolerance: f64, worse: Worse) -> MetricDelta {
+    let delta = baseline.zip(candidate).map(|(b, c)| c - b);
+    let regression = match delta {
+        Some(d) => match worse {
+            Worse::Higher => d > tolerance,
+            Worse::Lower => -d > tolerance,
+            Worse::Either => d.abs() > tolerance,
+        },
+        None => baseline.is_some() && candidate.is_none(),
+    };
+    MetricDelta {
+        metric: metric.to_string(),
+        baseline,
+        candidate,
+        delta,
+        tolerance,
+        regression,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(name: &str, peak_cpu_temp: f64) -> PhaseMetrics {
+        PhaseMetrics {
+            name: name.to_string(),
+            workload: PhaseWorkload::Cpu,
+            duration_secs: 600.0,
+            peak_cpu_temp: Some(peak_cpu_temp),
+            peak_gpu_temp: None,
+            steady_cpu_temp: Some(peak_cpu_temp - 2.0),
+            steady_gpu_temp: None,
+            cpu_time_to_steady_secs: Some(120.0),
+            gpu_time_to_steady_secs: None,
+            throttle_secs: 0.0,
+            avg_cpu_freq_mhz: Some(3000.0),
+        }
+    }
+
+    fn session(name: &str, phases: Vec<PhaseMetrics>) -> SessionSummary {
+        SessionSummary { name: name.to_string(), started_at: 0, machine: None, phases }
+    }
+
+    fn metric<'a>(comparison: &'a Comparison, phase: &str, name: &str) -> &'a MetricDelta {
+        let phase = comparison.phases.iter().find(|p| p.phase == phase).unwrap();
+        phase.metrics.iter().find(|m| m.metric == name).unwrap()
+    }
+
+    #[test]
+    fn same_session_passes() {
+        let baseline = session("before", vec![metrics("idle", 45.0), metrics("cpu", 90.0)]);
+        let comparison = compare(&baseline, &baseline, &Tolerances::default());
+        assert!(comparison.passed);
+        assert!(!comparison.inconclusive);
+        assert_eq!(comparison.regressions, 0);
+        assert_eq!(comparison.phases.len(), 2);
+    }
+
+    #[test]
+    fn hotter_phase_is_a_regression() {
+        let baseline = session("before", vec![metrics("cpu", 90.0)]);
+        let within = session("after", vec![metrics("cpu", 91.5)]);
+        assert!(compare(&baseline, &within, &Tolerances::default()).passed);
+
+        let hotter = session("after", vec![metrics("cpu", 93.0)]);
+        let comparison = compare(&baseline, &hotter, &Tolerances::default());
+        assert!(!comparison.passed);
+        assert_eq!(comparison.regressions, 2);  // Peak and steady temperature
+        assert_eq!(metric(&comparison, "cpu", "peak_cpu_temp").delta, Some(3.0));
+        assert!(metric(&comparison, "cpu", "peak_cpu_temp").regression);
+
+        // Cooler or faster is never a regression
+        let cooler = session("after", vec![PhaseMetrics { avg_cpu_freq_mhz: Some(3500.0), ..metrics("cpu", 80.0) }]);
+        assert!(compare(&baseline, &cooler, &Tolerances::default()).passed);
+    }
+
+    #[test]
+    fn lost_metric_is_a_regression() {
+        let baseline = session("before", vec![metrics("cpu", 90.0)]);
+        let unsettled = session("after", vec![PhaseMetrics { cpu_time_to_steady_secs: None, ..metrics("cpu", 90.0) }]);
+        let comparison = compare(&baseline, &unsettled, &Tolerances::default());
+        assert!(metric(&comparison, "cpu", "cpu_time_to_steady_secs").regression);
+        assert!(!comparison.passed);
+
+        // A value only the candidate has is reported, not flagged
+        let gained = session("after", vec![PhaseMetrics { peak_gpu_temp: Some(60.0), ..metrics("cpu", 90.0) }]);
+        let comparison = compare(&baseline, &gained, &Tolerances::default());
+        assert!(!metric(&comparison, "cpu", "peak_gpu_temp").regression);
+        assert!(comparison.passed);
+    }
+
+    #[test]
+    fn unmatched_phases_are_inconclusive() {
+        let plan = session("plan", vec![metrics("warm up", 60.0), metrics("full load", 90.0)]);
+        let recording = session("recording", vec![metrics("idle", 45.0), metrics("cpu", 90.0)]);
+        let comparison = compare(&plan, &recording, &Tolerances::default());
+        assert!(comparison.phases.is_empty());
+        assert!(comparison.inconclusive);
+        assert!(!comparison.passed);
+        assert_eq!(comparison.unmatched_baseline, vec!["warm up", "full load"]);
+        assert_eq!(comparison.unmatched_candidate, vec!["idle", "cpu"]);
+
+        // Extra candidate phases are fine, missing baseline phases are not
+        let longer = session("after", vec![metrics("warm up", 60.0), metrics("full load", 90.0), metrics("idle", 45.0)]);
+        assert!(compare(&plan, &longer, &Tolerances::default()).passed);
+        let shorter = session("after", vec![metrics("warm up", 60.0)]);
+        assert!(compare(&plan, &shorter, &Tolerances::default()).inconclusive);
+    }
+
+    #[test]
+    fn repeated_phases_pair_by_occurrence() {
+        let baseline = session("before", vec![metrics("cpu", 90.0), metrics("idle", 45.0), metrics("cpu", 92.0)]);
+        let candidate = session("after", vec![metrics("cpu", 90.0), metrics("idle", 45.0), metrics("cpu", 96.0)]);
+        let comparison = compare(&baseline, &candidate, &Tolerances::default());
+        assert!(!metric(&comparison, "cpu", "peak_cpu_temp").regression);
+        assert!(metric(&comparison, "cpu #2", "peak_cpu_temp").regression);
+    }
+
+    #[test]
+    fn recording_phases_follow_the_stress_state() {
+        let snapshots: Vec<Snapshot> = [(0.0, 45.0), (0.0, 46.0), (1.0, 70.0), (1.0, 80.0), (0.0, 60.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, &(running, temp))| Snapshot {
+                timestamp: i as u64 * 1000,
+                values: [("cpu_stress_running".to_string(), running), ("cpu_temp".to_string(), temp)].into(),
+            })
+            .collect();
+        let summary = summarize_recording("rec", MachineInfo::default(), &snapshots, &SteadyStateConfig::default());
+        let phases: Vec<(&str, Option<f64>)> = summary.phases.iter().map(|p| (p.name.as_str(), p.peak_cpu_temp)).collect();
+        assert_eq!(phases, vec![("idle", Some(46.0)), ("cpu", Some(80.0)), ("idle", Some(60.0))]);
+    }
+}
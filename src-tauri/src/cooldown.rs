@@ -0,0 +1,386 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::sampling::{self, SensorReader, SensorSample, SeriesStats};
+use crate::steady;
+use crate::stress::StressSlot;
+
+// How often the cool-down is sampled
+const COOLDOWN_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// How often to check whether the stress test has ended yet
+const COOLDOWN_WAIT_TICK: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CooldownOptions {
+    pub within_celsius: f64,     // Recovered once within this many °C of the idle baseline
+    pub timeout_secs: u64,       // Give up after this long
+    pub sensor: Option<String>,  // Channel to watch; defaults to the stressed component's temperature
+}
+
+impl Default for CooldownOptions {
+    fn default() -> Self {
+        CooldownOptions {
+            within_celsius: 2.0,
+            timeout_secs: 600,
+            sensor: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CooldownState {
+    WaitingForStop,  // The stress test is still running
+    Running,
+    Recovered,
+    TimedOut,
+    Cancelled,
+}
+
+// How one fan came back down after the load
+#[derive(Debug, Clone, Serialize)]
+pub struct FanRecovery {
+    pub fan: usize,
+    pub baseline_rpm: Option<f64>,
+    pub start_rpm: Option<f64>,  // When the stress test ended
+    pub end_rpm: Option<f64>,
+    pub rpm: SeriesStats,
+    pub settle_secs: Option<f64>,  // Time until back within 10% (at least 100 RPM) of the baseline
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CooldownResult {
+    pub state: CooldownState,
+    pub sensor: String,
+    pub baseline: f64,            // Idle reading before the test started
+    pub start_temp: Option<f64>,  // Reading when the test ended
+    pub current_temp: Option<f64>,
+    pub within_celsius: f64,
+    pub started_at: Option<u64>,  // Unix timestamp in milliseconds of the test ending
+    pub elapsed_secs: f64,
+    pub recovery_secs: Option<f64>,
+    pub decay_constant_secs: Option<f64>,  // Fitted exponential decay towards the baseline
+    pub fans: Vec<FanRecovery>,
+    pub samples: Vec<SensorSample>,
+}
+
+#[derive(Default)]
+struct MonitorInner {
+    cancel: Option<Arc<AtomicBool>>,
+    thread: Option<JoinHandle<()>>,
+    result: Option<CooldownResult>,
+}
+
+// Watches one cool-down at a time, after the stress test in a slot ends
+#[derive(Default)]
+pub struct CooldownMonitor {
+    inner: Mutex<MonitorInner>,
+}
+
+impl CooldownMonitor {
+    // Arm a cool-down for run `run` just started in `slot`. `baseline` is an idle sample taken before it started.
+    pub fn start(
+        &'static self,
+        slot: &'static StressSlot,
+        run: u64,
+        baseline: &SensorSample,
+        options: CooldownOptions,
+        default_sensor: &str,
+        read: SensorReader,
+    ) -> Result<(), String> {
+        let sensor = options.sensor.clone().unwrap_or_else(|| default_sensor.to_string());
+        if !sampling::is_channel(&sensor) {
+            return Err(format!("Unknown sensor: {}", sensor));
+        }
+        let baseline_temp = baseline
+            .value(&sensor)
+            .ok_or_else(|| format!("Cool-down needs an idle {} reading", sensor))?;
+
+        // A new stress test replaces any cool-down still in progress
+        self.cancel();
+
+        let fans = baseline
+            .fans
+            .iter()
+            .map(|&(fan, rpm)| FanRecovery {
+                fan,
+                baseline_rpm: Some(rpm),
+                start_rpm: None,
+                end_rpm: None,
+                rpm: SeriesStats::default(),
+                settle_secs: None,
+            })
+            .collect();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut inner = self.inner.lock();
+        inner.cancel = Some(cancel.clone());
+        inner.result = Some(CooldownResult {
+            state: CooldownState::WaitingForStop,
+            sensor,
+            baseline: baseline_temp,
+            start_temp: None,
+            current_temp: None,
+            within_celsius: options.within_celsius,
+            started_at: None,
+            elapsed_secs: 0.0,
+            recovery_secs: None,
+            decay_constant_secs: None,
+            fans,
+            samples: Vec::new(),
+        });
+        inner.thread = Some(thread::spawn(move || self.run(slot, run, options, read, cancel)));
+
+        Ok(())
+    }
+
+    pub fn cancel(&self) {
+        let (cancel, thread) = {
+            let mut inner = self.inner.lock();
+            (inner.cancel.take(), inner.thread.take())
+        };
+
+        if let Some(cancel) = cancel {
+            cancel.store(true, Ordering::SeqCst);
+        }
+        if let Some(thread) = thread {
+            let _ = thread.join();
+        }
+    }
+
+    pub fn result(&self) -> Option<CooldownResult> {
+        self.inner.lock().result.clone()
+    }
+
+    fn run(&self, slot: &StressSlot, run: u64, options: CooldownOptions, read: SensorReader, cancel: Arc<AtomicBool>) {
+        // Only the armed run counts; one started after it ended must not hold up the measurement
+        while slot.is_active(run) {
+            if cancel.load(Ordering::SeqCst) {
+                self.finish(CooldownState::Cancelled);
+                return;
+            }
+            thread::sleep(COOLDOWN_WAIT_TICK);
+        }
+
+        let start = Instant::now();
+        let timeout = Duration::from_secs(options.timeout_secs);
+        self.update(|result| {
+            result.state = CooldownState::Running;
+            result.started_at = Some(sampling::now_millis());
+        });
+
+        loop {
+            let sample = read();
+            let elapsed = start.elapsed().as_secs_f64();
+            let recovered = self.update(|result| result.add(sample, elapsed));
+            if recovered {
+                self.finish(CooldownState::Recovered);
+                return;
+            }
+            if start.elapsed() >= timeout {
+                self.finish(CooldownState::TimedOut);
+                return;
+            }
+
+            let next = Instant::now() + COOLDOWN_SAMPLE_INTERVAL;
+            while Instant::now() < next {
+                if cancel.load(Ordering::SeqCst) {
+                    self.finish(CooldownState::Cancelled);
+                    return;
+                }
+                thread::sleep(COOLDOWN_WAIT_TICK);
+            }
+        }
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut CooldownResult) -> T) -> T
+    where
+        T: Default,
+    {
+        self.inner.lock().result.as_mut().map(f).unwrap_or_default()
+    }
+
+    fn finish(&self, state: CooldownState) {
+        self.update(|result| {
+            result.state = state;
+            result.fit_decay();
+        });
+        self.inner.lock().cancel = None;
+    }
+}
+
+impl CooldownResult {
+    // Record one cool-down sample; returns true once the sensor is back near the baseline
+    fn add(&mut self, sample: SensorSample, elapsed: f64) -> bool {
+        let temp = sample.value(&self.sensor);
+        self.elapsed_secs = elapsed;
+        self.current_temp = temp;
+        if self.start_temp.is_none() {
+            self.start_temp = temp;
+        }
+
+        for &(fan, rpm) in &sample.fans {
+            let index = match self.fans.iter().position(|f| f.fan == fan) {
+                Some(index) => index,
+                None => {
+                    self.fans.push(FanRecovery {
+                        fan,
+                        baseline_rpm: None,
+                        start_rpm: None,
+                        end_rpm: None,
+                        rpm: SeriesStats::default(),
+                        settle_secs: None,
+                    });
+                    self.fans.len() - 1
+                }
+            };
+
+            let recovery = &mut self.fans[index];
+            recovery.start_rpm.get_or_insert(rpm);
+            recovery.end_rpm = Some(rpm);
+            recovery.rpm.add(rpm);
+            if let (Some(baseline), None) = (recovery.baseline_rpm, recovery.settle_secs) {
+                if (rpm - baseline).abs() <= (baseline * 0.1).max(100.0) {
+                    recovery.settle_secs = Some(elapsed);
+                }
+            }
+        }
+
+        self.samples.push(sample);
+
+        let recovered = temp.is_some_and(|temp| temp - self.baseline <= self.within_celsius);
+        if recovered {
+            self.recovery_secs = Some(elapsed);
+        }
+        recovered
+    }
+
+    // Fit the exponential decay of the samples so far towards the baseline
+    fn fit_decay(&mut self) {
+        let points = steady::series(&self.samples, &self.sensor);
+        self.decay_constant_secs = steady::fit_time_constant(&points, self.baseline);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stress::Worker;
+    use std::sync::atomic::AtomicBool;
+
+    fn cooling(baseline: f64, fan_baseline: f64) -> CooldownResult {
+        CooldownResult {
+            state: CooldownState::Running,
+            sensor: "cpu_temp".to_string(),
+            baseline,
+            start_temp: None,
+            current_temp: None,
+            within_celsius: 2.0,
+            started_at: None,
+            elapsed_secs: 0.0,
+            recovery_secs: None,
+            decay_constant_secs: None,
+            fans: vec![FanRecovery {
+                fan: 0,
+                baseline_rpm: Some(fan_baseline),
+                start_rpm: None,
+                end_rpm: None,
+                rpm: SeriesStats::default(),
+                settle_secs: None,
+            }],
+            samples: Vec::new(),
+        }
+    }
+
+    // One sample per second of `value(t)` for the CPU and `rpm(t)` for fan 0, until recovered
+    fn cool(result: &mut CooldownResult, secs: u64, value: impl Fn(f64) -> f64, rpm: impl Fn(f64) -> f64) -> bool {
+        for second in 0..secs {
+            let t = second as f64;
+            let sample = SensorSample {
+                timestamp: 1_000_000 + second * 1000,
+                cpu_temp: Some(value(t)),
+                fans: vec![(0, rpm(t))],
+                ..Default::default()
+            };
+            if result.add(sample, t) {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn fits_an_exponential_cool_down() {
+        // 40 °C above a 45 °C baseline, decaying with τ = 30 s
+        let mut result = cooling(45.0, 1200.0);
+        let recovered = cool(&mut result, 600, |t| 45.0 + 40.0 * (-t / 30.0).exp(), |t| 1200.0 + 3000.0 * (-t / 10.0).exp());
+        result.fit_decay();
+
+        assert!(recovered);
+        assert_eq!(result.start_temp, Some(85.0));
+        // 40·e^(-t/30) ≤ 2 once t ≥ 30·ln 20 ≈ 89.9 s
+        assert_eq!(result.recovery_secs, Some(90.0));
+        let tau = result.decay_constant_secs.unwrap();
+        assert!((tau - 30.0).abs() < 0.5, "fitted τ = {}", tau);
+
+        // Within 10% of 1200 RPM once 3000·e^(-t/10) ≤ 120, at t ≥ 10·ln 25 ≈ 32.2 s
+        let fan = &result.fans[0];
+        assert_eq!(fan.start_rpm, Some(4200.0));
+        assert_eq!(fan.settle_secs, Some(33.0));
+    }
+
+    #[test]
+    fn no_fit_without_a_decay() {
+        // Flat above the baseline: never recovers and there's no decay to fit
+        let mut result = cooling(45.0, 1200.0);
+        assert!(!cool(&mut result, 30, |_| 70.0, |_| 1200.0));
+        result.fit_decay();
+        assert_eq!(result.recovery_secs, None);
+        assert_eq!(result.decay_constant_secs, None);
+        assert_eq!(result.fans[0].settle_secs, Some(0.0));
+
+        // Too few samples
+        let mut result = cooling(45.0, 1200.0);
+        cool(&mut result, 2, |t| 45.0 + 40.0 * (-t / 30.0).exp(), |_| 1200.0);
+        result.fit_decay();
+        assert_eq!(result.decay_constant_secs, None);
+    }
+
+    #[test]
+    fn waits_only_for_the_armed_run() {
+        let slot: &'static StressSlot = Box::leak(Box::new(StressSlot::new("Test stress test")));
+        let monitor: &'static CooldownMonitor = Box::leak(Box::default());
+        let idle = |running: Arc<AtomicBool>| {
+            Ok(vec![Worker::spawn(move |_| {
+                while running.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })])
+        };
+        let reader: SensorReader = Arc::new(|| SensorSample { cpu_temp: Some(80.0), ..Default::default() });
+        let baseline = SensorSample { cpu_temp: Some(45.0), ..Default::default() };
+
+        let armed = slot.start("idle", 1, None, None, idle).unwrap();
+        monitor.start(slot, armed, &baseline, CooldownOptions::default(), "cpu_temp", reader).unwrap();
+        assert_eq!(monitor.result().unwrap().state, CooldownState::WaitingForStop);
+
+        // A new run right after the armed one ends doesn't hold up the cool-down
+        slot.stop();
+        slot.start("idle", 1, None, None, idle).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while monitor.result().unwrap().state == CooldownState::WaitingForStop {
+            assert!(Instant::now() < deadline, "cool-down never started");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        monitor.cancel();
+        assert_eq!(monitor.result().unwrap().state, CooldownState::Cancelled);
+        slot.stop();
+    }
+}
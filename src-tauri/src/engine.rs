@@ -0,0 +1,321 @@
+// The monitoring engine behind the app and the command line: the background sampler with the
+// history, recorder, database and replay fed from it, and the stress tests with the controllers
+// that run around them. Embedders call `start_sampling` once and then use the functions below.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use sysinfo::{System, SystemExt};
+
+use crate::compare::{self, Comparison, SessionSummary, Tolerances};
+use crate::cooldown::{CooldownMonitor, CooldownOptions};
+use crate::gpu_stress::{self, GpuIntensity, GpuSelector};
+use crate::history::{SensorHistory, SensorSeries, HISTORY_CAPACITY};
+use crate::html_report;
+use crate::load_profile::{LoadProfile, ProfileRunner};
+use crate::memory_stress::{self, MemoryStressOptions, MemoryStressStatus};
+use crate::metrics::{self, MetricsExporter, MetricsStatus};
+use crate::plan::{PlanContext, PlanEvent, PlanRunner, TestPlan};
+use crate::recording::{self, Recorder, RecordingFormat, RecordingStatus};
+use crate::replay::{ReplayOptions, ReplayPlayer, ReplayStatus};
+use crate::report::{self, TestReport};
+use crate::sampling::{self, Sampler, SensorSample, Snapshot};
+use crate::sensors;
+use crate::steady::SteadyStateConfig;
+use crate::store::ThermalStore;
+use crate::stress::{self, CpuStressOptions, StressSlot, StressStatus};
+use crate::thermal_target::{TargetController, TargetOptions};
+use crate::watchdog::{SafetyLimits, Watchdog};
+use crate::{GPU_STRESS_TEST, MEMORY_STRESS_TEST, STRESS_TEST};
+
+pub static COOLDOWN: Lazy<CooldownMonitor> = Lazy::new(CooldownMonitor::default);
+pub static LOAD_PROFILE: Lazy<ProfileRunner> = Lazy::new(ProfileRunner::default);
+pub static TARGET_CONTROLLER: Lazy<TargetController> = Lazy::new(TargetController::default);
+pub static TEST_PLAN: Lazy<PlanRunner> = Lazy::new(PlanRunner::default);
+
+// Background sampler and the sensor history it fills
+pub static SAMPLER: Lazy<Sampler> = Lazy::new(Sampler::default);
+pub static HISTORY: Lazy<SensorHistory> = Lazy::new(|| SensorHistory::new(HISTORY_CAPACITY));
+// Replayed snapshots carry their recorded timestamps, so they're kept apart from the live history
+pub static REPLAY_HISTORY: Lazy<SensorHistory> = Lazy::new(|| SensorHistory::new(HISTORY_CAPACITY));
+pub static RECORDER: Lazy<Recorder> = Lazy::new(Recorder::default);
+pub static STORE: Lazy<ThermalStore> = Lazy::new(ThermalStore::default);
+pub static REPLAY: Lazy<ReplayPlayer> = Lazy::new(ReplayPlayer::default);
+pub static METRICS: Lazy<MetricsExporter> = Lazy::new(MetricsExporter::default);
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Sampler readings older than this are read again rather than reused
+const LIVE_SAMPLE_MAX_AGE_MS: u64 = 3000;
+
+// Start reading every sensor each `interval` into the history, and into the recorder and the
+// database while they're open. Call once per process; subscribe to `SAMPLER` for the snapshots.
+pub fn start_sampling(interval: Duration) {
+    // A replay goes to the UI and the replay history, but recordings and the database keep live data only
+    SAMPLER.subscribe(Arc::new(|snapshot: &Snapshot| {
+        if SAMPLER.is_live() {
+            HISTORY.record(snapshot);
+            RECORDER.record(snapshot);
+            STORE.record(snapshot);
+        } else {
+            REPLAY_HISTORY.record(snapshot);
+        }
+    }));
+    // CPU usage is measured between two refreshes, so take the first one ahead of the first reading
+    let mut sys = System::new();
+    sys.refresh_cpu();
+    thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+    SAMPLER.start(interval, move || sensors::read_snapshot(&mut sys));
+}
+
+// Sensor reading for the watchdog, the cool-down, the controllers and test plans. They share the
+// sampler's latest live reading instead of each polling the SMC and ioreg, and read the sensors
+// themselves only while the sampler isn't running or has fallen behind.
+pub fn read_sample() -> SensorSample {
+    let now = sampling::now_millis();
+    match SAMPLER.latest_live().filter(|snapshot| now.saturating_sub(snapshot.timestamp) <= LIVE_SAMPLE_MAX_AGE_MS) {
+        Some(snapshot) => SensorSample::from(&snapshot),
+        None => sensors::read_sensor_sample(),
+    }
+}
+
+fn safety_watchdog(limits: Option<SafetyLimits>) -> Watchdog {
+    Watchdog {
+        limits: limits.unwrap_or_default(),
+        read: Arc::new(read_sample),
+    }
+}
+
+// Start the CPU stress test, optionally held at a temperature target or driven by a load profile
+// (which then decides the duration), and followed by a cool-down measurement.
+pub fn start_cpu_stress(
+    options: &CpuStressOptions,
+    duration: Option<Duration>,
+    safety: Option<SafetyLimits>,
+    cooldown: Option<CooldownOptions>,
+    target: Option<TargetOptions>,  // Closed-loop mode; the options' load becomes the starting load
+    profile: Option<LoadProfile>,   // Step ramp or cycling
+) -> Result<(), String> {
+    let watchdog = safety_watchdog(safety);
+    if let Some(target) = &target {
+        target.validate()?;
+    }
+    if let Some(profile) = &profile {
+        profile.validate()?;
+        if target.is_some() {
+            return Err("A load profile and a temperature target can't be combined".to_string());
+        }
+    }
+    let baseline = idle_baseline(&cooldown);
+
+    let duration = if profile.is_some() { None } else { duration };
+    let run = stress::start_cpu(&STRESS_TEST, options, duration, Some(watchdog))?;
+    let id = run.id;
+    if let Some(target) = target {
+        TARGET_CONTROLLER.start(&STRESS_TEST, run, target, Arc::new(read_sample));
+    } else if let Some(profile) = profile {
+        LOAD_PROFILE.start(&STRESS_TEST, run, profile, Arc::new(read_sample));
+    }
+    arm_cooldown(&STRESS_TEST, id, baseline, cooldown, "cpu_temp")
+}
+
+pub fn start_gpu_stress(
+    selector: &GpuSelector,
+    intensity: GpuIntensity,
+    duration: Option<Duration>,
+    safety: Option<SafetyLimits>,
+    cooldown: Option<CooldownOptions>,
+) -> Result<(), String> {
+    let watchdog = safety_watchdog(safety);
+    let baseline = idle_baseline(&cooldown);
+
+    let id = gpu_stress::start(&GPU_STRESS_TEST, selector, intensity, duration, Some(watchdog))?;
+    arm_cooldown(&GPU_STRESS_TEST, id, baseline, cooldown, "gpu_temp")
+}
+
+pub fn start_memory_stress(
+    options: &MemoryStressOptions,
+    duration: Option<Duration>,
+    safety: Option<SafetyLimits>,
+    cooldown: Option<CooldownOptions>,
+) -> Result<(), String> {
+    let watchdog = safety_watchdog(safety);
+    let baseline = idle_baseline(&cooldown);
+
+    let id = memory_stress::start(&MEMORY_STRESS_TEST, options, duration, Some(watchdog))?;
+    arm_cooldown(&MEMORY_STRESS_TEST, id, baseline, cooldown, "cpu_temp")
+}
+
+// Idle reading taken before a stress test starts, when a cool-down was requested
+fn idle_baseline(cooldown: &Option<CooldownOptions>) -> Option<SensorSample> {
+    cooldown.as_ref().map(|_| read_sample())
+}
+
+// Arm the cool-down for run `run` that just started; if it can't be armed, stop the test again
+fn arm_cooldown(
+    slot: &'static StressSlot,
+    run: u64,
+    baseline: Option<SensorSample>,
+    cooldown: Option<CooldownOptions>,
+    default_sensor: &str,
+) -> Result<(), String> {
+    let (Some(baseline), Some(options)) = (baseline, cooldown) else {
+        return Ok(());
+    };
+    COOLDOWN
+        .start(slot, run, &baseline, options, default_sensor, Arc::new(read_sample))
+        .inspect_err(|_| {
+            slot.stop();
+        })
+}
+
+// Stress test state for the CPU, the GPU and memory
+#[derive(Debug, Clone, Serialize)]
+pub struct StressStatusReport {
+    pub cpu: StressStatus,
+    pub gpu: StressStatus,
+    pub memory: MemoryStressStatus,
+}
+
+pub fn stress_status() -> StressStatusReport {
+    StressStatusReport {
+        cpu: STRESS_TEST.status(),
+        gpu: GPU_STRESS_TEST.status(),
+        memory: MEMORY_STRESS_TEST.status().into(),
+    }
+}
+
+// Serve the latest sampler readings and the stress test state at http://`bind`/metrics.
+// While a replay runs the live readings aren't published, so only the stress tests are served.
+pub fn start_metrics(bind: &str) -> Result<MetricsStatus, String> {
+    METRICS.start(
+        bind,
+        Arc::new(|| {
+            let snapshot = SAMPLER.latest().filter(|_| SAMPLER.is_live());
+            let tests = [
+                ("cpu", STRESS_TEST.status()),
+                ("gpu", GPU_STRESS_TEST.status()),
+                ("memory", MEMORY_STRESS_TEST.status()),
+            ];
+            metrics::render(snapshot.as_ref(), &tests)
+        }),
+    )
+}
+
+// Sensor history for charts: the replay's while one is playing, the live history otherwise
+pub fn history(sensor_ids: &[String], since: Option<u64>, max_points: Option<usize>) -> Vec<SensorSeries> {
+    if SAMPLER.is_live() {
+        HISTORY.query(sensor_ids, since, max_points)
+    } else {
+        REPLAY_HISTORY.query(sensor_ids, since, max_points)
+    }
+}
+
+// Record every sampled metric to `path` (a file or a directory).
+// The format follows the file extension unless given.
+pub fn start_recording(path: &Path, format: Option<RecordingFormat>) -> Result<RecordingStatus, String> {
+    let format = format
+        .or_else(|| RecordingFormat::from_path(path))
+        .unwrap_or(RecordingFormat::Csv);
+    RECORDER.start(path, format, sensors::machine_info())
+}
+
+// Play a CSV or JSONL recording back through the sampler in place of the live sensors
+pub fn start_replay(path: &str, options: ReplayOptions) -> Result<ReplayStatus, String> {
+    let recording = recording::read(Path::new(path))?;
+    // Stop the previous replay first, so none of its snapshots land in the fresh history
+    REPLAY.stop();
+    REPLAY_HISTORY.clear();
+    REPLAY.start(&SAMPLER, path, recording, options)
+}
+
+// Run a test plan, reporting progress through `emit`.
+// With the history database open, each plan run gets its own session.
+pub fn run_test_plan(plan: TestPlan, emit: Arc<dyn Fn(PlanEvent) + Send + Sync>) -> Result<(), String> {
+    let store_session = STORE.is_open();
+    let name = plan.name.clone();
+
+    let ctx = PlanContext {
+        cpu: &STRESS_TEST,
+        gpu: &GPU_STRESS_TEST,
+        memory: &MEMORY_STRESS_TEST,
+        read: Arc::new(read_sample),
+        emit: Arc::new(move |event: PlanEvent| {
+            if store_session && matches!(event, PlanEvent::PlanFinished { .. }) {
+                let _ = STORE.end_session();
+            }
+            emit(event);
+        }),
+    };
+    // The session is opened under the runner's lock, so a plan that's already running keeps its
+    // session, and before the plan thread starts, so its first samples and its end land in it
+    TEST_PLAN.start(plan, ctx, || {
+        if store_session {
+            STORE.begin_session(&name, "plan")?;
+        }
+        Ok(())
+    })
+}
+
+// Pass/fail report of the last finished plan, optionally written as JSON, JUnit XML and a standalone HTML page
+pub fn test_plan_report(
+    json_path: Option<&Path>,
+    junit_path: Option<&Path>,
+    html_path: Option<&Path>,
+) -> Result<TestReport, String> {
+    let report = TEST_PLAN.report().ok_or("No finished test plan")?;
+    if let Some(path) = json_path {
+        report::write_json(&report, path)?;
+    }
+    if let Some(path) = junit_path {
+        report::write_junit(&report, path)?;
+    }
+    if let Some(path) = html_path {
+        let plan = TEST_PLAN.plan().ok_or("No finished test plan")?;
+        // Plan samples don't carry CPU usage, so it comes from the live history while it still covers the run
+        let cpu_usage = HISTORY
+            .query(&["cpu_usage".to_string()], Some(report.started_at), Some(HISTORY_CAPACITY))
+            .pop()
+            .map(|series| series.points)
+            .unwrap_or_default();
+        html_report::write(&report, &plan, &sensors::machine_info(), &cpu_usage, path)?;
+    }
+    Ok(report)
+}
+
+// Summary of a recording, saved report or baseline file, or of the last test plan run
+pub fn session_summary(path: Option<&Path>, config: &SteadyStateConfig) -> Result<SessionSummary, String> {
+    match path {
+        Some(path) => compare::load_session(path, config),
+        None => {
+            let result = TEST_PLAN.result().ok_or("No test plan has run")?;
+            Ok(compare::summarize_plan(&result, config))
+        }
+    }
+}
+
+// Compare a session with a baseline phase by phase. Either may be a CSV/JSONL recording,
+// a saved test report or a saved baseline; without a candidate the last plan run is used.
+pub fn compare_sessions(
+    baseline: &Path,
+    candidate: Option<&Path>,
+    tolerances: &Tolerances,
+    config: &SteadyStateConfig,
+) -> Result<Comparison, String> {
+    let baseline = session_summary(Some(baseline), config)?;
+    let candidate = session_summary(candidate, config)?;
+    Ok(compare::compare(&baseline, &candidate, tolerances))
+}
+
+// Save the per-phase metrics of a session (the last plan run unless `source` is given) as a baseline
+pub fn save_session_baseline(
+    path: &Path,
+    source: Option<&Path>,
+    config: &SteadyStateConfig,
+) -> Result<SessionSummary, String> {
+    let summary = session_summary(source, config)?;
+    compare::save_baseline(&summary, path)?;
+    Ok(summary)
+}
@@ -0,0 +1,623 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+#[cfg(not(target_os = "macos"))]
+use std::thread;
+use std::time::Duration;
+
+use crate::stress::{StressSlot, Worker};
+use crate::watchdog::Watchdog;
+
+#[cfg(target_os = "macos")]
+pub use metal_backend::MetalBackend;
+
+// Backend used by the GPU stress test on this platform
+#[cfg(target_os = "macos")]
+pub const DEFAULT_BACKEND: &str = "metal";
+#[cfg(not(target_os = "macos"))]
+pub const DEFAULT_BACKEND: &str = "noop";
+
+// How hard the GPU stress test pushes the GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuIntensity {
+    Low,
+    Medium,
+    #[default]
+    High,
+    Extreme,
+}
+
+// Work submitted per command buffer
+#[derive(Debug, Clone, Copy)]
+pub struct GpuWorkSize {
+    pub grid_side: u64,   // The grid is grid_side x grid_side threads
+    pub iterations: u32,  // Shader loop iterations per thread
+    pub dispatches: u32,  // Dispatches per command buffer
+}
+
+// Shader loop iterations allowed in one command buffer: roughly a tenth of a second
+// on a base M1 GPU. Stopping waits for the buffers still in flight, so this bounds
+// stop latency to well under a second.
+pub const MAX_OPS_PER_BUFFER: u64 = 1 << 30;
+
+impl GpuIntensity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GpuIntensity::Low => "low",
+            GpuIntensity::Medium => "medium",
+            GpuIntensity::High => "high",
+            GpuIntensity::Extreme => "extreme",
+        }
+    }
+
+    // Work in one command buffer, at most MAX_OPS_PER_BUFFER. Above low, each level
+    // adds a dispatch of the same grid, so the GPU sits idle less between buffers.
+    pub fn work_size(&self) -> GpuWorkSize {
+        match self {
+            GpuIntensity::Low => GpuWorkSize { grid_side: 512, iterations: 500, dispatches: 1 },
+            GpuIntensity::Medium => GpuWorkSize { grid_side: 1024, iterations: 250, dispatches: 2 },
+            GpuIntensity::High => GpuWorkSize { grid_side: 1024, iterations: 250, dispatches: 3 },
+            GpuIntensity::Extreme => GpuWorkSize { grid_side: 1024, iterations: 250, dispatches: 4 },
+        }
+    }
+}
+
+impl GpuWorkSize {
+    // Shader loop iterations in one command buffer
+    pub fn ops_per_buffer(&self) -> u64 {
+        self.grid_side * self.grid_side * self.iterations as u64 * self.dispatches as u64
+    }
+}
+
+// A GPU that can run the stress test
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuDevice {
+    pub index: usize,       // Position in the device list
+    pub name: String,
+    pub registry_id: u64,   // Stable IORegistry id, used to find the device again on the worker thread
+    pub low_power: bool,    // Integrated GPU
+    pub removable: bool,    // External GPU
+    pub headless: bool,     // Not connected to a display
+    pub is_default: bool,   // The GPU the system prefers
+}
+
+// Which GPUs a stress run should use
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuSelector {
+    #[default]
+    Default,        // The system default GPU
+    Index(usize),   // Position in the device list
+    Name(String),   // First GPU whose name contains this text (case-insensitive)
+    LowPower,       // All integrated GPUs
+    HighPower,      // All discrete and external GPUs
+    All,            // Every GPU at once
+}
+
+#[cfg(target_os = "macos")]
+pub fn list_devices() -> Vec<GpuDevice> {
+    let default_id = metal::Device::system_default().map(|device| device.registry_id());
+
+    metal::Device::all()
+        .iter()
+        .enumerate()
+        .map(|(index, device)| GpuDevice {
+            index,
+            name: device.name().to_string(),
+            registry_id: device.registry_id(),
+            low_power: device.is_low_power(),
+            removable: device.is_removable(),
+            headless: device.is_headless(),
+            is_default: Some(device.registry_id()) == default_id,
+        })
+        .collect()
+}
+
+// Without Metal there is a single placeholder device served by the no-op backend
+#[cfg(not(target_os = "macos"))]
+pub fn list_devices() -> Vec<GpuDevice> {
+    vec![GpuDevice {
+        index: 0,
+        name: "No-op GPU".to_string(),
+        registry_id: 0,
+        low_power: false,
+        removable: false,
+        headless: true,
+        is_default: true,
+    }]
+}
+
+pub fn select_devices(selector: &GpuSelector) -> Result<Vec<GpuDevice>, String> {
+    let devices = list_devices();
+    let selected: Vec<GpuDevice> = match selector {
+        GpuSelector::Default => devices.into_iter().filter(|d| d.is_default).collect(),
+        GpuSelector::Index(index) => devices.into_iter().filter(|d| d.index == *index).collect(),
+        GpuSelector::Name(name) => {
+            let name = name.to_lowercase();
+            devices.into_iter().find(|d| d.name.to_lowercase().contains(&name)).into_iter().collect()
+        }
+        GpuSelector::LowPower => devices.into_iter().filter(|d| d.low_power).collect(),
+        GpuSelector::HighPower => devices.into_iter().filter(|d| !d.low_power).collect(),
+        GpuSelector::All => devices,
+    };
+
+    if selected.is_empty() {
+        return Err(format!("No GPU matches {:?}", selector));
+    }
+    Ok(selected)
+}
+
+// Result of checking the GPU output against the reference
+pub struct Verification {
+    pub ops: u64,      // Shader loop iterations completed since the previous check
+    pub matched: bool,
+}
+
+// One way of keeping a GPU busy. Backends are created on the worker thread,
+// since GPU API objects usually can't be moved between threads.
+pub trait GpuStressBackend {
+    // Submit one round of work. Returns a verification result whenever the output was checked.
+    // Backends must bound the work they keep queued, so stop takes effect quickly.
+    fn run_round(&mut self) -> Result<Option<Verification>, String>;
+
+    // Wait for all submitted work to finish
+    fn drain(&mut self) {}
+}
+
+// Spawn the GPU stress worker. `make_backend` runs on the worker thread, and its
+// error, if any, is returned here before the run is registered.
+pub fn spawn_worker<F, B>(running: Arc<AtomicBool>, make_backend: F) -> Result<Worker, String>
+where
+    F: FnOnce() -> Result<B, String> + Send + 'static,
+    B: GpuStressBackend,
+{
+    let (ready_tx, ready_rx) = mpsc::channel();
+
+    let worker = Worker::spawn(move |counters| {
+        let mut backend = match make_backend() {
+            Ok(backend) => {
+                let _ = ready_tx.send(Ok(()));
+                backend
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+
+        while running.load(Ordering::SeqCst) {
+            match backend.run_round() {
+                Ok(Some(verification)) => {
+                    if !verification.matched {
+                        println!("GPU stress kernel produced a different result than the reference");
+                    }
+                    counters.record(verification.ops, verification.matched);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    counters.fail(e);
+                    break;
+                }
+            }
+        }
+
+        backend.drain();
+    });
+
+    ready_rx.recv()
+        .map_err(|_| "GPU stress worker exited during setup".to_string())??;
+
+    Ok(worker)
+}
+
+// Start a GPU stress run in `slot` on the selected GPUs; returns the run id
+pub fn start(
+    slot: &'static StressSlot,
+    selector: &GpuSelector,
+    intensity: GpuIntensity,
+    duration: Option<Duration>,
+    watchdog: Option<Watchdog>,
+) -> Result<u64, String> {
+    let devices = select_devices(selector)?;
+    let names: Vec<&str> = devices.iter().map(|device| device.name.as_str()).collect();
+    let workload = format!("{} ({}) on {}", DEFAULT_BACKEND, intensity.name(), names.join(", "));
+
+    let id = slot.start(&workload, devices.len(), duration, watchdog, |running| {
+        // Device and shader setup errors are returned here instead of panicking in the thread
+        spawn_workers(running, &devices, intensity)
+    })?;
+    slot.attach_gpu_intensity(intensity);
+    Ok(id)
+}
+
+// Spawn one worker per selected GPU with the platform's backend
+pub fn spawn_workers(
+    running: Arc<AtomicBool>,
+    devices: &[GpuDevice],
+    intensity: GpuIntensity,
+) -> Result<Vec<Worker>, String> {
+    #[cfg(target_os = "macos")]
+    let backends = devices.iter().cloned().map(|device| move || MetalBackend::new(&device, intensity));
+    #[cfg(not(target_os = "macos"))]
+    let backends = {
+        let _ = intensity;
+        devices.iter().map(|_| || Ok(NoopBackend))
+    };
+
+    spawn_all(running, backends)
+}
+
+// Spawn one worker per backend. If any of them fails to set up, the workers that
+// already started are stopped and joined before the error is returned.
+pub fn spawn_all<I, F, B>(running: Arc<AtomicBool>, backends: I) -> Result<Vec<Worker>, String>
+where
+    I: IntoIterator<Item = F>,
+    F: FnOnce() -> Result<B, String> + Send + 'static,
+    B: GpuStressBackend,
+{
+    let mut workers = Vec::new();
+
+    for make_backend in backends {
+        match spawn_worker(running.clone(), make_backend) {
+            Ok(worker) => workers.push(worker),
+            Err(e) => {
+                running.store(false, Ordering::SeqCst);
+                for worker in &mut workers {
+                    worker.join();
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(workers)
+}
+
+// Backend that does no GPU work, so the stress test lifecycle can run without a GPU
+#[cfg(not(target_os = "macos"))]
+pub struct NoopBackend;
+
+#[cfg(not(target_os = "macos"))]
+impl GpuStressBackend for NoopBackend {
+    fn run_round(&mut self) -> Result<Option<Verification>, String> {
+        thread::sleep(Duration::from_millis(10));
+        Ok(Some(Verification { ops: 0, matched: true }))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod metal_backend {
+    use metal::{
+        Buffer, CommandBuffer, CommandQueue, CompileOptions, ComputePipelineState, Device,
+        MTLCommandBufferStatus, MTLResourceOptions, MTLSize,
+    };
+    use std::collections::VecDeque;
+    use std::mem;
+
+    use super::{GpuDevice, GpuIntensity, GpuStressBackend, GpuWorkSize, Verification};
+
+    // The shader writes one float4 per cell of a 128x128 output grid
+    const GPU_OUTPUT_WORDS: usize = 128 * 128 * 4;
+
+    // Command buffers allowed in flight. Submitting waits for the oldest one once
+    // this many are queued, so stop never waits for more than this much work.
+    const MAX_IN_FLIGHT: usize = 3;
+
+    // Check the GPU output after every this many command buffers
+    const GPU_VERIFY_BUFFERS: u64 = 64;
+
+    pub struct MetalBackend {
+        command_queue: CommandQueue,
+        pipeline: ComputePipelineState,
+        output: Buffer,
+        work_size: GpuWorkSize,
+        in_flight: VecDeque<CommandBuffer>,
+        reference: Option<Vec<u32>>,
+        submitted: u64,
+        completed_since_check: u64,
+    }
+
+    impl MetalBackend {
+        pub fn new(gpu: &GpuDevice, intensity: GpuIntensity) -> Result<Self, String> {
+            let device = Device::all()
+                .into_iter()
+                .find(|device| device.registry_id() == gpu.registry_id)
+                .ok_or_else(|| format!("Metal device {} is no longer available", gpu.name))?;
+            let command_queue = device.new_command_queue();
+            let compile_options = CompileOptions::new();
+
+            let library = device.new_library_with_source(METAL_SHADER_SOURCE, &compile_options)
+                .map_err(|e| format!("Failed to create shader library: {}", e))?;
+            let kernel = library.get_function("gpu_stress", None)
+                .map_err(|e| format!("Failed to get kernel function: {}", e))?;
+            let pipeline = device.new_compute_pipeline_state_with_function(&kernel)
+                .map_err(|e| format!("Failed to create pipeline state: {}", e))?;
+
+            // The kernel writes its results here so the work can't be optimized away
+            let output = device.new_buffer(
+                (GPU_OUTPUT_WORDS * mem::size_of::<u32>()) as u64,
+                MTLResourceOptions::StorageModeShared,
+            );
+
+            Ok(MetalBackend {
+                command_queue,
+                pipeline,
+                output,
+                work_size: intensity.work_size(),
+                in_flight: VecDeque::with_capacity(MAX_IN_FLIGHT),
+                reference: None,
+                submitted: 0,
+                completed_since_check: 0,
+            })
+        }
+
+        // Wait for the oldest command buffer in flight
+        fn wait_oldest(&mut self) -> Result<(), String> {
+            if let Some(command_buffer) = self.in_flight.pop_front() {
+                command_buffer.wait_until_completed();
+                if command_buffer.status() == MTLCommandBufferStatus::Error {
+                    return Err("Metal command buffer failed".to_string());
+                }
+                self.completed_since_check += 1;
+            }
+            Ok(())
+        }
+
+        // Compare the output with the first result
+        fn verify(&mut self) -> bool {
+            let words = unsafe {
+                std::slice::from_raw_parts(self.output.contents() as *const u32, GPU_OUTPUT_WORDS)
+            }.to_vec();
+
+            match &self.reference {
+                Some(expected) => *expected == words,
+                None => {
+                    self.reference = Some(words);
+                    true
+                }
+            }
+        }
+    }
+
+    impl GpuStressBackend for MetalBackend {
+        fn run_round(&mut self) -> Result<Option<Verification>, String> {
+            // Keep the queue bounded: wait for the oldest buffer before adding another
+            while self.in_flight.len() >= MAX_IN_FLIGHT {
+                self.wait_oldest()?;
+            }
+
+            let work_size = self.work_size;
+            let command_buffer = self.command_queue.new_command_buffer();
+            let compute_encoder = command_buffer.new_compute_command_encoder();
+
+            compute_encoder.set_compute_pipeline_state(&self.pipeline);
+            compute_encoder.set_buffer(0, Some(&self.output), 0);
+            compute_encoder.set_bytes(
+                1,
+                mem::size_of::<u32>() as u64,
+                &work_size.iterations as *const u32 as *const std::ffi::c_void,
+            );
+
+            let grid_size = MTLSize::new(work_size.grid_side, work_size.grid_side, 1);
+            let thread_group_size = MTLSize::new(16, 16, 1);
+            for _ in 0..work_size.dispatches {
+                compute_encoder.dispatch_threads(grid_size, thread_group_size);
+            }
+
+            compute_encoder.end_encoding();
+            command_buffer.commit();
+            self.in_flight.push_back(command_buffer.to_owned());
+            self.submitted += 1;
+
+            // Periodically wait for the queued work and check the output
+            if self.submitted.is_multiple_of(GPU_VERIFY_BUFFERS) {
+                while !self.in_flight.is_empty() {
+                    self.wait_oldest()?;
+                }
+                let verification = Verification {
+                    ops: self.completed_since_check * work_size.ops_per_buffer(),
+                    matched: self.verify(),
+                };
+                self.completed_since_check = 0;
+                return Ok(Some(verification));
+            }
+
+            Ok(None)
+        }
+
+        fn drain(&mut self) {
+            for command_buffer in self.in_flight.drain(..) {
+                command_buffer.wait_until_completed();
+            }
+        }
+    }
+
+    // Metal shader source code
+    const METAL_SHADER_SOURCE: &str = r#"
+#include <metal_stdlib>
+using namespace metal;
+
+kernel void gpu_stress(
+    device float4 *output [[buffer(0)]],
+    constant uint &iterations [[buffer(1)]],
+    uint2 gid [[thread_position_in_grid]]
+) {
+    // Threads sharing an output cell compute identical values, so their writes don't race
+    uint2 cell = gid % 128;
+    float4 result = float4(0.0);
+    float4 temp = float4(cell.x, cell.y, 1.0, 1.0);
+    float3 temp3 = float3(1.0, 1.0, 1.0);
+    
+    // Loop count is set by the intensity level
+    for(uint i = 0; i < iterations; i++) {
+        // Complex mathematical operations
+        temp = sin(temp) * 0.5 + cos(temp) * 0.5;
+        result += temp;
+        
+        // Dense mathematical operations
+        temp = pow(temp, 2.0) + float4(0.1);
+        temp = fmod(temp * 1.5, 3.14159);
+        temp = log(abs(temp) + 1.0);
+        temp = exp(temp * 0.5);
+        
+        // Conditional branching and vector operations
+        if(length(temp) > 2.0) {
+            temp = normalize(temp);
+            temp3 = cross(temp3, float3(0.5, 0.7, 0.3));
+            temp.xyz = temp3;
+            temp = sqrt(abs(temp)) + 0.5;
+        } else {
+            temp = mix(temp, float4(1.0), 0.5);
+            temp = reflect(temp, normalize(float4(1.0)));
+            temp = floor(temp * 3.0) / 3.0;
+        }
+        
+        // Dense matrix operations
+        float4x4 matrix = float4x4(
+            cos(temp.x), sin(temp.y), -sin(temp.z), cos(temp.w),
+            sin(temp.x), cos(temp.y), cos(temp.z), -sin(temp.w),
+            -sin(temp.x), cos(temp.y), cos(temp.z), sin(temp.w),
+            cos(temp.x), -sin(temp.y), sin(temp.z), cos(temp.w)
+        );
+        temp = matrix * temp;
+        
+        // Additional mathematical operations
+        temp = smoothstep(float4(-1.0), float4(1.0), temp);
+        temp = fract(temp * 1.5) * 2.0 - 1.0;
+        temp = atan2(temp + 0.1, float4(1.0));
+        
+        // More conditional branching
+        if(any(temp > 0.5)) {
+            temp = pow(temp, 3.0);
+        }
+        
+        result += temp;
+    }
+    
+    // Prevent compiler optimization
+    output[cell.y * 128 + cell.x] = result;
+}
+"#;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stress::StressState;
+    use std::sync::atomic::AtomicU64;
+    use std::time::Instant;
+
+    // Backend that fails or reports a mismatch after a given number of rounds
+    #[derive(Default)]
+    struct MockBackend {
+        rounds: u64,
+        fail_after: Option<u64>,
+        mismatch: bool,
+        drained: Option<Arc<AtomicBool>>,
+    }
+
+    impl GpuStressBackend for MockBackend {
+        fn run_round(&mut self) -> Result<Option<Verification>, String> {
+            std::thread::sleep(Duration::from_millis(2));
+            self.rounds += 1;
+            if self.fail_after.is_some_and(|rounds| self.rounds > rounds) {
+                return Err("mock command buffer failed".to_string());
+            }
+            Ok(Some(Verification { ops: 10, matched: !self.mismatch }))
+        }
+
+        fn drain(&mut self) {
+            if let Some(drained) = &self.drained {
+                drained.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn slot() -> &'static StressSlot {
+        Box::leak(Box::new(StressSlot::new("Mock GPU stress test")))
+    }
+
+    fn start_mock(slot: &'static StressSlot, duration: Option<Duration>, make: fn() -> MockBackend) -> Result<u64, String> {
+        slot.start("mock", 1, duration, None, |running| Ok(vec![spawn_worker(running, move || Ok(make()))?]))
+    }
+
+    // Poll the slot until `done` holds for its status, or fail after a few seconds
+    fn wait_for(slot: &StressSlot, done: impl Fn(&crate::stress::StressStatus) -> bool) -> crate::stress::StressStatus {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let status = slot.status();
+            if done(&status) {
+                return status;
+            }
+            assert!(Instant::now() < deadline, "timed out in state {:?}", status.state);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn backend_error_fails_the_run() {
+        let slot = slot();
+        start_mock(slot, None, || MockBackend { fail_after: Some(2), ..Default::default() }).unwrap();
+        let status = wait_for(slot, |status| status.state == StressState::Failed);
+        assert_eq!(status.error.as_deref(), Some("mock command buffer failed"));
+        assert_eq!(status.verified_batches, 2);
+    }
+
+    #[test]
+    fn mismatches_are_counted() {
+        let slot = slot();
+        start_mock(slot, None, || MockBackend { mismatch: true, ..Default::default() }).unwrap();
+        wait_for(slot, |status| status.mismatches >= 2);
+        let status = slot.stop().unwrap();
+        assert_eq!(status.mismatches, status.verified_batches);
+    }
+
+    #[test]
+    fn setup_error_is_returned_before_the_run_starts() {
+        let slot = slot();
+        let result = slot.start("mock", 1, None, None, |running| {
+            Ok(vec![spawn_worker(running, || Err::<MockBackend, _>("no device".to_string()))?])
+        });
+        assert_eq!(result, Err("no device".to_string()));
+        assert_eq!(slot.status().state, StressState::Idle);
+    }
+
+    #[test]
+    fn failed_setup_stops_the_workers_already_started() {
+        let running = Arc::new(AtomicBool::new(true));
+        let drained = Arc::new(AtomicBool::new(false));
+        let started = Arc::new(AtomicU64::new(0));
+
+        let backends: Vec<Box<dyn FnOnce() -> Result<MockBackend, String> + Send>> = vec![
+            Box::new({
+                let drained = drained.clone();
+                let started = started.clone();
+                move || {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    Ok(MockBackend { drained: Some(drained), ..Default::default() })
+                }
+            }),
+            Box::new(|| Err("second GPU is gone".to_string())),
+        ];
+
+        assert_eq!(spawn_all(running.clone(), backends).err().as_deref(), Some("second GPU is gone"));
+        assert!(!running.load(Ordering::SeqCst));
+        assert_eq!(started.load(Ordering::SeqCst), 1);
+        // The first worker was joined, so it has already drained its backend
+        assert!(drained.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn command_buffers_stay_small() {
+        let intensities = [GpuIntensity::Low, GpuIntensity::Medium, GpuIntensity::High, GpuIntensity::Extreme];
+        for intensity in intensities {
+            assert!(intensity.work_size().ops_per_buffer() <= MAX_OPS_PER_BUFFER, "{}", intensity.name());
+        }
+        // Each level does more work per buffer than the one below
+        for pair in intensities.windows(2) {
+            assert!(pair[0].work_size().ops_per_buffer() < pair[1].work_size().ops_per_buffer());
+        }
+    }
+}
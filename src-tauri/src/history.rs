@@ -0,0 +1,203 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::sampling::{SeriesStats, Snapshot};
+
+// Points kept per sensor: one hour at the default 1 s sampling interval
+pub const HISTORY_CAPACITY: usize = 3600;
+
+// Points returned per series when the caller doesn't say
+const DEFAULT_MAX_POINTS: usize = 300;
+
+// One sensor's history, downsampled for charting
+#[derive(Debug, Clone, Serialize)]
+pub struct SensorSeries {
+    pub sensor: String,
+    pub points: Vec<(u64, f64)>,  // (Unix timestamp in milliseconds, value)
+    pub stats: SeriesStats,       // Over the raw points in the range, not the downsampled ones
+}
+
+// Bounded ring buffer of timestamped values per sensor
+pub struct SensorHistory {
+    capacity: usize,
+    buffers: Mutex<BTreeMap<String, VecDeque<(u64, f64)>>>,
+}
+
+impl SensorHistory {
+    pub fn new(capacity: usize) -> Self {
+        SensorHistory {
+            capacity,
+            buffers: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn record(&self, snapshot: &Snapshot) {
+        let mut buffers = self.buffers.lock();
+        // Queries rely on timestamps only growing, so a source that jumps back
+        // (a replay seeking, or the system clock being set back) starts the history afresh
+        let newest = buffers.values().filter_map(|buffer| buffer.back()).map(|&(t, _)| t).max();
+        if newest.is_some_and(|newest| snapshot.timestamp < newest) {
+            buffers.clear();
+        }
+        for (sensor, &value) in &snapshot.values {
+            let buffer = buffers
+                .entry(sensor.clone())
+                .or_insert_with(|| VecDeque::with_capacity(self.capacity));
+            if buffer.len() == self.capacity {
+                buffer.pop_front();
+            }
+            buffer.push_back((snapshot.timestamp, value));
+        }
+    }
+
+    pub fn clear(&self) {
+        self.buffers.lock().clear();
+    }
+
+    pub fn sensors(&self) -> Vec<String> {
+        self.buffers.lock().keys().cloned().collect()
+    }
+
+    // History of the given sensors (all when empty) since `since`, at most `max_points` per series
+    pub fn query(&self, sensor_ids: &[String], since: Option<u64>, max_points: Option<usize>) -> Vec<SensorSeries> {
+        let max_points = max_points.unwrap_or(DEFAULT_MAX_POINTS).max(1);
+        let since = since.unwrap_or(0);
+        let buffers = self.buffers.lock();
+
+        let selected: Vec<&String> = if sensor_ids.is_empty() {
+            buffers.keys().collect()
+        } else {
+            sensor_ids.iter().filter(|id| buffers.contains_key(*id)).collect()
+        };
+
+        selected
+            .into_iter()
+            .map(|sensor| {
+                let buffer = &buffers[sensor];
+                // Timestamps only grow, so the range starts at a partition point
+                let start = buffer.partition_point(|&(t, _)| t < since);
+                let points: Vec<(u64, f64)> = buffer.range(start..).copied().collect();
+
+                let mut stats = SeriesStats::default();
+                for &(_, value) in &points {
+                    stats.add(value);
+                }
+
+                SensorSeries {
+                    sensor: sensor.clone(),
+                    points: downsample(&points, max_points),
+                    stats,
+                }
+            })
+            .collect()
+    }
+}
+
+// Average into `max_points` equal time buckets; empty buckets are skipped
+fn downsample(points: &[(u64, f64)], max_points: usize) -> Vec<(u64, f64)> {
+    if points.len() <= max_points {
+        return points.to_vec();
+    }
+
+    let first = points[0].0;
+    let span = points[points.len() - 1].0 - first + 1;
+    let mut buckets: Vec<(u64, f64, usize)> = vec![(0, 0.0, 0); max_points];
+
+    for &(t, value) in points {
+        let index = ((t - first) as u128 * max_points as u128 / span as u128) as usize;
+        let bucket = &mut buckets[index];
+        bucket.0 += t - first;
+        bucket.1 += value;
+        bucket.2 += 1;
+    }
+
+    buckets
+        .into_iter()
+        .filter(|&(_, _, count)| count > 0)
+        .map(|(t, sum, count)| (first + t / count as u64, sum / count as f64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(timestamp: u64, values: &[(&str, f64)]) -> Snapshot {
+        Snapshot {
+            timestamp,
+            values: values.iter().map(|&(id, value)| (id.to_string(), value)).collect(),
+        }
+    }
+
+    fn ids(list: &[&str]) -> Vec<String> {
+        list.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn ring_buffer_drops_the_oldest_points() {
+        let history = SensorHistory::new(3);
+        for second in 0..5 {
+            history.record(&snapshot(second * 1000, &[("cpu_temp", 50.0 + second as f64)]));
+        }
+        let series = history.query(&[], None, None);
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].points, vec![(2000, 52.0), (3000, 53.0), (4000, 54.0)]);
+        assert_eq!((series[0].stats.min, series[0].stats.max, series[0].stats.count), (Some(52.0), Some(54.0), 3));
+    }
+
+    #[test]
+    fn query_filters_by_sensor_and_since() {
+        let history = SensorHistory::new(10);
+        for second in 0..4 {
+            history.record(&snapshot(second * 1000, &[("cpu_temp", 50.0), ("fan_0", 1200.0 + second as f64)]));
+        }
+        assert_eq!(history.sensors(), ids(&["cpu_temp", "fan_0"]));
+
+        // Unknown sensors are skipped; `since` is inclusive
+        let series = history.query(&ids(&["fan_0", "gpu_temp"]), Some(2000), None);
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].sensor, "fan_0");
+        assert_eq!(series[0].points, vec![(2000, 1202.0), (3000, 1203.0)]);
+        assert_eq!(series[0].stats.count, 2);
+
+        assert!(history.query(&[], Some(5000), None)[0].points.is_empty());
+    }
+
+    #[test]
+    fn downsampling_keeps_raw_min_and_max_in_the_stats() {
+        let history = SensorHistory::new(1000);
+        for second in 0..100u64 {
+            // One spike and one dip that averaging smooths out of the points
+            let value = match second {
+                17 => 95.0,
+                63 => 20.0,
+                _ => 50.0,
+            };
+            history.record(&snapshot(second * 1000, &[("cpu_temp", value)]));
+        }
+
+        let series = &history.query(&[], None, Some(10))[0];
+        assert_eq!(series.points.len(), 10);
+        assert!(series.points.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(series.points.iter().all(|&(_, value)| value < 95.0 && value > 20.0));
+        assert_eq!((series.stats.min, series.stats.max, series.stats.count), (Some(20.0), Some(95.0), 100));
+
+        // The first bucket averages the first ten points
+        assert_eq!(series.points[0], (4500, 50.0));
+    }
+
+    #[test]
+    fn timestamps_going_back_start_afresh() {
+        let history = SensorHistory::new(10);
+        history.record(&snapshot(5000, &[("cpu_temp", 60.0), ("gpu_temp", 55.0)]));
+        history.record(&snapshot(6000, &[("cpu_temp", 61.0)]));
+        history.record(&snapshot(1000, &[("cpu_temp", 40.0)]));
+
+        assert_eq!(history.sensors(), ids(&["cpu_temp"]));
+        assert_eq!(history.query(&[], None, None)[0].points, vec![(1000, 40.0)]);
+
+        history.clear();
+        assert!(history.query(&[], None, None).is_empty());
+    }
+}
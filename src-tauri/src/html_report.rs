@@ -0,0 +1,695 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::plan::{Condition, Phase, PhaseWorkload, TestPlan};
+use crate::recording::{self, MachineInfo};
+use crate::report::{escape, Assertions, TestReport};
+use crate::sampling::{SensorSample, SeriesStats};
+
+// Chart geometry in SVG user units; the charts scale to the page width
+const CHART_WIDTH: f64 = 960.0;
+const CHART_HEIGHT: f64 = 240.0;
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 22.0;
+const MARGIN_BOTTOM: f64 = 26.0;
+
+const CPU_COLOR: &str = "#d9480f";
+const GPU_COLOR: &str = "#1971c2";
+const FAN_COLORS: [&str; 6] = ["#2f9e44", "#7048e8", "#0c8599", "#c2255c", "#e67700", "#5c940d"];
+
+const STYLE: &str = "
+body { font: 14px/1.45 system-ui, -apple-system, 'Segoe UI', sans-serif; color: #212529; margin: 0 auto; max-width: 1040px; padding: 24px; }
+h1 { font-size: 24px; margin: 0 0 4px; }
+h2 { font-size: 18px; margin: 32px 0 8px; border-bottom: 1px solid #dee2e6; padding-bottom: 4px; }
+h3 { font-size: 15px; margin: 20px 0 4px; }
+table { border-collapse: collapse; width: 100%; margin: 8px 0; }
+th, td { text-align: left; padding: 4px 8px; border-bottom: 1px solid #e9ecef; vertical-align: top; }
+th { background: #f8f9fa; font-weight: 600; }
+td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; }
+.badge { display: inline-block; padding: 1px 8px; border-radius: 4px; font-weight: 600; font-size: 12px; color: #fff; }
+.pass { background: #2f9e44; }
+.fail { background: #e03131; }
+.skip { background: #868e96; }
+.verdict { font-size: 16px; padding: 3px 12px; vertical-align: middle; }
+.muted { color: #868e96; }
+.legend span { margin-right: 16px; white-space: nowrap; }
+.legend i { display: inline-block; width: 14px; height: 3px; margin-right: 5px; vertical-align: middle; }
+svg { width: 100%; height: auto; display: block; }
+svg text { font: 11px system-ui, sans-serif; fill: #495057; }
+svg .grid { stroke: #e9ecef; }
+svg .axis { stroke: #adb5bd; }
+svg .band { fill: #f1f3f5; }
+svg .throttle { fill: #ffe3e3; }
+svg .boundary { stroke: #ced4da; stroke-dasharray: 3 3; }
+svg polyline { fill: none; stroke-width: 1.6; stroke-linejoin: round; }
+pre { background: #f8f9fa; padding: 12px; overflow-x: auto; font-size: 12px; }
+";
+
+// One line on a chart; missing readings break it into segments
+struct Series {
+    label: String,
+    color: &'static str,
+    dashed: bool,
+    points: Vec<(f64, Option<f64>)>,  // (seconds since the plan started, value)
+}
+
+// A phase's span on the chart time axis
+struct Span {
+    name: String,
+    start: f64,
+    end: f64,
+}
+
+pub fn write(
+    report: &TestReport,
+    plan: &TestPlan,
+    machine: &MachineInfo,
+    cpu_usage: &[(u64, f64)],
+    path: &Path,
+) -> Result<(), String> {
+    fs::write(path, render(report, plan, machine, cpu_usage))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// A single self-contained HTML page: inline CSS and SVG charts, no scripts or external assets.
+// `cpu_usage` comes from the sensor history since plan samples don't carry it.
+pub fn render(report: &TestReport, plan: &TestPlan, machine: &MachineInfo, cpu_usage: &[(u64, f64)]) -> String {
+    let origin = report.started_at;
+    let samples: Vec<&SensorSample> = report.result.phases.iter().flat_map(|phase| &phase.samples).collect();
+    let spans: Vec<Span> = report
+        .result
+        .phases
+        .iter()
+        .map(|phase| {
+            let start = offset_secs(origin, phase.started_at);
+            Span {
+                name: phase.name.clone(),
+                start,
+                end: start + phase.elapsed_secs,
+            }
+        })
+        .collect();
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{} – thermal test report</title>\n<style>{}</style>\n</head>\n<body>\n",
+        escape(&report.plan),
+        STYLE
+    );
+
+    header(&mut html, report);
+    machine_section(&mut html, machine);
+    plan_section(&mut html, plan);
+
+    html.push_str("<h2>Sensor charts</h2>\n");
+    let channel = |id: &str, label: &str, color: &'static str, dashed: bool| Series {
+        label: label.to_string(),
+        color,
+        dashed,
+        points: samples.iter().map(|s| (offset_secs(origin, s.timestamp), s.value(id))).collect(),
+    };
+    let throttled: Vec<(f64, f64)> = report
+        .result
+        .phases
+        .iter()
+        .flat_map(|phase| &phase.throttle_events)
+        .map(|event| (offset_secs(origin, event.started_at), offset_secs(origin, event.ended_at)))
+        .collect();
+
+    chart(
+        &mut html,
+        "Temperature",
+        "°C",
+        &[channel("cpu_temp", "CPU", CPU_COLOR, false), channel("gpu_temp", "GPU", GPU_COLOR, false)],
+        &spans,
+        &[],
+        None,
+    );
+
+    let cpu_usage_series = Series {
+        label: "CPU".to_string(),
+        color: CPU_COLOR,
+        dashed: false,
+        points: cpu_usage
+            .iter()
+            .filter(|&&(t, _)| t >= origin && report.finished_at.is_none_or(|end| t <= end))
+            .map(|&(t, value)| (offset_secs(origin, t), Some(value)))
+            .collect(),
+    };
+    chart(
+        &mut html,
+        "Usage",
+        "%",
+        &[
+            cpu_usage_series,
+            channel("gpu_usage", "GPU", GPU_COLOR, false),
+            channel("cpu_speed_limit", "CPU speed limit", "#868e96", true),
+        ],
+        &spans,
+        &[],
+        Some((0.0, 100.0)),
+    );
+
+    chart(
+        &mut html,
+        "CPU frequency",
+        "MHz",
+        &[channel("cpu_freq", "Average core frequency", CPU_COLOR, false)],
+        &spans,
+        &throttled,
+        None,
+    );
+
+    let fans: BTreeSet<usize> = samples.iter().flat_map(|s| s.fans.iter().map(|&(fan, _)| fan)).collect();
+    let fan_series: Vec<Series> = fans
+        .iter()
+        .enumerate()
+        .map(|(i, fan)| {
+            let id = format!("fan_{}", fan);
+            channel(&id, &format!("Fan {}", fan), FAN_COLORS[i % FAN_COLORS.len()], false)
+        })
+        .collect();
+    chart(&mut html, "Fans", "RPM", &fan_series, &spans, &[], None);
+
+    summary_section(&mut html, report, &samples);
+    throttling_section(&mut html, report);
+    assertion_section(&mut html, report);
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn header(html: &mut String, report: &TestReport) {
+    let (class, verdict) = if report.passed { ("pass", "PASSED") } else { ("fail", "FAILED") };
+    let passed = report.assertions.iter().filter(|a| a.passed && !a.skipped).count();
+    let skipped = report.assertions.iter().filter(|a| a.skipped).count();
+    let elapsed: f64 = report.result.phases.iter().map(|phase| phase.elapsed_secs).sum();
+
+    let _ = writeln!(
+        html,
+        "<h1>{} <span class=\"badge verdict {}\">{}</span></h1>",
+        escape(&report.plan),
+        class,
+        verdict
+    );
+    let _ = writeln!(
+        html,
+        "<p class=\"muted\">Plan {} · started {} · finished {} · {} · {} of {} checks passed{}</p>",
+        label(&report.state),
+        recording::format_utc(report.started_at),
+        report.finished_at.map_or("–".to_string(), recording::format_utc),
+        duration(elapsed),
+        passed,
+        report.assertions.len(),
+        if skipped > 0 { format!(", {} skipped", skipped) } else { String::new() }
+    );
+}
+
+fn machine_section(html: &mut String, machine: &MachineInfo) {
+    let text = |parts: &[&Option<String>]| {
+        let known: Vec<&str> = parts.iter().filter_map(|part| part.as_deref()).collect();
+        if known.is_empty() {
+            "–".to_string()
+        } else {
+            escape(&known.join(" "))
+        }
+    };
+    let count = |value: Option<usize>| value.map_or("–".to_string(), |n| n.to_string());
+
+    html.push_str("<h2>Machine</h2>\n<table>\n");
+    for (name, value) in [
+        ("Host", text(&[&machine.hostname])),
+        ("Operating system", text(&[&machine.os])),
+        ("CPU", text(&[&machine.cpu_vendor, &machine.cpu_model])),
+        ("CPU cores / threads", format!("{} / {}", count(machine.cpu_cores), count(machine.cpu_threads))),
+        ("GPU", text(&[&machine.gpu_vendor, &machine.gpu_model])),
+        (
+            "Memory",
+            machine
+                .memory_bytes
+                .map_or("–".to_string(), |bytes| format!("{:.1} GiB", bytes as f64 / (1u64 << 30) as f64)),
+        ),
+    ] {
+        let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, value);
+    }
+    html.push_str("</table>\n");
+}
+
+fn plan_section(html: &mut String, plan: &TestPlan) {
+    html.push_str("<h2>Test plan</h2>\n");
+    if let Some(assertions) = &plan.assertions {
+        let _ = writeln!(html, "<p>Checked on every phase: {}</p>", escape(&assertion_list(assertions)));
+    }
+
+    html.push_str(
+        "<table>\n<tr><th>#</th><th>Phase</th><th>Workload</th><th class=\"num\">Duration</th>\
+         <th>Ends early when</th><th>Load</th><th>Phase checks</th></tr>\n",
+    );
+    for (index, phase) in plan.phases.iter().enumerate() {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            index + 1,
+            escape(&phase.name),
+            label(&phase.workload),
+            duration(phase.duration_secs as f64),
+            phase.until.as_ref().map_or("–".to_string(), |until| escape(&condition(until))),
+            escape(&load_options(phase)),
+            phase.assertions.as_ref().map_or("–".to_string(), |a| escape(&assertion_list(a))),
+        );
+    }
+    html.push_str("</table>\n");
+
+    let definition = serde_json::to_string_pretty(plan).unwrap_or_default();
+    let _ = writeln!(
+        html,
+        "<details><summary>Plan definition</summary>\n<pre>{}</pre>\n</details>",
+        escape(&definition)
+    );
+}
+
+fn summary_section(html: &mut String, report: &TestReport, samples: &[&SensorSample]) {
+    html.push_str(
+        "<h2>Summary</h2>\n<table>\n<tr><th>Phase</th><th>Workload</th><th>End</th><th class=\"num\">Duration</th>\
+         <th class=\"num\">CPU °C min / avg / max</th><th class=\"num\">GPU °C min / avg / max</th>\
+         <th class=\"num\">Peak fan RPM</th><th class=\"num\">Avg CPU MHz</th><th class=\"num\">CPU steady</th>\
+         <th class=\"num\">Throttling</th></tr>\n",
+    );
+
+    for phase in &report.result.phases {
+        let steady = phase.cpu_steady_state.as_ref().map_or("–".to_string(), |steady| {
+            format!("{:.1} °C after {}", steady.mean, duration(steady.time_to_steady_secs))
+        });
+        let throttled: f64 = phase.throttle_events.iter().map(|event| event.duration_secs()).sum();
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td class=\"num\">{}</td></tr>",
+            escape(&phase.name),
+            label(&phase.workload),
+            label(&phase.end),
+            duration(phase.elapsed_secs),
+            min_avg_max(&phase.cpu_temp),
+            min_avg_max(&phase.gpu_temp),
+            number(phase.fan_rpm.max, 0),
+            number(phase.cpu_freq.avg, 0),
+            steady,
+            if phase.throttle_events.is_empty() {
+                "–".to_string()
+            } else {
+                format!("{} × {}", phase.throttle_events.len(), duration(throttled))
+            },
+        );
+    }
+
+    // Whole-run figures over every sample
+    let mut cpu_temp = SeriesStats::default();
+    let mut gpu_temp = SeriesStats::default();
+    let mut fan_rpm = SeriesStats::default();
+    let mut cpu_freq = SeriesStats::default();
+    for sample in samples {
+        cpu_temp.add_opt(sample.cpu_temp);
+        gpu_temp.add_opt(sample.gpu_temp);
+        cpu_freq.add_opt(sample.cpu_freq_mhz);
+        for &(_, rpm) in &sample.fans {
+            fan_rpm.add(rpm);
+        }
+    }
+    let events = report.result.phases.iter().map(|phase| phase.throttle_events.len()).sum::<usize>();
+    let elapsed: f64 = report.result.phases.iter().map(|phase| phase.elapsed_secs).sum();
+    let _ = writeln!(
+        html,
+        "<tr><th>All phases</th><th></th><th>{}</th><th class=\"num\">{}</th><th class=\"num\">{}</th>\
+         <th class=\"num\">{}</th><th class=\"num\">{}</th><th class=\"num\">{}</th><th></th>\
+         <th class=\"num\">{}</th></tr>",
+        label(&report.state),
+        duration(elapsed),
+        min_avg_max(&cpu_temp),
+        min_avg_max(&gpu_temp),
+        number(fan_rpm.max, 0),
+        number(cpu_freq.avg, 0),
+        if events == 0 { "–".to_string() } else { events.to_string() },
+    );
+    html.push_str("</table>\n");
+}
+
+fn throttling_section(html: &mut String, report: &TestReport) {
+    html.push_str("<h2>Throttling events</h2>\n");
+    let events: Vec<_> = report
+        .result
+        .phases
+        .iter()
+        .flat_map(|phase| phase.throttle_events.iter().map(move |event| (&phase.name, event)))
+        .collect();
+    if events.is_empty() {
+        html.push_str("<p class=\"muted\">The OS never limited CPU speed during the run.</p>\n");
+        return;
+    }
+
+    html.push_str(
+        "<table>\n<tr><th>Phase</th><th class=\"num\">Start</th><th class=\"num\">Duration</th>\
+         <th class=\"num\">Lowest speed limit</th></tr>\n",
+    );
+    for (phase, event) in events {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{:.0}%</td></tr>",
+            escape(phase),
+            clock(offset_secs(report.started_at, event.started_at)),
+            duration(event.duration_secs()),
+            event.min_speed_limit
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn assertion_section(html: &mut String, report: &TestReport) {
+    html.push_str(
+        "<h2>Results</h2>\n<table>\n<tr><th>Result</th><th>Phase</th><th>Check</th><th>Details</th></tr>\n",
+    );
+    for assertion in &report.assertions {
+        let (class, result) = match (assertion.skipped, assertion.passed) {
+            (true, _) => ("skip", "SKIP"),
+            (false, true) => ("pass", "PASS"),
+            (false, false) => ("fail", "FAIL"),
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td><span class=\"badge {}\">{}</span></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            class,
+            result,
+            escape(&assertion.phase),
+            escape(&assertion.name),
+            escape(&assertion.message)
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+// Line chart over the whole run, with alternating phase bands and optional highlighted ranges
+fn chart(
+    html: &mut String,
+    title: &str,
+    unit: &str,
+    series: &[Series],
+    spans: &[Span],
+    highlights: &[(f64, f64)],
+    fixed_range: Option<(f64, f64)>,
+) {
+    let _ = writeln!(html, "<h3>{} ({})</h3>", escape(title), escape(unit));
+
+    let series: Vec<&Series> = series.iter().filter(|s| s.points.iter().any(|p| p.1.is_some())).collect();
+    let values = series.iter().flat_map(|s| s.points.iter().filter_map(|p| p.1));
+    let Some((min, max)) = values.fold(None, |range: Option<(f64, f64)>, v| {
+        Some(range.map_or((v, v), |(lo, hi)| (lo.min(v), hi.max(v))))
+    }) else {
+        html.push_str("<p class=\"muted\">No readings</p>\n");
+        return;
+    };
+
+    let (lo, hi, step) = match fixed_range {
+        Some((lo, hi)) => (lo, hi, (hi - lo) / 4.0),
+        None => nice_range(min, max),
+    };
+    let end = spans
+        .iter()
+        .map(|span| span.end)
+        .chain(series.iter().flat_map(|s| s.points.iter().map(|p| p.0)))
+        .fold(1.0, f64::max);
+
+    let plot_width = CHART_WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = CHART_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let x = |t: f64| MARGIN_LEFT + t.clamp(0.0, end) / end * plot_width;
+    let y = |v: f64| MARGIN_TOP + (1.0 - (v - lo) / (hi - lo)) * plot_height;
+    let bottom = MARGIN_TOP + plot_height;
+
+    let _ = writeln!(
+        html,
+        "<svg viewBox=\"0 0 {} {}\" role=\"img\" aria-label=\"{}\">",
+        CHART_WIDTH,
+        CHART_HEIGHT,
+        escape(title)
+    );
+
+    // Phase bands and names
+    for (index, span) in spans.iter().enumerate() {
+        let (x0, x1) = (x(span.start), x(span.end));
+        if index % 2 == 1 {
+            let _ = writeln!(
+                html,
+                "<rect class=\"band\" x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"/>",
+                x0,
+                MARGIN_TOP,
+                (x1 - x0).max(0.0),
+                plot_height
+            );
+        }
+        if index > 0 {
+            let _ = writeln!(
+                html,
+                "<line class=\"boundary\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>",
+                x0, MARGIN_TOP, x0, bottom
+            );
+        }
+        if x1 - x0 > 40.0 {
+            let _ = writeln!(html, "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>", x0 + 4.0, MARGIN_TOP - 7.0, escape(&span.name));
+        }
+    }
+    for &(start, stop) in highlights {
+        let (x0, x1) = (x(start), x(stop));
+        let _ = writeln!(
+            html,
+            "<rect class=\"throttle\" x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"/>",
+            x0,
+            MARGIN_TOP,
+            (x1 - x0).max(1.5),
+            plot_height
+        );
+    }
+
+    // Value grid
+    let decimals = if step >= 1.0 { 0 } else { 1 };
+    let mut value = lo;
+    while value <= hi + step / 2.0 {
+        let _ = writeln!(
+            html,
+            "<line class=\"grid\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>\
+             <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{:.*}</text>",
+            MARGIN_LEFT,
+            y(value),
+            CHART_WIDTH - MARGIN_RIGHT,
+            y(value),
+            MARGIN_LEFT - 6.0,
+            y(value) + 4.0,
+            decimals,
+            value
+        );
+        value += step;
+    }
+
+    // Time axis
+    let _ = writeln!(
+        html,
+        "<line class=\"axis\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>",
+        MARGIN_LEFT,
+        bottom,
+        CHART_WIDTH - MARGIN_RIGHT,
+        bottom
+    );
+    let tick = [10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 900.0, 1800.0, 3600.0]
+        .into_iter()
+        .find(|&tick| end / tick <= 8.0)
+        .unwrap_or((end / 8.0 / 3600.0).ceil() * 3600.0);
+    let mut t = 0.0;
+    while t <= end {
+        let _ = writeln!(
+            html,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            x(t),
+            bottom + 17.0,
+            clock(t)
+        );
+        t += tick;
+    }
+
+    // Lines, broken wherever a reading is missing
+    for s in &series {
+        let dash = if s.dashed { " stroke-dasharray=\"5 4\"" } else { "" };
+        for segment in s.points.split(|p| p.1.is_none()).filter(|segment| !segment.is_empty()) {
+            if let [(t, Some(v))] = segment {
+                let _ = writeln!(
+                    html,
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"1.8\" fill=\"{}\"/>",
+                    x(*t),
+                    y(*v),
+                    s.color
+                );
+                continue;
+            }
+            let points: Vec<String> = segment
+                .iter()
+                .filter_map(|&(t, v)| Some(format!("{:.1},{:.1}", x(t), y(v?))))
+                .collect();
+            let _ = writeln!(
+                html,
+                "<polyline points=\"{}\" stroke=\"{}\"{}/>",
+                points.join(" "),
+                s.color,
+                dash
+            );
+        }
+    }
+    html.push_str("</svg>\n<div class=\"legend\">");
+
+    for s in &series {
+        let style = if s.dashed {
+            format!("background: repeating-linear-gradient(90deg, {} 0 5px, transparent 5px 8px)", s.color)
+        } else {
+            format!("background: {}", s.color)
+        };
+        let _ = write!(html, "<span><i style=\"{}\"></i>{}</span>", style, escape(&s.label));
+    }
+    if !highlights.is_empty() {
+        html.push_str("<span><i style=\"background: #ffc9c9; height: 10px\"></i>Throttled</span>");
+    }
+    html.push_str("</div>\n");
+}
+
+// Axis bounds on round numbers with about four steps between them
+fn nice_range(min: f64, max: f64) -> (f64, f64, f64) {
+    let raw = (max - min).max(1.0) / 4.0;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 2.5, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= raw)
+        .unwrap_or(10.0 * magnitude);
+
+    let lo = (min / step).floor() * step;
+    let hi = ((max / step).ceil() * step).max(lo + step);
+    (lo, hi, step)
+}
+
+fn load_options(phase: &Phase) -> String {
+    let mut options = Vec::new();
+    if matches!(phase.workload, PhaseWorkload::Cpu | PhaseWorkload::Combined) {
+        options.push(format!("CPU {}", phase.cpu_workload.as_ref().map_or("float".to_string(), label)));
+        if let Some(threads) = phase.threads {
+            options.push(format!("{} threads", threads));
+        }
+        if let Some(load) = phase.load_percent {
+            options.push(format!("{}% load", load));
+        }
+        if let Some(cpus) = &phase.cpu_set {
+            options.push(format!("CPUs {:?}", cpus));
+        }
+    }
+    if matches!(phase.workload, PhaseWorkload::Gpu | PhaseWorkload::Combined) {
+        options.push(format!("GPU {}", phase.gpu_intensity.as_ref().map_or("high".to_string(), label)));
+        if let Some(device) = &phase.gpu_device {
+            options.push(format!("device {}", label(device)));
+        }
+    }
+    if phase.workload == PhaseWorkload::Memory {
+        if let Some(fraction) = phase.memory_fraction {
+            options.push(format!("{:.0}% of memory", fraction * 100.0));
+        }
+    }
+
+    if options.is_empty() {
+        "–".to_string()
+    } else {
+        options.join(", ")
+    }
+}
+
+fn condition(until: &Condition) -> String {
+    match until {
+        Condition::TempStable { sensor, window_secs, tolerance } => format!(
+            "{} temperature within {} °C for {}",
+            label(sensor).to_uppercase(),
+            tolerance,
+            duration(*window_secs as f64)
+        ),
+        Condition::TempAbove { sensor, celsius } => format!("{} above {} °C", label(sensor).to_uppercase(), celsius),
+        Condition::TempBelow { sensor, celsius } => format!("{} below {} °C", label(sensor).to_uppercase(), celsius),
+        Condition::SteadyState { sensor, .. } => format!("{} reaches steady state", sensor),
+    }
+}
+
+fn assertion_list(assertions: &Assertions) -> String {
+    let mut checks = Vec::new();
+    if let Some(limit) = assertions.max_cpu_temp {
+        checks.push(format!("CPU ≤ {} °C", limit));
+    }
+    if let Some(limit) = assertions.max_gpu_temp {
+        checks.push(format!("GPU ≤ {} °C", limit));
+    }
+    if assertions.no_throttling == Some(true) {
+        checks.push("no throttling".to_string());
+    }
+    if let Some(limit) = assertions.max_fan_rpm {
+        checks.push(format!("fans ≤ {} RPM", limit));
+    }
+    if let Some(floor) = assertions.min_cpu_freq_mhz {
+        checks.push(format!("CPU ≥ {} MHz average", floor));
+    }
+
+    if checks.is_empty() {
+        "–".to_string()
+    } else {
+        checks.join(", ")
+    }
+}
+
+// Enum variants as they appear in plan files; anything else as compact JSON
+fn label<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => text.replace('_', " "),
+        Ok(other) => other.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+fn min_avg_max(stats: &SeriesStats) -> String {
+    match (stats.min, stats.avg, stats.max) {
+        (Some(min), Some(avg), Some(max)) => format!("{:.1} / {:.1} / {:.1}", min, avg, max),
+        _ => "–".to_string(),
+    }
+}
+
+fn number(value: Option<f64>, decimals: usize) -> String {
+    value.map_or("–".to_string(), |value| format!("{:.*}", decimals, value))
+}
+
+fn offset_secs(origin: u64, timestamp: u64) -> f64 {
+    timestamp.saturating_sub(origin) as f64 / 1000.0
+}
+
+// "95 s", "12 min 5 s" or "1 h 3 min"
+fn duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    match secs {
+        0..60 => format!("{} s", secs),
+        60..3600 => format!("{} min {} s", secs / 60, secs % 60),
+        _ => format!("{} h {} min", secs / 3600, secs / 60 % 60),
+    }
+}
+
+// "m:ss" or "h:mm:ss" since the plan started
+fn clock(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -1,14 +1,40 @@
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
-}
-
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![greet])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
+use once_cell::sync::Lazy;
+
+// Sensors: SMC, ioreg and sysinfo readings, or the simulated machine in their place
+pub mod sensors;
+pub mod simulation;
+
+// Sampling: periodic snapshots, the in-memory history, recordings, replay and the history database
+pub mod history;
+pub mod recording;
+pub mod replay;
+pub mod sampling;
+pub mod store;
+
+// Stress tests and what runs around them: safety limits, cool-down, load control and test plans
+pub mod cooldown;
+pub mod gpu_stress;
+pub mod load_profile;
+pub mod memory_stress;
+pub mod plan;
+pub mod steady;
+pub mod stress;
+pub mod thermal_target;
+pub mod watchdog;
+
+// Reporting: plan reports, HTML pages, baseline comparisons and the Prometheus endpoint
+pub mod compare;
+pub mod html_report;
+pub mod metrics;
+pub mod report;
+
+// The shared engine the app, the command line and embedders drive
+pub mod engine;
+
+use stress::StressSlot;
+
+// The stress tests of this process, shared by the app and the command line.
+// The sensors read their state for snapshots and for the simulated machine.
+pub static STRESS_TEST: Lazy<StressSlot> = Lazy::new(|| StressSlot::new("Stress test"));
+pub static GPU_STRESS_TEST: Lazy<StressSlot> = Lazy::new(|| StressSlot::new("GPU stress test"));
+pub static MEMORY_STRESS_TEST: Lazy<StressSlot> = Lazy::new(|| StressSlot::new("Memory stress test"));
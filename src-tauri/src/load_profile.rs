@@ -0,0 +1,262 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::sampling::{self, SensorReader, SeriesStats};
+use crate::stress::{CpuRun, StressSlot};
+
+// How often each step is sampled
+const PROFILE_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// How often the runner re-checks the stop flag while waiting
+const PROFILE_STOP_TICK: Duration = Duration::from_millis(100);
+
+// A load schedule played through the duty-cycled CPU workers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum LoadProfile {
+    // Hold each load for `dwell_secs`, in order
+    Ramp {
+        #[serde(default = "default_ramp_steps")]
+        steps: Vec<u32>,
+        dwell_secs: u64,
+    },
+    // Alternate load and idle `cycles` times
+    Cycle {
+        cycles: u32,
+        load_secs: u64,
+        idle_secs: u64,
+        #[serde(default = "default_cycle_load")]
+        load_percent: u32,
+    },
+}
+
+fn default_ramp_steps() -> Vec<u32> {
+    vec![0, 25, 50, 75, 100]
+}
+
+fn default_cycle_load() -> u32 {
+    100
+}
+
+struct Step {
+    label: String,
+    cycle: Option<u32>,
+    load_percent: u32,
+    secs: u64,
+}
+
+impl LoadProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            LoadProfile::Ramp { steps, dwell_secs } => {
+                if steps.is_empty() || *dwell_secs == 0 {
+                    return Err("A ramp needs at least one step and a dwell time".to_string());
+                }
+                if steps.iter().any(|&load| load > 100) {
+                    return Err("Ramp steps must be between 0 and 100%".to_string());
+                }
+            }
+            LoadProfile::Cycle { cycles, load_secs, idle_secs, load_percent } => {
+                if *cycles == 0 || *load_secs == 0 || *idle_secs == 0 {
+                    return Err("Cycling needs at least one cycle and non-zero load and idle times".to_string());
+                }
+                if !(1..=100).contains(load_percent) {
+                    return Err(format!("Cycle load must be between 1 and 100%, got {}", load_percent));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn steps(&self) -> Vec<Step> {
+        match self {
+            LoadProfile::Ramp { steps, dwell_secs } => steps
+                .iter()
+                .map(|&load| Step {
+                    label: format!("{}%", load),
+                    cycle: None,
+                    load_percent: load,
+                    secs: *dwell_secs,
+                })
+                .collect(),
+            LoadProfile::Cycle { cycles, load_secs, idle_secs, load_percent } => (1..=*cycles)
+                .flat_map(|cycle| {
+                    [
+                        Step {
+                            label: format!("cycle {} load", cycle),
+                            cycle: Some(cycle),
+                            load_percent: *load_percent,
+                            secs: *load_secs,
+                        },
+                        Step {
+                            label: format!("cycle {} idle", cycle),
+                            cycle: Some(cycle),
+                            load_percent: 0,
+                            secs: *idle_secs,
+                        },
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+// Averages over one step of the profile
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    pub label: String,
+    pub cycle: Option<u32>,
+    pub load_percent: u32,
+    pub started_at: u64,  // Unix timestamp in milliseconds
+    pub elapsed_secs: f64,
+    pub cpu_temp: SeriesStats,
+    pub gpu_temp: SeriesStats,
+    pub fan_rpm: SeriesStats,
+    pub cpu_freq: SeriesStats,  // MHz
+    pub ops_per_sec: f64,       // Worker throughput during this step
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileStatus {
+    pub active: bool,
+    pub profile: LoadProfile,
+    pub current_step: Option<usize>,
+    pub steps: Vec<StepResult>,  // Finished steps, then the one in progress
+}
+
+#[derive(Default)]
+struct RunnerInner {
+    stop: Option<Arc<AtomicBool>>,
+    thread: Option<JoinHandle<()>>,
+    status: Option<ProfileStatus>,
+}
+
+// Plays a load profile on a running CPU stress test, then stops the test
+#[derive(Default)]
+pub struct ProfileRunner {
+    inner: Mutex<RunnerInner>,
+}
+
+impl ProfileRunner {
+    // `run` is the CPU stress test just started in `slot`; the profile only drives that run
+    pub fn start(&'static self, slot: &'static StressSlot, run: CpuRun, profile: LoadProfile, read: SensorReader) {
+        self.stop();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut inner = self.inner.lock();
+        inner.stop = Some(stop.clone());
+        inner.status = Some(ProfileStatus {
+            active: true,
+            profile: profile.clone(),
+            current_step: None,
+            steps: Vec::new(),
+        });
+        inner.thread = Some(thread::spawn(move || self.run(slot, run, profile, read, stop)));
+    }
+
+    pub fn stop(&self) {
+        let (stop, thread) = {
+            let mut inner = self.inner.lock();
+            (inner.stop.take(), inner.thread.take())
+        };
+
+        if let Some(stop) = stop {
+            stop.store(true, Ordering::SeqCst);
+        }
+        if let Some(thread) = thread {
+            let _ = thread.join();
+        }
+    }
+
+    pub fn status(&self) -> Option<ProfileStatus> {
+        self.inner.lock().status.clone()
+    }
+
+    fn run(&self, slot: &StressSlot, run: CpuRun, profile: LoadProfile, read: SensorReader, stop: Arc<AtomicBool>) {
+        let CpuRun { id, load } = run;
+        // A run started after this one ended belongs to someone else
+        let running = || !stop.load(Ordering::SeqCst) && slot.is_active(id);
+
+        'steps: for (index, step) in profile.steps().into_iter().enumerate() {
+            if !running() {
+                break;
+            }
+
+            load.store(step.load_percent, Ordering::Relaxed);
+            let ops_before = total_ops(slot);
+            let start = Instant::now();
+
+            self.update(|status| {
+                status.current_step = Some(index);
+                status.steps.push(StepResult {
+                    label: step.label.clone(),
+                    cycle: step.cycle,
+                    load_percent: step.load_percent,
+                    started_at: sampling::now_millis(),
+                    elapsed_secs: 0.0,
+                    cpu_temp: SeriesStats::default(),
+                    gpu_temp: SeriesStats::default(),
+                    fan_rpm: SeriesStats::default(),
+                    cpu_freq: SeriesStats::default(),
+                    ops_per_sec: 0.0,
+                });
+            });
+
+            let dwell = Duration::from_secs(step.secs);
+            loop {
+                let sample = read();
+                let elapsed = start.elapsed().as_secs_f64();
+                let ops_per_sec = (total_ops(slot) - ops_before).max(0.0) / elapsed.max(f64::EPSILON);
+                self.update(|status| {
+                    if let Some(result) = status.steps.last_mut() {
+                        result.elapsed_secs = elapsed;
+                        result.cpu_temp.add_opt(sample.cpu_temp);
+                        result.gpu_temp.add_opt(sample.gpu_temp);
+                        for &(_, rpm) in &sample.fans {
+                            result.fan_rpm.add(rpm);
+                        }
+                        result.cpu_freq.add_opt(sample.cpu_freq_mhz);
+                        result.ops_per_sec = ops_per_sec;
+                    }
+                });
+
+                if start.elapsed() >= dwell {
+                    break;
+                }
+                let next = Instant::now() + PROFILE_SAMPLE_INTERVAL.min(dwell.saturating_sub(start.elapsed()));
+                while Instant::now() < next {
+                    if !running() {
+                        break 'steps;
+                    }
+                    thread::sleep(PROFILE_STOP_TICK.min(next.saturating_duration_since(Instant::now())));
+                }
+            }
+        }
+
+        // The profile owns the run: once the last step is done it has completed.
+        // If the run already ended (stopped, aborted, failed) there's nothing left to finish.
+        if !stop.load(Ordering::SeqCst) {
+            slot.complete(id);
+        }
+        self.update(|status| {
+            status.active = false;
+            status.current_step = None;
+        });
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ProfileStatus)) {
+        if let Some(status) = self.inner.lock().status.as_mut() {
+            f(status);
+        }
+    }
+}
+
+// Ops completed so far in the slot's current run
+fn total_ops(slot: &StressSlot) -> f64 {
+    let status = slot.status();
+    status.ops_per_sec * status.elapsed_secs
+}
@@ -0,0 +1,265 @@
+use serde::Serialize;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use sysinfo::{System, SystemExt};
+
+use crate::sensors;
+use crate::stress::{StressSlot, StressStatus, Worker, WorkerCounters};
+use crate::watchdog::Watchdog;
+
+// Words processed between stop-flag checks (8 MiB)
+const CHUNK_WORDS: usize = 1024 * 1024;
+
+// Never take more than this fraction of RAM, so the system stays usable
+const MAX_FRACTION: f64 = 0.9;
+
+// Fraction of RAM used when none is given
+const DEFAULT_FRACTION: f64 = 0.5;
+
+// Validated settings for one memory stress run
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryStressOptions {
+    pub total_bytes: u64,
+    pub threads: usize,
+}
+
+impl MemoryStressOptions {
+    pub fn new(fraction: Option<f64>, threads: Option<usize>) -> Result<Self, String> {
+        Self::validate(fraction, threads)?;
+        let fraction = fraction.unwrap_or(DEFAULT_FRACTION);
+
+        let mut sys = System::new();
+        sys.refresh_memory();
+        let total_bytes = (sys.total_memory() as f64 * fraction) as u64;
+        if total_bytes > sys.available_memory() {
+            return Err(format!(
+                "Requested {:.1} GiB but only {:.1} GiB is available",
+                gib(total_bytes),
+                gib(sys.available_memory())
+            ));
+        }
+
+        let threads = threads.unwrap_or_else(sensors::logical_cpus);
+        Ok(MemoryStressOptions { total_bytes, threads })
+    }
+
+    // Check the settings that don't depend on this machine's free memory, e.g. when parsing a plan
+    pub fn validate(fraction: Option<f64>, threads: Option<usize>) -> Result<(), String> {
+        let fraction = fraction.unwrap_or(DEFAULT_FRACTION);
+        if !(fraction > 0.0 && fraction <= MAX_FRACTION) {
+            return Err(format!("Memory fraction must be between 0 and {}, got {}", MAX_FRACTION, fraction));
+        }
+        if threads == Some(0) {
+            return Err("Thread count must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn label(&self) -> String {
+        format!("memory ({:.1} GiB)", gib(self.total_bytes))
+    }
+}
+
+fn gib(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+}
+
+// Memory stress state with the bandwidth spelled out
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryStressStatus {
+    #[serde(flatten)]
+    pub status: StressStatus,
+    pub gb_per_sec: f64,      // Achieved bandwidth over all threads (reads + writes)
+    pub failed_passes: u64,   // Write/read/copy passes in which some word did not read back as written
+}
+
+impl From<StressStatus> for MemoryStressStatus {
+    fn from(status: StressStatus) -> Self {
+        // Memory workers count bytes moved as their ops
+        MemoryStressStatus {
+            gb_per_sec: status.ops_per_sec / 1e9,
+            failed_passes: status.mismatches,
+            status,
+        }
+    }
+}
+
+// Start a memory stress run in `slot`; returns the run id
+pub fn start(
+    slot: &'static StressSlot,
+    options: &MemoryStressOptions,
+    duration: Option<Duration>,
+    watchdog: Option<Watchdog>,
+) -> Result<u64, String> {
+    slot.start(&options.label(), options.threads, duration, watchdog, |running| {
+        Ok(spawn_workers(options, running))
+    })
+}
+
+// Spawn the memory workers. Each thread allocates its own share of the buffer.
+pub fn spawn_workers(options: &MemoryStressOptions, running: Arc<AtomicBool>) -> Vec<Worker> {
+    let words_per_thread = (options.total_bytes / options.threads as u64) as usize / mem::size_of::<u64>();
+
+    (0..options.threads)
+        .map(|index| {
+            let running = running.clone();
+            Worker::spawn(move |counters| {
+                run_worker(index, words_per_thread, &running, counters);
+            })
+        })
+        .collect()
+}
+
+fn run_worker(index: usize, words: usize, running: &AtomicBool, counters: &WorkerCounters) {
+    // Round down to an even length so the buffer splits into two halves for the copy pattern
+    let words = words & !1;
+    let mut buffer: Vec<u64> = Vec::new();
+    if buffer.try_reserve_exact(words).is_err() {
+        counters.fail(format!("Memory stress thread {} failed to allocate {:.1} GiB", index, gib((words * mem::size_of::<u64>()) as u64)));
+        return;
+    }
+    buffer.resize(words, 0);
+
+    let mut pass = 0u64;
+    while running.load(Ordering::Relaxed) {
+        let seed = pass ^ ((index as u64) << 32);
+        let Some(errors) = run_pass(&mut buffer, pass, seed, running, counters) else {
+            return;
+        };
+
+        if errors > 0 {
+            println!("Memory stress thread {} found {} bad words in pass {}", index, errors, pass);
+        }
+        counters.record(0, errors == 0);
+        pass += 1;
+    }
+}
+
+// One write, read and copy pass over the buffer. Returns the words that didn't read back
+// as written, or None when stopped part way.
+fn run_pass(buffer: &mut [u64], pass: u64, seed: u64, running: &AtomicBool, counters: &WorkerCounters) -> Option<u64> {
+    // Write: fill the whole buffer with this pass's pattern
+    for (chunk_index, chunk) in buffer.chunks_mut(CHUNK_WORDS).enumerate() {
+        if !running.load(Ordering::Relaxed) {
+            return None;
+        }
+        fill(chunk, seed, chunk_index * CHUNK_WORDS);
+        counters.ops.fetch_add(chunk_bytes(chunk.len()), Ordering::Relaxed);
+    }
+
+    // Read: check every word against the pattern
+    let mut errors = 0u64;
+    for (chunk_index, chunk) in buffer.chunks(CHUNK_WORDS).enumerate() {
+        if !running.load(Ordering::Relaxed) {
+            return None;
+        }
+        errors += bad_words(chunk, seed, chunk_index * CHUNK_WORDS);
+        counters.ops.fetch_add(chunk_bytes(chunk.len()), Ordering::Relaxed);
+    }
+
+    // Copy: move one half onto the other, alternating direction each pass, then check the copy
+    let half = buffer.len() / 2;
+    let (low, high) = buffer.split_at_mut(half);
+    let (src, dst, src_base) = if pass.is_multiple_of(2) { (&*low, high, 0) } else { (&*high, low, half) };
+    for (chunk_index, (dst_chunk, src_chunk)) in dst.chunks_mut(CHUNK_WORDS).zip(src.chunks(CHUNK_WORDS)).enumerate() {
+        if !running.load(Ordering::Relaxed) {
+            return None;
+        }
+        errors += copy_checked(dst_chunk, src_chunk, seed, src_base + chunk_index * CHUNK_WORDS);
+        // Copy reads and writes each word, then the check reads it again
+        counters.ops.fetch_add(3 * chunk_bytes(dst_chunk.len()), Ordering::Relaxed);
+    }
+
+    Some(errors)
+}
+
+// Write the pattern into a chunk starting at word `base`
+fn fill(chunk: &mut [u64], seed: u64, base: usize) {
+    for (offset, word) in chunk.iter_mut().enumerate() {
+        *word = pattern(seed, base + offset);
+    }
+}
+
+// Words of a chunk starting at word `base` that don't hold the pattern
+fn bad_words(chunk: &[u64], seed: u64, base: usize) -> u64 {
+    chunk.iter().enumerate()
+        .filter(|&(offset, &word)| word != pattern(seed, base + offset))
+        .count() as u64
+}
+
+// Copy `src` (words from `src_base` on) into `dst` and count the copied words that don't hold the
+// pattern, so corruption on either side of the copy shows up
+fn copy_checked(dst: &mut [u64], src: &[u64], seed: u64, src_base: usize) -> u64 {
+    dst.copy_from_slice(src);
+    bad_words(dst, seed, src_base)
+}
+
+// Value written to word `index` during a pass; differs between passes and neighbouring words
+fn pattern(seed: u64, index: usize) -> u64 {
+    (index as u64 ^ seed.rotate_left(17)).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ seed
+}
+
+fn chunk_bytes(words: usize) -> u64 {
+    (words * mem::size_of::<u64>()) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_reads_back_until_corrupted() {
+        let mut buffer = vec![0u64; 1000];
+        fill(&mut buffer, 7, 0);
+        assert_eq!(bad_words(&buffer, 7, 0), 0);
+        // Another pass's pattern, or the right pattern at the wrong offset, doesn't match
+        assert_eq!(bad_words(&buffer, 8, 0), 1000);
+        assert_eq!(bad_words(&buffer[1..], 7, 0), 999);
+
+        buffer[10] ^= 1 << 3;
+        buffer[500] = 0;
+        assert_eq!(bad_words(&buffer, 7, 0), 2);
+    }
+
+    #[test]
+    fn copy_check_catches_corrupted_source() {
+        let mut buffer = vec![0u64; 64];
+        fill(&mut buffer, 3, 0);
+        let (low, high) = buffer.split_at_mut(32);
+        assert_eq!(copy_checked(high, low, 3, 0), 0);
+        assert_eq!(high, low);
+
+        // A word that went bad in the source half after it was checked is caught in the copy
+        low[5] ^= 1;
+        assert_eq!(copy_checked(high, low, 3, 0), 1);
+    }
+
+    #[test]
+    fn clean_passes_find_no_errors() {
+        let running = AtomicBool::new(true);
+        let counters = WorkerCounters::default();
+        // Not a whole number of chunks, and both copy directions
+        let mut buffer = vec![0u64; CHUNK_WORDS + 10];
+        for pass in 0..2 {
+            assert_eq!(run_pass(&mut buffer, pass, pass, &running, &counters), Some(0));
+        }
+        // Write, read, then copy and check of half the buffer, per pass
+        let bytes = chunk_bytes(buffer.len());
+        assert_eq!(counters.ops.load(Ordering::Relaxed), 2 * (2 * bytes + 3 * bytes / 2));
+
+        running.store(false, Ordering::Relaxed);
+        assert_eq!(run_pass(&mut buffer, 2, 2, &running, &counters), None);
+    }
+
+    #[test]
+    fn validate_checks_fraction_and_threads() {
+        assert!(MemoryStressOptions::validate(None, None).is_ok());
+        assert!(MemoryStressOptions::validate(Some(0.9), Some(2)).is_ok());
+        for fraction in [0.0, -0.5, 0.95, f64::NAN] {
+            assert!(MemoryStressOptions::validate(Some(fraction), None).is_err(), "{}", fraction);
+        }
+        assert!(MemoryStressOptions::validate(None, Some(0)).is_err());
+    }
+}
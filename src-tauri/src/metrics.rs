@@ -0,0 +1,292 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::sampling::{self, Snapshot};
+use crate::stress::{StressState, StressStatus};
+
+// Local only unless another address is given; 9100 is taken by the node exporter
+pub const DEFAULT_BIND: &str = "127.0.0.1:9101";
+
+// Enables the endpoint at startup when no --metrics flag is given, e.g. TEMPDETECT_METRICS=0.0.0.0:9101
+pub const METRICS_ENV: &str = "TEMPDETECT_METRICS";
+
+// Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+// One metric family per channel kind: (kind, metric name, help, scale from the snapshot unit)
+const FAMILIES: [(&str, &str, &str, f64); 8] = [
+    ("temperature", "tempdetect_temperature_celsius", "Sensor temperature in degrees Celsius.", 1.0),
+    ("fan", "tempdetect_fan_speed_rpm", "Fan speed in revolutions per minute.", 1.0),
+    ("usage", "tempdetect_usage_percent", "CPU and GPU utilization in percent; cpu_usage_N is logical CPU N.", 1.0),
+    ("frequency", "tempdetect_frequency_hertz", "Clock frequency in hertz.", 1e6),
+    ("throttle", "tempdetect_speed_limit_percent", "OS CPU speed limit in percent, below 100 while throttled.", 1.0),
+    ("stress_state", "tempdetect_stress_running", "1 while the stress test is running.", 1.0),
+    ("throughput", "tempdetect_stress_ops_per_second", "Stress test throughput in operations per second.", 1.0),
+    ("other", "tempdetect_sensor_value", "Other sensor readings.", 1.0),
+];
+
+const STRESS_STATES: [StressState; 6] = [
+    StressState::Idle,
+    StressState::Running,
+    StressState::Completed,
+    StressState::Stopped,
+    StressState::Aborted,
+    StressState::Failed,
+];
+
+// Bind address from `--metrics[=ADDR]` or TEMPDETECT_METRICS, if the endpoint was asked for at startup
+pub fn requested(args: &[String]) -> Option<String> {
+    let flag = args.iter().find_map(|arg| match arg.as_str() {
+        "--metrics" => Some(String::new()),
+        _ => arg.strip_prefix("--metrics=").map(str::to_string),
+    });
+    let value = match flag {
+        Some(value) => value,
+        None => std::env::var(METRICS_ENV).ok().filter(|value| !matches!(value.trim(), "" | "0" | "false"))?,
+    };
+    match value.trim() {
+        "" | "1" | "true" => Some(DEFAULT_BIND.to_string()),
+        bind => Some(bind.to_string()),
+    }
+}
+
+// Renders the current metrics for each scrape
+pub type MetricsSource = Arc<dyn Fn() -> String + Send + Sync>;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsStatus {
+    pub active: bool,
+    pub address: String,  // Where the listener is bound, with the actual port when 0 was asked for
+    pub started_at: u64,  // Unix timestamp in milliseconds
+    pub scrapes: u64,
+}
+
+// Serves `/metrics` over HTTP from its own thread while started
+#[derive(Default)]
+pub struct MetricsExporter {
+    running: Mutex<Option<RunningExporter>>,
+    last: Mutex<Option<MetricsStatus>>,
+}
+
+struct RunningExporter {
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+    status: Arc<Mutex<MetricsStatus>>,
+}
+
+impl MetricsExporter {
+    pub fn start(&self, bind: &str, source: MetricsSource) -> Result<MetricsStatus, String> {
+        let mut running = self.running.lock();
+        if running.is_some() {
+            return Err("The metrics endpoint is already running".to_string());
+        }
+
+        let server = Arc::new(Server::http(bind).map_err(|e| format!("Failed to listen on {}: {}", bind, e))?);
+        let address = server.server_addr().to_ip().map_or(bind.to_string(), |addr| addr.to_string());
+        let status = Arc::new(Mutex::new(MetricsStatus {
+            active: true,
+            address,
+            started_at: sampling::now_millis(),
+            scrapes: 0,
+        }));
+
+        let thread = thread::spawn({
+            let server = server.clone();
+            let status = status.clone();
+            // Ends once `stop` unblocks the server
+            move || {
+                for request in server.incoming_requests() {
+                    let path = request.url().split('?').next().unwrap_or("");
+                    let response = match (request.method(), path) {
+                        (Method::Get | Method::Head, "/metrics") => {
+                            status.lock().scrapes += 1;
+                            Response::from_string(source()).with_header(header("Content-Type", CONTENT_TYPE))
+                        }
+                        (Method::Get | Method::Head, "/") => Response::from_string("tempdetect exporter; metrics are at /metrics\n"),
+                        (Method::Get | Method::Head, _) => Response::from_string("Not found\n").with_status_code(404),
+                        _ => Response::from_string("Method not allowed\n")
+                            .with_status_code(405)
+                            .with_header(header("Allow", "GET, HEAD")),
+                    };
+                    let _ = request.respond(response);
+                }
+            }
+        });
+
+        let current = status.lock().clone();
+        *running = Some(RunningExporter { server, thread, status });
+        Ok(current)
+    }
+
+    pub fn stop(&self) -> Option<MetricsStatus> {
+        let running = self.running.lock().take()?;
+        running.server.unblock();
+        let _ = running.thread.join();
+
+        let mut status = running.status.lock().clone();
+        status.active = false;
+        *self.last.lock() = Some(status.clone());
+        Some(status)
+    }
+
+    pub fn status(&self) -> Option<MetricsStatus> {
+        match self.running.lock().as_ref() {
+            Some(running) => Some(running.status.lock().clone()),
+            None => self.last.lock().clone(),
+        }
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("static header is valid")
+}
+
+// Metrics for one scrape: every channel of the snapshot, labelled with its sensor id, kind and
+// readable name, then the state of each stress test (`tests` pairs a name like "cpu" with its status).
+pub fn render(snapshot: Option<&Snapshot>, tests: &[(&str, StressStatus)]) -> String {
+    let mut out = String::new();
+
+    if let Some(snapshot) = snapshot {
+        for (kind, name, help, scale) in FAMILIES {
+            let mut channels: Vec<(&String, f64)> = snapshot
+                .values
+                .iter()
+                .filter(|(id, value)| sampling::channel_kind(id).0 == kind && value.is_finite())
+                .map(|(id, &value)| (id, value))
+                .collect();
+            if channels.is_empty() {
+                continue;
+            }
+            channels.sort_by_key(|(id, _)| channel_order(id));
+
+            family(&mut out, name, help);
+            for (id, value) in channels {
+                let labels = [("sensor", id.as_str()), ("kind", kind), ("label", &sampling::channel_label(id))];
+                sample(&mut out, name, &labels, value * scale);
+            }
+        }
+
+        let name = "tempdetect_last_sample_timestamp_seconds";
+        family(&mut out, name, "Unix time of the sensor readings above.");
+        sample(&mut out, name, &[], snapshot.timestamp as f64 / 1000.0);
+    }
+
+    if !tests.is_empty() {
+        let name = "tempdetect_stress_state";
+        family(&mut out, name, "Stress test state; 1 for the current state of each test.");
+        for (test, status) in tests {
+            for state in STRESS_STATES {
+                let value = if status.state == state { 1.0 } else { 0.0 };
+                sample(&mut out, name, &[("test", test), ("state", &state_label(state))], value);
+            }
+        }
+
+        let name = "tempdetect_stress_elapsed_seconds";
+        family(&mut out, name, "Run time of the current or last run of each stress test.");
+        for (test, status) in tests {
+            sample(&mut out, name, &[("test", test)], status.elapsed_secs);
+        }
+    }
+
+    out
+}
+
+fn family(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    let labels: Vec<String> = labels.iter().map(|(key, value)| format!("{}=\"{}\"", key, escape(value))).collect();
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+    } else {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), value);
+    }
+}
+
+// Label values escape backslashes, quotes and newlines
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// Numbered channels in numeric order, so core_temp_10 follows core_temp_9
+fn channel_order(id: &str) -> (String, usize) {
+    match id.rsplit_once('_').and_then(|(name, index)| Some((name, index.parse::<usize>().ok()?))) {
+        Some((name, index)) => (name.to_string(), index + 1),
+        None => (id.to_string(), 0),
+    }
+}
+
+fn state_label(state: StressState) -> String {
+    match serde_json::to_value(state) {
+        Ok(serde_json::Value::String(label)) => label,
+        _ => format!("{:?}", state).to_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stress::StressSlot;
+
+    #[test]
+    fn renders_one_family_per_kind() {
+        let snapshot = Snapshot {
+            timestamp: 1_700_000_000_500,
+            values: [
+                ("cpu_temp", 61.5),
+                ("core_temp_10", 63.0),
+                ("core_temp_9", 62.0),
+                ("cpu_freq", 3200.0),
+                ("fan_0", 1800.0),
+                ("gpu_temp", f64::NAN),
+            ]
+            .iter()
+            .map(|&(id, value)| (id.to_string(), value))
+            .collect(),
+        };
+        let text = render(Some(&snapshot), &[]);
+
+        assert!(text.contains("# TYPE tempdetect_temperature_celsius gauge\n"));
+        assert!(text.contains("tempdetect_temperature_celsius{sensor=\"cpu_temp\",kind=\"temperature\",label=\"CPU\"} 61.5\n"));
+        assert!(text.contains("tempdetect_frequency_hertz{sensor=\"cpu_freq\",kind=\"frequency\",label=\"CPU\"} 3200000000\n"));
+        assert!(text.contains("tempdetect_fan_speed_rpm{sensor=\"fan_0\",kind=\"fan\",label=\"Fan 0\"} 1800\n"));
+        assert!(text.contains("tempdetect_last_sample_timestamp_seconds 1700000000.5\n"));
+        // NaN readings are left out, and numbered channels sort numerically
+        assert!(!text.contains("gpu_temp"));
+        assert!(text.find("core_temp_9").unwrap() < text.find("core_temp_10").unwrap());
+        // Kinds without channels get no family
+        assert!(!text.contains("tempdetect_usage_percent"));
+        assert!(!text.contains("tempdetect_stress_state"));
+    }
+
+    #[test]
+    fn renders_stress_state() {
+        let idle = StressSlot::new("CPU stress test").status();
+        let text = render(None, &[("cpu", idle)]);
+
+        assert!(text.contains("tempdetect_stress_state{test=\"cpu\",state=\"idle\"} 1\n"));
+        assert!(text.contains("tempdetect_stress_state{test=\"cpu\",state=\"running\"} 0\n"));
+        assert_eq!(text.matches("tempdetect_stress_state{").count(), STRESS_STATES.len());
+        assert!(text.contains("tempdetect_stress_elapsed_seconds{test=\"cpu\"} 0\n"));
+        assert!(!text.contains("tempdetect_last_sample_timestamp_seconds"));
+        assert_eq!(render(None, &[]), "");
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[test]
+    fn requested_address() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(requested(&args(&["app", "--metrics"])).as_deref(), Some(DEFAULT_BIND));
+        assert_eq!(requested(&args(&["app", "--metrics=0.0.0.0:9200"])).as_deref(), Some("0.0.0.0:9200"));
+    }
+}
@@ -0,0 +1,671 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::gpu_stress::{self, GpuIntensity, GpuSelector};
+use crate::memory_stress::{self, MemoryStressOptions};
+use crate::report::{self, Assertions, TestReport};
+use crate::sampling::{self, SensorReader, SensorSample, SeriesStats, ThrottleEvent};
+use crate::steady::{self, SteadyState, SteadyStateConfig, SteadyStateDetector};
+use crate::stress::{self, CpuStressOptions, CpuWorkload, StressSlot, StressState, StressStatus};
+use crate::watchdog::{SafetyLimits, Watchdog};
+
+// How often the runner samples the sensors and checks phase conditions
+const PLAN_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// How often the runner re-checks the stop flag while waiting
+const PLAN_STOP_TICK: Duration = Duration::from_millis(100);
+
+// A scripted sequence of load phases, loaded from TOML or JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestPlan {
+    pub name: String,
+    pub safety: Option<SafetyLimits>,  // Defaults apply when omitted
+    pub assertions: Option<Assertions>,  // Checked against every phase
+    pub steady_state: Option<SteadyStateConfig>,  // Settings for the per-phase steady-state analysis
+    pub phases: Vec<Phase>,
+}
+
+// What a phase runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseWorkload {
+    Idle,
+    Cpu,
+    Gpu,
+    Combined,  // CPU and GPU together
+    Memory,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Phase {
+    pub name: String,
+    pub workload: PhaseWorkload,
+    pub duration_secs: u64,  // Phase length, or the timeout when `until` is set
+    pub until: Option<Condition>,
+    pub assertions: Option<Assertions>,  // Overrides the plan's assertions for this phase
+
+    // CPU options (cpu and combined phases)
+    pub cpu_workload: Option<CpuWorkload>,
+    pub threads: Option<usize>,
+    pub load_percent: Option<u32>,
+    pub cpu_set: Option<Vec<usize>>,
+
+    // GPU options (gpu and combined phases)
+    pub gpu_device: Option<GpuSelector>,
+    pub gpu_intensity: Option<GpuIntensity>,
+
+    // Memory options (memory phases)
+    pub memory_fraction: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TempSensor {
+    Cpu,
+    Gpu,
+}
+
+impl TempSensor {
+    fn read(&self, sample: &SensorSample) -> Option<f64> {
+        match self {
+            TempSensor::Cpu => sample.cpu_temp,
+            TempSensor::Gpu => sample.gpu_temp,
+        }
+    }
+}
+
+// Ends a phase early once it holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    // Temperature stayed within `tolerance` °C over the last `window_secs`
+    TempStable { sensor: TempSensor, window_secs: u64, tolerance: f64 },
+    TempAbove { sensor: TempSensor, celsius: f64 },
+    TempBelow { sensor: TempSensor, celsius: f64 },
+    // Any sensor channel (see `SensorSample::value`) reached steady state
+    SteadyState {
+        sensor: String,
+        #[serde(flatten)]
+        config: SteadyStateConfig,
+    },
+}
+
+impl TestPlan {
+    // Parse a plan; JSON if the text starts with `{`, TOML otherwise
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let plan: TestPlan = if text.trim_start().starts_with('{') {
+            serde_json::from_str(text).map_err(|e| format!("Invalid JSON test plan: {}", e))?
+        } else {
+            toml::from_str(text).map_err(|e| format!("Invalid TOML test plan: {}", e))?
+        };
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read test plan {}: {}", path.display(), e))?;
+        Self::parse(&text)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.phases.is_empty() {
+            return Err("Test plan has no phases".to_string());
+        }
+        if let Some(config) = &self.steady_state {
+            config.validate().map_err(|e| format!("Test plan steady_state: {}", e))?;
+        }
+
+        for phase in &self.phases {
+            if phase.duration_secs == 0 {
+                return Err(format!("Phase '{}' needs a duration", phase.name));
+            }
+            if let Some(Condition::TempStable { window_secs, tolerance, .. }) = &phase.until {
+                if *window_secs == 0 || *tolerance <= 0.0 {
+                    return Err(format!("Phase '{}' has an invalid temp_stable condition", phase.name));
+                }
+            }
+            if let Some(Condition::SteadyState { sensor, config }) = &phase.until {
+                if !sampling::is_channel(sensor) || config.validate().is_err() {
+                    return Err(format!("Phase '{}' has an invalid steady_state condition", phase.name));
+                }
+            }
+            if matches!(phase.workload, PhaseWorkload::Cpu | PhaseWorkload::Combined) {
+                phase.cpu_options().map_err(|e| format!("Phase '{}': {}", phase.name, e))?;
+            }
+            if phase.workload == PhaseWorkload::Memory {
+                MemoryStressOptions::validate(phase.memory_fraction, phase.threads)
+                    .map_err(|e| format!("Phase '{}': {}", phase.name, e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Phase {
+    fn cpu_options(&self) -> Result<CpuStressOptions, String> {
+        CpuStressOptions::new(
+            self.cpu_workload.unwrap_or(CpuWorkload::Float),
+            self.threads,
+            self.load_percent,
+            self.cpu_set.clone(),
+        )
+    }
+}
+
+// How a phase ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseEnd {
+    DurationElapsed,
+    ConditionMet,
+    TimedOut,  // The `until` condition never held
+    Stopped,
+    Aborted,   // Safety watchdog
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseResult {
+    pub name: String,
+    pub workload: PhaseWorkload,
+    pub started_at: u64,  // Unix timestamp in milliseconds
+    pub elapsed_secs: f64,
+    pub end: PhaseEnd,
+    pub message: Option<String>,  // Abort reason or error
+    pub cpu_temp: SeriesStats,
+    pub gpu_temp: SeriesStats,
+    pub fan_rpm: SeriesStats,
+    pub cpu_freq: SeriesStats,  // MHz
+    pub throttle_events: Vec<ThrottleEvent>,
+    pub cpu_steady_state: Option<SteadyState>,
+    pub gpu_steady_state: Option<SteadyState>,
+    pub cpu_stress: Option<StressStatus>,
+    pub gpu_stress: Option<StressStatus>,
+    pub memory_stress: Option<StressStatus>,
+    pub samples: Vec<SensorSample>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanState {
+    Running,
+    Completed,
+    Stopped,
+    Aborted,
+    Failed,
+}
+
+// Combined result of a whole plan
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanResult {
+    pub name: String,
+    pub state: PlanState,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub phases: Vec<PhaseResult>,
+}
+
+// Progress notifications sent while a plan runs
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PlanEvent {
+    PhaseStarted { plan: String, index: usize, name: String },
+    PhaseFinished { plan: String, index: usize, result: Box<PhaseResult> },
+    PlanFinished { result: PlanResult },
+}
+
+// Everything the runner drives: the stress slots, the sensors and the event sink
+#[derive(Clone)]
+pub struct PlanContext {
+    pub cpu: &'static StressSlot,
+    pub gpu: &'static StressSlot,
+    pub memory: &'static StressSlot,
+    pub read: SensorReader,
+    pub emit: Arc<dyn Fn(PlanEvent) + Send + Sync>,
+}
+
+#[derive(Default)]
+struct RunnerInner {
+    stop: Option<Arc<AtomicBool>>,
+    thread: Option<JoinHandle<()>>,
+    plan: Option<TestPlan>,
+    result: Option<PlanResult>,
+}
+
+fn is_running(inner: &RunnerInner) -> bool {
+    inner.result.as_ref().is_some_and(|result| result.state == PlanState::Running)
+}
+
+// Runs one test plan at a time on a background thread
+#[derive(Default)]
+pub struct PlanRunner {
+    inner: Mutex<RunnerInner>,
+}
+
+impl PlanRunner {
+    // `setup` runs once the runner is known to be free and before the plan thread starts,
+    // with the runner locked so no other plan can start in between
+    pub fn start(
+        &'static self,
+        plan: TestPlan,
+        ctx: PlanContext,
+        setup: impl FnOnce() -> Result<(), String>,
+    ) -> Result<(), String> {
+        let mut inner = self.inner.lock();
+        if is_running(&inner) {
+            return Err("A test plan is already running".to_string());
+        }
+        setup()?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        inner.stop = Some(stop.clone());
+        inner.plan = Some(plan.clone());
+        inner.result = Some(PlanResult {
+            name: plan.name.clone(),
+            state: PlanState::Running,
+            started_at: sampling::now_millis(),
+            finished_at: None,
+            phases: Vec::new(),
+        });
+        inner.thread = Some(thread::spawn(move || self.run(plan, ctx, stop)));
+
+        Ok(())
+    }
+
+    // Stop the running plan and wait for it to wind down its current phase
+    pub fn stop(&self) {
+        let (stop, thread) = {
+            let mut inner = self.inner.lock();
+            (inner.stop.take(), inner.thread.take())
+        };
+
+        if let Some(stop) = stop {
+            stop.store(true, Ordering::SeqCst);
+        }
+        if let Some(thread) = thread {
+            let _ = thread.join();
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        is_running(&self.inner.lock())
+    }
+
+    // Result of the running or last plan
+    pub fn result(&self) -> Option<PlanResult> {
+        self.inner.lock().result.clone()
+    }
+
+    // Definition of the running or last plan
+    pub fn plan(&self) -> Option<TestPlan> {
+        self.inner.lock().plan.clone()
+    }
+
+    // Pass/fail report of the last plan, once it has finished
+    pub fn report(&self) -> Option<TestReport> {
+        let inner = self.inner.lock();
+        match (&inner.plan, &inner.result) {
+            (Some(plan), Some(result)) if result.state != PlanState::Running => Some(report::evaluate(plan, result)),
+            _ => None,
+        }
+    }
+
+    fn run(&self, plan: TestPlan, ctx: PlanContext, stop: Arc<AtomicBool>) {
+        let mut state = PlanState::Completed;
+
+        for (index, phase) in plan.phases.iter().enumerate() {
+            (ctx.emit)(PlanEvent::PhaseStarted {
+                plan: plan.name.clone(),
+                index,
+                name: phase.name.clone(),
+            });
+
+            let result = run_phase(&plan, phase, &ctx, &stop);
+            let end = result.end;
+
+            if let Some(plan_result) = self.inner.lock().result.as_mut() {
+                plan_result.phases.push(result.clone());
+            }
+            (ctx.emit)(PlanEvent::PhaseFinished { plan: plan.name.clone(), index, result: Box::new(result) });
+
+            state = match end {
+                PhaseEnd::Stopped => PlanState::Stopped,
+                PhaseEnd::Aborted => PlanState::Aborted,
+                PhaseEnd::Failed => PlanState::Failed,
+                _ => continue,
+            };
+            break;
+        }
+
+        let result = {
+            let mut inner = self.inner.lock();
+            inner.stop = None;
+            inner.result.as_mut().map(|result| {
+                result.state = state;
+                result.finished_at = Some(sampling::now_millis());
+                result.clone()
+            })
+        };
+        if let Some(result) = result {
+            (ctx.emit)(PlanEvent::PlanFinished { result });
+        }
+    }
+}
+
+fn run_phase(plan: &TestPlan, phase: &Phase, ctx: &PlanContext, stop: &AtomicBool) -> PhaseResult {
+    let mut result = PhaseResult {
+        name: phase.name.clone(),
+        workload: phase.workload,
+        started_at: sampling::now_millis(),
+        elapsed_secs: 0.0,
+        end: PhaseEnd::DurationElapsed,
+        message: None,
+        cpu_temp: SeriesStats::default(),
+        gpu_temp: SeriesStats::default(),
+        fan_rpm: SeriesStats::default(),
+        cpu_freq: SeriesStats::default(),
+        throttle_events: Vec::new(),
+        cpu_steady_state: None,
+        gpu_steady_state: None,
+        cpu_stress: None,
+        gpu_stress: None,
+        memory_stress: None,
+        samples: Vec::new(),
+    };
+
+    let watchdog = Watchdog {
+        limits: plan.safety.clone().unwrap_or_default(),
+        read: ctx.read.clone(),
+    };
+    let slots = match start_load(phase, ctx, watchdog) {
+        Ok(slots) => slots,
+        Err(e) => {
+            result.end = PhaseEnd::Failed;
+            result.message = Some(e);
+            return result;
+        }
+    };
+
+    let start = Instant::now();
+    let duration = Duration::from_secs(phase.duration_secs);
+    let mut condition = phase.until.clone().map(ConditionTracker::new);
+
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            result.end = PhaseEnd::Stopped;
+            break;
+        }
+
+        let sample = (ctx.read)();
+        result.cpu_temp.add_opt(sample.cpu_temp);
+        result.gpu_temp.add_opt(sample.gpu_temp);
+        for &(_, rpm) in &sample.fans {
+            result.fan_rpm.add(rpm);
+        }
+        result.cpu_freq.add_opt(sample.cpu_freq_mhz);
+
+        // The watchdog or a failing worker ends the phase, and the plan, early
+        if let Some((end, message)) = slots.iter().find_map(|slot| ended_early(&slot.status())) {
+            result.end = end;
+            result.message = message;
+            break;
+        }
+
+        let elapsed = start.elapsed();
+        let met = condition.as_mut().is_some_and(|condition| condition.update(&sample, elapsed));
+        result.samples.push(sample);
+
+        if met {
+            result.end = PhaseEnd::ConditionMet;
+            break;
+        }
+        if elapsed >= duration {
+            result.end = if condition.is_some() { PhaseEnd::TimedOut } else { PhaseEnd::DurationElapsed };
+            break;
+        }
+
+        wait(PLAN_SAMPLE_INTERVAL, stop);
+    }
+
+    result.elapsed_secs = start.elapsed().as_secs_f64();
+    result.throttle_events = sampling::throttle_events(&result.samples);
+    let config = plan.steady_state.clone().unwrap_or_default();
+    result.cpu_steady_state = steady::analyze(&steady::series(&result.samples, "cpu_temp"), &config);
+    result.gpu_steady_state = steady::analyze(&steady::series(&result.samples, "gpu_temp"), &config);
+
+    // Stopping joins the workers, so the final counters are complete
+    for slot in slots {
+        let status = slot.stop().unwrap_or_else(|| slot.status());
+        if std::ptr::eq(slot, ctx.cpu) {
+            result.cpu_stress = Some(status);
+        } else if std::ptr::eq(slot, ctx.gpu) {
+            result.gpu_stress = Some(status);
+        } else {
+            result.memory_stress = Some(status);
+        }
+    }
+
+    result
+}
+
+// Start the stress tests a phase needs; returns the slots to stop afterwards
+fn start_load(phase: &Phase, ctx: &PlanContext, watchdog: Watchdog) -> Result<Vec<&'static StressSlot>, String> {
+    let duration = Some(Duration::from_secs(phase.duration_secs));
+    let mut slots = Vec::new();
+
+    let result = (|| {
+        if matches!(phase.workload, PhaseWorkload::Cpu | PhaseWorkload::Combined) {
+            stress::start_cpu(ctx.cpu, &phase.cpu_options()?, duration, Some(watchdog.clone()))?;
+            slots.push(ctx.cpu);
+        }
+        if matches!(phase.workload, PhaseWorkload::Gpu | PhaseWorkload::Combined) {
+            let selector = phase.gpu_device.clone().unwrap_or_default();
+            let intensity = phase.gpu_intensity.unwrap_or_default();
+            gpu_stress::start(ctx.gpu, &selector, intensity, duration, Some(watchdog.clone()))?;
+            slots.push(ctx.gpu);
+        }
+        if phase.workload == PhaseWorkload::Memory {
+            let options = MemoryStressOptions::new(phase.memory_fraction, phase.threads)?;
+            memory_stress::start(ctx.memory, &options, duration, Some(watchdog.clone()))?;
+            slots.push(ctx.memory);
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => Ok(slots),
+        Err(e) => {
+            for slot in slots {
+                slot.stop();
+            }
+            Err(e)
+        }
+    }
+}
+
+fn ended_early(status: &StressStatus) -> Option<(PhaseEnd, Option<String>)> {
+    match status.state {
+        StressState::Aborted => Some((PhaseEnd::Aborted, status.abort_reason.clone())),
+        StressState::Failed => Some((PhaseEnd::Failed, status.error.clone())),
+        _ => None,
+    }
+}
+
+// Sleep in short steps so a stop request is noticed quickly
+fn wait(duration: Duration, stop: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+    while !stop.load(Ordering::SeqCst) {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        thread::sleep((deadline - now).min(PLAN_STOP_TICK));
+    }
+}
+
+// Evaluates a phase's `until` condition sample by sample
+struct ConditionTracker {
+    condition: Condition,
+    window: VecDeque<(Duration, f64)>,
+    detector: Option<SteadyStateDetector>,
+}
+
+impl ConditionTracker {
+    fn new(condition: Condition) -> Self {
+        let detector = match &condition {
+            Condition::SteadyState { config, .. } => Some(SteadyStateDetector::new(config.clone())),
+            _ => None,
+        };
+        ConditionTracker { condition, window: VecDeque::new(), detector }
+    }
+
+    fn update(&mut self, sample: &SensorSample, elapsed: Duration) -> bool {
+        match &self.condition {
+            Condition::TempAbove { sensor, celsius } => sensor.read(sample).is_some_and(|temp| temp >= *celsius),
+            Condition::TempBelow { sensor, celsius } => sensor.read(sample).is_some_and(|temp| temp <= *celsius),
+            Condition::SteadyState { sensor, .. } => {
+                let (Some(value), Some(detector)) = (sample.value(sensor), self.detector.as_mut()) else {
+                    return false;
+                };
+                detector.push(elapsed.as_secs_f64(), value).is_some()
+            }
+            Condition::TempStable { sensor, window_secs, tolerance } => {
+                let Some(temp) = sensor.read(sample) else {
+                    return false;
+                };
+                let window = Duration::from_secs(*window_secs);
+
+                self.window.push_back((elapsed, temp));
+                // Keep one sample at or beyond the window edge so the window stays covered
+                while self.window.get(1).is_some_and(|&(t, _)| elapsed - t >= window) {
+                    self.window.pop_front();
+                }
+
+                // Only judge once the samples cover the whole window
+                let covered = self.window.front().is_some_and(|&(t, _)| elapsed - t >= window);
+                let (min, max) = self.window.iter()
+                    .fold((f64::MAX, f64::MIN), |(min, max), &(_, v)| (min.min(v), max.max(v)));
+                covered && max - min <= *tolerance
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_toml() {
+        let plan = TestPlan::parse(
+            r#"
+            name = "Soak"
+            assertions = { max_cpu_temp = 95.0, no_throttling = true }
+
+            [[phases]]
+            name = "idle"
+            workload = "idle"
+            duration_secs = 60
+            until = { temp_below = { sensor = "cpu", celsius = 50.0 } }
+
+            [[phases]]
+            name = "load"
+            workload = "cpu"
+            duration_secs = 600
+            threads = 1
+            load_percent = 75
+            until = { steady_state = { sensor = "cpu_temp", window_secs = 30.0 } }
+
+            [[phases]]
+            name = "gpu"
+            workload = "gpu"
+            duration_secs = 60
+            gpu_intensity = "extreme"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(plan.name, "Soak");
+        assert_eq!(plan.assertions.as_ref().unwrap().no_throttling, Some(true));
+        assert_eq!(plan.phases.len(), 3);
+        assert!(matches!(plan.phases[0].until, Some(Condition::TempBelow { sensor: TempSensor::Cpu, celsius }) if celsius == 50.0));
+        match &plan.phases[1].until {
+            Some(Condition::SteadyState { sensor, config }) => {
+                assert_eq!(sensor, "cpu_temp");
+                assert_eq!(config.window_secs, 30.0);
+                // Unset thresholds keep their defaults
+                assert_eq!(config.max_slope_per_min, SteadyStateConfig::default().max_slope_per_min);
+            }
+            other => panic!("unexpected condition {:?}", other),
+        }
+        assert_eq!(plan.phases[1].cpu_options().unwrap().load_percent, 75);
+        assert_eq!(plan.phases[2].gpu_intensity, Some(GpuIntensity::Extreme));
+    }
+
+    #[test]
+    fn parses_json() {
+        let plan = TestPlan::parse(
+            r#"{"name": "Quick", "phases": [
+                {"name": "mem", "workload": "memory", "duration_secs": 30, "memory_fraction": 0.25}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(plan.phases[0].workload, PhaseWorkload::Memory);
+        assert_eq!(plan.phases[0].memory_fraction, Some(0.25));
+    }
+
+    fn parse_error(phases: &str) -> String {
+        TestPlan::parse(&format!("name = \"Bad\"\n{}", phases)).unwrap_err()
+    }
+
+    #[test]
+    fn steady_state_works_on_any_sampler_channel() {
+        for sensor in ["core_temp_0", "cpu_usage", "cpu_usage_3", "fan_1", "gpu_stress_ops_per_sec"] {
+            let phases = format!(
+                "[[phases]]\nname = \"a\"\nworkload = \"idle\"\nduration_secs = 5\nuntil = {{ steady_state = {{ sensor = \"{}\" }} }}",
+                sensor
+            );
+            assert!(TestPlan::parse(&format!("name = \"Any\"\n{}", phases)).is_ok(), "{}", sensor);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_plans() {
+        assert_eq!(parse_error("phases = []"), "Test plan has no phases");
+        assert_eq!(
+            parse_error("[[phases]]\nname = \"a\"\nworkload = \"idle\"\nduration_secs = 0"),
+            "Phase 'a' needs a duration"
+        );
+        assert_eq!(
+            parse_error("[[phases]]\nname = \"a\"\nworkload = \"idle\"\nduration_secs = 5\nuntil = { temp_stable = { sensor = \"cpu\", window_secs = 10, tolerance = 0.0 } }"),
+            "Phase 'a' has an invalid temp_stable condition"
+        );
+        assert_eq!(
+            parse_error("[[phases]]\nname = \"a\"\nworkload = \"idle\"\nduration_secs = 5\nuntil = { steady_state = { sensor = \"core_temp_x\" } }"),
+            "Phase 'a' has an invalid steady_state condition"
+        );
+        assert_eq!(
+            parse_error("[[phases]]\nname = \"a\"\nworkload = \"idle\"\nduration_secs = 5\nuntil = { steady_state = { sensor = \"cpu_temp\", window_secs = 0.0 } }"),
+            "Phase 'a' has an invalid steady_state condition"
+        );
+        assert_eq!(
+            parse_error("[[phases]]\nname = \"a\"\nworkload = \"cpu\"\nduration_secs = 5\nload_percent = 0"),
+            "Phase 'a': Target load must be between 1 and 100%, got 0"
+        );
+        assert_eq!(
+            parse_error("[[phases]]\nname = \"a\"\nworkload = \"memory\"\nduration_secs = 5\nmemory_fraction = 0.95"),
+            "Phase 'a': Memory fraction must be between 0 and 0.9, got 0.95"
+        );
+        assert!(parse_error("[[phases]]\nname = \"a\"\nworkload = \"sleep\"\nduration_secs = 5").starts_with("Invalid TOML test plan"));
+        assert!(TestPlan::parse("{\"name\": 1}").unwrap_err().starts_with("Invalid JSON test plan"));
+    }
+}
@@ -0,0 +1,485 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::sampling::{self, Snapshot};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    Csv,
+    Jsonl,
+}
+
+impl RecordingFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordingFormat::Csv => "csv",
+            RecordingFormat::Jsonl => "jsonl",
+        }
+    }
+
+    // Guess from a file name, e.g. when replaying
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(RecordingFormat::Csv),
+            "jsonl" | "ndjson" => Some(RecordingFormat::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+// The machine a recording was made on
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MachineInfo {
+    pub hostname: Option<String>,
+    pub os: Option<String>,
+    pub cpu_vendor: Option<String>,
+    pub cpu_model: Option<String>,
+    pub cpu_cores: Option<usize>,
+    pub cpu_threads: Option<usize>,
+    pub gpu_vendor: Option<String>,
+    pub gpu_model: Option<String>,
+    pub memory_bytes: Option<u64>,
+}
+
+// First record of every recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub format_version: u32,
+    pub started_at: u64,  // Unix timestamp in milliseconds
+    pub machine: MachineInfo,
+    pub sensors: Vec<String>,  // Channel ids, in CSV column order; later column rows may add more
+}
+
+const FORMAT_VERSION: u32 = 1;
+
+// One line of a JSONL recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JsonlRecord {
+    Header(RecordingHeader),
+    Sample(Snapshot),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingStatus {
+    pub active: bool,
+    pub path: String,
+    pub format: RecordingFormat,
+    pub started_at: u64,
+    pub records: u64,
+    pub error: Option<String>,  // Write failure that ended the recording
+}
+
+struct ActiveRecording {
+    writer: BufWriter<File>,
+    machine: MachineInfo,
+    sensors: Option<Vec<String>>,  // Set by the first snapshot, which also writes the header; grows as channels appear
+    status: RecordingStatus,
+}
+
+// Writes every sampler snapshot to a file while active
+#[derive(Default)]
+pub struct Recorder {
+    active: Mutex<Option<ActiveRecording>>,
+    last: Mutex<Option<RecordingStatus>>,
+}
+
+impl Recorder {
+    // `path` may be a directory, in which case a timestamped file name is chosen
+    pub fn start(&self, path: &Path, format: RecordingFormat, machine: MachineInfo) -> Result<RecordingStatus, String> {
+        let mut active = self.active.lock();
+        if active.is_some() {
+            return Err("A recording is already running".to_string());
+        }
+
+        let started_at = sampling::now_millis();
+        let path = if path.is_dir() {
+            path.join(format!("tempdetect-{}.{}", file_stamp(started_at), format.extension()))
+        } else {
+            path.to_path_buf()
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+        let status = RecordingStatus {
+            active: true,
+            path: path.display().to_string(),
+            format,
+            started_at,
+            records: 0,
+            error: None,
+        };
+        *active = Some(ActiveRecording {
+            writer: BufWriter::new(file),
+            machine,
+            sensors: None,
+            status: status.clone(),
+        });
+
+        Ok(status)
+    }
+
+    pub fn stop(&self) -> Option<RecordingStatus> {
+        let mut recording = self.active.lock().take()?;
+        if let Err(e) = recording.writer.flush() {
+            recording.status.error.get_or_insert(e.to_string());
+        }
+        recording.status.active = false;
+        *self.last.lock() = Some(recording.status.clone());
+        Some(recording.status)
+    }
+
+    pub fn status(&self) -> Option<RecordingStatus> {
+        match self.active.lock().as_ref() {
+            Some(recording) => Some(recording.status.clone()),
+            None => self.last.lock().clone(),
+        }
+    }
+
+    // Sampler sink. A write error ends the recording and is kept in its status.
+    pub fn record(&self, snapshot: &Snapshot) {
+        let mut active = self.active.lock();
+        let Some(recording) = active.as_mut() else {
+            return;
+        };
+
+        if let Err(e) = recording.write(snapshot) {
+            recording.status.error = Some(e);
+            drop(active);
+            self.stop();
+        }
+    }
+}
+
+impl ActiveRecording {
+    fn write(&mut self, snapshot: &Snapshot) -> Result<(), String> {
+        if self.sensors.is_none() {
+            let header = RecordingHeader {
+                format_version: FORMAT_VERSION,
+                started_at: self.status.started_at,
+                machine: self.machine.clone(),
+                sensors: snapshot.values.keys().cloned().collect(),
+            };
+            self.write_header(&header).map_err(|e| e.to_string())?;
+            self.sensors = Some(header.sensors);
+        }
+
+        // A channel can show up after the first tick (a sensor that woke up, a stress test that
+        // started). CSV gets a new column row with it appended, so no value is dropped.
+        let sensors = self.sensors.get_or_insert_with(Vec::new);
+        let new_sensors: Vec<String> = snapshot.values.keys().filter(|id| !sensors.contains(id)).cloned().collect();
+        if !new_sensors.is_empty() {
+            sensors.extend(new_sensors);
+            if self.status.format == RecordingFormat::Csv {
+                writeln!(self.writer, "timestamp,{}", sensors.join(",")).map_err(|e| e.to_string())?;
+            }
+        }
+
+        match self.status.format {
+            RecordingFormat::Csv => {
+                let mut line = snapshot.timestamp.to_string();
+                for sensor in self.sensors.iter().flatten() {
+                    line.push(',');
+                    if let Some(value) = snapshot.values.get(sensor) {
+                        line.push_str(&value.to_string());
+                    }
+                }
+                writeln!(self.writer, "{}", line)
+            }
+            RecordingFormat::Jsonl => {
+                let json = serde_json::to_string(&JsonlRecord::Sample(snapshot.clone())).map_err(|e| e.to_string())?;
+                writeln!(self.writer, "{}", json)
+            }
+        }
+        .map_err(|e| e.to_string())?;
+
+        // Flush every record so a crash mid-test keeps everything up to the last sample
+        self.writer.flush().map_err(|e| e.to_string())?;
+        self.status.records += 1;
+        Ok(())
+    }
+
+    fn write_header(&mut self, header: &RecordingHeader) -> std::io::Result<()> {
+        match self.status.format {
+            RecordingFormat::Csv => {
+                // Metadata goes in comment lines ahead of the column row
+                let machine = serde_json::to_string(&header.machine).unwrap_or_default();
+                writeln!(self.writer, "# tempdetect recording v{}", header.format_version)?;
+                writeln!(self.writer, "# started_at: {}", header.started_at)?;
+                writeln!(self.writer, "# machine: {}", machine)?;
+                writeln!(self.writer, "timestamp,{}", header.sensors.join(","))
+            }
+            RecordingFormat::Jsonl => {
+                let json = serde_json::to_string(&JsonlRecord::Header(header.clone())).unwrap_or_default();
+                writeln!(self.writer, "{}", json)
+            }
+        }
+    }
+}
+
+// A recording read back from disk
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub header: RecordingHeader,
+    pub snapshots: Vec<Snapshot>,
+}
+
+// Read a CSV or JSONL recording, picking the format from the extension
+pub fn read(path: &Path) -> Result<Recording, String> {
+    let format = RecordingFormat::from_path(path)
+        .ok_or_else(|| format!("Unknown recording format: {}", path.display()))?;
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let lines = BufReader::new(file).lines();
+
+    let mut recording = match format {
+        RecordingFormat::Csv => read_csv(lines),
+        RecordingFormat::Jsonl => read_jsonl(lines),
+    }
+    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    if recording.snapshots.is_empty() {
+        return Err(format!("{} holds no samples", path.display()));
+    }
+    // Replay and the summaries walk the samples in time order, even if the clock jumped back while recording
+    recording.snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+    Ok(recording)
+}
+
+fn read_csv(lines: impl Iterator<Item = std::io::Result<String>>) -> Result<Recording, String> {
+    let mut header = RecordingHeader {
+        format_version: FORMAT_VERSION,
+        started_at: 0,
+        machine: MachineInfo::default(),
+        sensors: Vec::new(),
+    };
+    let mut columns: Option<Vec<String>> = None;
+    let mut snapshots = Vec::new();
+
+    for (number, line) in lines.enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim();
+            if let Some(value) = comment.strip_prefix("started_at:") {
+                header.started_at = value.trim().parse().unwrap_or(0);
+            } else if let Some(value) = comment.strip_prefix("machine:") {
+                header.machine = serde_json::from_str(value.trim()).unwrap_or_default();
+            } else if let Some(value) = comment.strip_prefix("tempdetect recording v") {
+                header.format_version = value.trim().parse().unwrap_or(FORMAT_VERSION);
+            }
+            continue;
+        }
+
+        let mut fields = line.split(',');
+        // A later column row replaces the columns when channels were added mid-recording
+        if line.starts_with("timestamp,") || line == "timestamp" {
+            fields.next();
+            let sensors: Vec<String> = fields.map(str::to_string).collect();
+            for sensor in &sensors {
+                if !header.sensors.contains(sensor) {
+                    header.sensors.push(sensor.clone());
+                }
+            }
+            columns = Some(sensors);
+            continue;
+        }
+        let Some(columns) = columns.as_ref() else {
+            return Err(format!("line {}: expected the column row", number + 1));
+        };
+
+        let timestamp = fields
+            .next()
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| format!("line {}: bad timestamp", number + 1))?;
+        // Empty cells are channels missing from that snapshot
+        let values = columns
+            .iter()
+            .zip(fields)
+            .filter_map(|(sensor, value)| Some((sensor.clone(), value.parse().ok()?)))
+            .collect();
+        snapshots.push(Snapshot { timestamp, values });
+    }
+
+    if header.started_at == 0 {
+        header.started_at = snapshots.first().map_or(0, |s: &Snapshot| s.timestamp);
+    }
+    Ok(Recording { header, snapshots })
+}
+
+fn read_jsonl(lines: impl Iterator<Item = std::io::Result<String>>) -> Result<Recording, String> {
+    let mut header = None;
+    let mut snapshots = Vec::new();
+
+    for (number, line) in lines.enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line).map_err(|e| format!("line {}: {}", number + 1, e))? {
+            JsonlRecord::Header(h) => header = Some(h),
+            JsonlRecord::Sample(snapshot) => snapshots.push(snapshot),
+        }
+    }
+
+    let header = header.unwrap_or_else(|| RecordingHeader {
+        format_version: FORMAT_VERSION,
+        started_at: snapshots.first().map_or(0, |s| s.timestamp),
+        machine: MachineInfo::default(),
+        sensors: snapshots.first().map(|s| s.values.keys().cloned().collect()).unwrap_or_default(),
+    });
+    Ok(Recording { header, snapshots })
+}
+
+// UTC "YYYYMMDD-HHMMSS" for file names
+fn file_stamp(millis: u64) -> String {
+    let (year, month, day, hour, minute, second) = utc_parts(millis);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, hour, minute, second)
+}
+
+// UTC "YYYY-MM-DD HH:MM:SS UTC" for reports
+pub fn format_utc(millis: u64) -> String {
+    let (year, month, day, hour, minute, second) = utc_parts(millis);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, hour, minute, second)
+}
+
+fn utc_parts(millis: u64) -> (i64, i64, i64, u64, u64, u64) {
+    let secs = millis / 1000;
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day, rem / 3600, rem / 60 % 60, rem % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn snapshot(timestamp: u64, values: &[(&str, f64)]) -> Snapshot {
+        Snapshot {
+            timestamp,
+            values: values.iter().map(|&(id, value)| (id.to_string(), value)).collect(),
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("tempdetect-test-{}-{}", std::process::id(), name))
+    }
+
+    fn lines(text: &str) -> impl Iterator<Item = std::io::Result<String>> + '_ {
+        text.lines().map(|line| Ok(line.to_string()))
+    }
+
+    fn round_trip(format: RecordingFormat) {
+        let path = temp_path(&format!("round-trip.{}", format.extension()));
+        let machine = MachineInfo { hostname: Some("test-mac".to_string()), cpu_cores: Some(8), ..Default::default() };
+        let snapshots = vec![
+            snapshot(1_000, &[("cpu_temp", 45.5), ("fan_0", 1200.0)]),
+            snapshot(2_000, &[("cpu_temp", 46.0)]),
+            // Channels that only show up later are kept too
+            snapshot(3_000, &[("cpu_temp", 47.25), ("fan_0", 1250.0), ("gpu_temp", 50.0)]),
+        ];
+
+        let recorder = Recorder::default();
+        recorder.start(&path, format, machine).unwrap();
+        for snapshot in &snapshots {
+            recorder.record(snapshot);
+        }
+        let status = recorder.stop().unwrap();
+        assert_eq!(status.records, 3);
+        assert!(status.error.is_none());
+
+        let recording = read(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(recording.header.machine.hostname.as_deref(), Some("test-mac"));
+        assert_eq!(recording.header.machine.cpu_cores, Some(8));
+        assert_eq!(recording.header.started_at, status.started_at);
+        assert_eq!(recording.snapshots.len(), 3);
+        for (read, written) in recording.snapshots.iter().zip(&snapshots) {
+            assert_eq!(read.timestamp, written.timestamp);
+            assert_eq!(read.values, written.values);
+        }
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        round_trip(RecordingFormat::Csv);
+    }
+
+    #[test]
+    fn jsonl_round_trip() {
+        round_trip(RecordingFormat::Jsonl);
+    }
+
+    #[test]
+    fn csv_column_rows_can_grow() {
+        let text = "# tempdetect recording v1\n\
+                    # started_at: 500\n\
+                    timestamp,cpu_temp\n\
+                    1000,40\n\
+                    timestamp,cpu_temp,gpu_temp\n\
+                    2000,41,50\n\
+                    3000,,51\n";
+        let recording = read_csv(lines(text)).unwrap();
+        assert_eq!(recording.header.started_at, 500);
+        assert_eq!(recording.header.sensors, vec!["cpu_temp", "gpu_temp"]);
+        assert_eq!(recording.snapshots[0].values, snapshot(0, &[("cpu_temp", 40.0)]).values);
+        assert_eq!(recording.snapshots[1].values, snapshot(0, &[("cpu_temp", 41.0), ("gpu_temp", 50.0)]).values);
+        assert_eq!(recording.snapshots[2].values, snapshot(0, &[("gpu_temp", 51.0)]).values);
+    }
+
+    #[test]
+    fn csv_errors() {
+        assert_eq!(read_csv(lines("1000,40\n")).unwrap_err(), "line 1: expected the column row");
+        assert_eq!(read_csv(lines("timestamp,cpu_temp\nsoon,40\n")).unwrap_err(), "line 2: bad timestamp");
+    }
+
+    #[test]
+    fn jsonl_without_header() {
+        let text = "{\"type\":\"sample\",\"timestamp\":2000,\"values\":{\"cpu_temp\":40.0}}\n\n\
+                    {\"type\":\"sample\",\"timestamp\":3000,\"values\":{\"cpu_temp\":41.0}}\n";
+        let recording = read_jsonl(lines(text)).unwrap();
+        assert_eq!(recording.header.started_at, 2000);
+        assert_eq!(recording.header.sensors, vec!["cpu_temp"]);
+        assert_eq!(recording.snapshots.len(), 2);
+
+        assert!(read_jsonl(lines("{\"type\":\"other\"}\n")).unwrap_err().starts_with("line 1:"));
+    }
+
+    #[test]
+    fn read_sorts_by_time() {
+        let path = temp_path("unsorted.csv");
+        fs::write(&path, "timestamp,cpu_temp\n3000,42\n1000,40\n2000,41\n").unwrap();
+        let recording = read(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let timestamps: Vec<u64> = recording.snapshots.iter().map(|s| s.timestamp).collect();
+        assert_eq!(timestamps, vec![1000, 2000, 3000]);
+    }
+
+    #[test]
+    fn utc_formatting() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_utc(1_709_210_096_000), "2024-02-29 12:34:56 UTC");
+        assert_eq!(file_stamp(1_709_210_096_000), "20240229-123456");
+    }
+}
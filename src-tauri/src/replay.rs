@@ -0,0 +1,408 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::recording::{MachineInfo, Recording};
+use crate::sampling::{Sampler, Snapshot};
+
+// How often the player re-checks pause, seek, speed and stop between snapshots
+const REPLAY_TICK: Duration = Duration::from_millis(50);
+
+const MAX_SPEED: f64 = 1000.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayOptions {
+    pub speed: f64,       // 2.0 plays twice as fast as recorded
+    pub paused: bool,     // Load and wait for `resume`
+    pub start_secs: f64,  // Offset into the recording to start from
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        ReplayOptions {
+            speed: 1.0,
+            paused: false,
+            start_secs: 0.0,
+        }
+    }
+}
+
+fn validate_speed(speed: f64) -> Result<(), String> {
+    if !(speed > 0.0 && speed <= MAX_SPEED) {
+        return Err(format!("Replay speed must be above 0 and at most {}x", MAX_SPEED));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayStatus {
+    pub active: bool,
+    pub finished: bool,  // Reached the end of the recording
+    pub path: String,
+    pub machine: MachineInfo,
+    pub speed: f64,
+    pub paused: bool,
+    pub index: usize,  // Snapshots played up to the current position
+    pub total: usize,
+    pub started_at: u64,  // Recorded timestamp of the first snapshot
+    pub position_secs: f64,  // Offset of the last published snapshot
+    pub duration_secs: f64,
+    pub timestamp: Option<u64>,  // Recorded timestamp of the last published snapshot
+}
+
+struct Control {
+    paused: bool,
+    speed: f64,
+    seek: Option<u64>,  // Recorded timestamp to continue from
+}
+
+#[derive(Default)]
+struct PlayerInner {
+    stop: Option<Arc<AtomicBool>>,
+    thread: Option<JoinHandle<()>>,
+    control: Option<Arc<Mutex<Control>>>,
+    status: Option<ReplayStatus>,
+}
+
+// Plays a recording back through the sampler in place of live readings
+#[derive(Default)]
+pub struct ReplayPlayer {
+    inner: Mutex<PlayerInner>,
+}
+
+impl ReplayPlayer {
+    // Live publishing is suspended until the replay ends or is stopped
+    pub fn start(
+        &'static self,
+        sampler: &'static Sampler,
+        path: &str,
+        recording: Recording,
+        options: ReplayOptions,
+    ) -> Result<ReplayStatus, String> {
+        validate_speed(options.speed)?;
+        let first = recording.snapshots.first().ok_or("The recording holds no samples")?.timestamp;
+        let last = recording.snapshots.last().map_or(first, |s| s.timestamp);
+
+        self.stop();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let control = Arc::new(Mutex::new(Control {
+            paused: options.paused,
+            speed: options.speed,
+            seek: Some(first + (options.start_secs.max(0.0) * 1000.0) as u64),
+        }));
+        let status = ReplayStatus {
+            active: true,
+            finished: false,
+            path: path.to_string(),
+            machine: recording.header.machine.clone(),
+            speed: options.speed,
+            paused: options.paused,
+            index: 0,
+            total: recording.snapshots.len(),
+            started_at: first,
+            position_secs: 0.0,
+            duration_secs: last.saturating_sub(first) as f64 / 1000.0,
+            timestamp: None,
+        };
+
+        let mut inner = self.inner.lock();
+        inner.stop = Some(stop.clone());
+        inner.control = Some(control.clone());
+        inner.status = Some(status.clone());
+        sampler.suspend_live(true);
+        let snapshots = recording.snapshots;
+        inner.thread = Some(thread::spawn(move || {
+            self.run(sampler, &snapshots, control, &stop);
+            sampler.suspend_live(false);
+            self.update(|status| status.active = false);
+        }));
+
+        Ok(status)
+    }
+
+    pub fn stop(&self) -> Option<ReplayStatus> {
+        let (stop, thread) = {
+            let mut inner = self.inner.lock();
+            inner.control = None;
+            (inner.stop.take(), inner.thread.take())
+        };
+
+        if let Some(stop) = stop {
+            stop.store(true, Ordering::SeqCst);
+        }
+        if let Some(thread) = thread {
+            let _ = thread.join();
+        }
+        self.status()
+    }
+
+    pub fn status(&self) -> Option<ReplayStatus> {
+        self.inner.lock().status.clone()
+    }
+
+    pub fn set_paused(&self, paused: bool) -> Result<ReplayStatus, String> {
+        self.control(|control, status| {
+            control.paused = paused;
+            status.paused = paused;
+        })
+    }
+
+    pub fn set_speed(&self, speed: f64) -> Result<ReplayStatus, String> {
+        validate_speed(speed)?;
+        self.control(|control, status| {
+            control.speed = speed;
+            status.speed = speed;
+        })
+    }
+
+    // Jump to `offset_secs` into the recording; the next snapshot plays straight away
+    pub fn seek(&self, offset_secs: f64) -> Result<ReplayStatus, String> {
+        self.control(|control, status| {
+            let offset = offset_secs.clamp(0.0, status.duration_secs);
+            control.seek = Some(status.started_at + (offset * 1000.0) as u64);
+        })
+    }
+
+    fn control(&self, f: impl FnOnce(&mut Control, &mut ReplayStatus)) -> Result<ReplayStatus, String> {
+        let mut inner = self.inner.lock();
+        let control = inner.control.clone();
+        match (control, inner.status.as_mut()) {
+            (Some(control), Some(status)) if status.active => {
+                f(&mut control.lock(), status);
+                Ok(status.clone())
+            }
+            _ => Err("No replay is running".to_string()),
+        }
+    }
+
+    fn run(&self, sampler: &Sampler, snapshots: &[Snapshot], control: Arc<Mutex<Control>>, stop: &AtomicBool) {
+        let first = snapshots[0].timestamp;
+        let mut index = 0;
+        let mut shown = None;  // Index last published, so a frame shown while paused isn't sent twice
+
+        while !stop.load(Ordering::SeqCst) {
+            let (paused, seek) = {
+                let mut control = control.lock();
+                (control.paused, control.seek.take())
+            };
+            if let Some(target) = seek {
+                index = snapshots.partition_point(|s| s.timestamp < target);
+                self.update(|status| status.finished = false);
+            }
+            if index >= snapshots.len() {
+                if paused {
+                    thread::sleep(REPLAY_TICK);
+                    continue;
+                }
+                self.update(|status| status.finished = true);
+                break;
+            }
+
+            // While paused only a seek publishes, so the UI shows where it landed
+            let snapshot = &snapshots[index];
+            if shown != Some(index) && (!paused || seek.is_some()) {
+                sampler.publish(snapshot);
+                shown = Some(index);
+                self.update(|status| {
+                    status.index = index + 1;
+                    status.position_secs = snapshot.timestamp.saturating_sub(first) as f64 / 1000.0;
+                    status.timestamp = Some(snapshot.timestamp);
+                });
+            }
+            if paused {
+                thread::sleep(REPLAY_TICK);
+                continue;
+            }
+
+            // Wait out the recorded gap to the next snapshot, scaled by the current speed
+            let gap = snapshots
+                .get(index + 1)
+                .map_or(0, |next| next.timestamp.saturating_sub(snapshot.timestamp));
+            if wait(gap as f64, &control, stop) {
+                index += 1;
+            }
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ReplayStatus)) {
+        if let Some(status) = self.inner.lock().status.as_mut() {
+            f(status);
+        }
+    }
+}
+
+// Sleep for `gap_ms` of recording time. Paused time doesn't count and speed changes apply mid-gap.
+// Returns false when a seek or stop cut the wait short.
+fn wait(gap_ms: f64, control: &Mutex<Control>, stop: &AtomicBool) -> bool {
+    let mut played = 0.0;
+    let mut last = Instant::now();
+
+    while played < gap_ms {
+        let (paused, speed, seeking) = {
+            let control = control.lock();
+            (control.paused, control.speed, control.seek.is_some())
+        };
+        if stop.load(Ordering::SeqCst) || seeking {
+            return false;
+        }
+
+        let remaining = Duration::from_secs_f64((gap_ms - played) / speed / 1000.0);
+        thread::sleep(REPLAY_TICK.min(remaining));
+        let now = Instant::now();
+        if !paused {
+            played += now.duration_since(last).as_secs_f64() * 1000.0 * speed;
+        }
+        last = now;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::RecordingHeader;
+
+    // Five snapshots 100ms apart
+    fn recording() -> Recording {
+        recording_at(&[1_000, 1_100, 1_200, 1_300, 1_400])
+    }
+
+    fn recording_at(timestamps: &[u64]) -> Recording {
+        Recording {
+            header: RecordingHeader {
+                format_version: 1,
+                started_at: timestamps[0],
+                machine: MachineInfo::default(),
+                sensors: vec!["cpu_temp".to_string()],
+            },
+            snapshots: timestamps
+                .iter()
+                .map(|&timestamp| Snapshot {
+                    timestamp,
+                    values: [("cpu_temp".to_string(), 50.0)].into_iter().collect(),
+                })
+                .collect(),
+        }
+    }
+
+    // A player on its own sampler, with a sink that keeps the timestamps it was handed
+    fn player() -> (&'static ReplayPlayer, &'static Sampler, Arc<Mutex<Vec<u64>>>) {
+        let sampler: &'static Sampler = Box::leak(Box::default());
+        let published = Arc::new(Mutex::new(Vec::new()));
+        let sink = published.clone();
+        sampler.subscribe(Arc::new(move |snapshot: &Snapshot| sink.lock().push(snapshot.timestamp)));
+        (Box::leak(Box::default()), sampler, published)
+    }
+
+    fn start(player: &'static ReplayPlayer, sampler: &'static Sampler, recording: Recording, options: ReplayOptions) {
+        player.start(sampler, "test.csv", recording, options).unwrap();
+    }
+
+    // Poll the player until `done` holds for its status, or fail after a few seconds
+    fn wait_for(player: &ReplayPlayer, done: impl Fn(&ReplayStatus) -> bool) -> ReplayStatus {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let status = player.status().unwrap();
+            if done(&status) {
+                return status;
+            }
+            assert!(Instant::now() < deadline, "timed out at snapshot {}", status.index);
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn plays_to_the_end() {
+        let (player, sampler, published) = player();
+        start(player, sampler, recording(), ReplayOptions { speed: 10.0, ..Default::default() });
+        assert!(!sampler.is_live());
+
+        let status = wait_for(player, |status| !status.active);
+        assert!(status.finished);
+        assert_eq!((status.index, status.total), (5, 5));
+        assert_eq!(status.position_secs, 0.4);
+        assert_eq!(*published.lock(), vec![1_000, 1_100, 1_200, 1_300, 1_400]);
+        assert!(sampler.is_live());
+        assert!(player.set_paused(true).is_err());
+    }
+
+    #[test]
+    fn paused_only_publishes_seeks() {
+        let (player, sampler, published) = player();
+        start(player, sampler, recording(), ReplayOptions { paused: true, ..Default::default() });
+
+        // Loading paused shows the first snapshot and then holds
+        wait_for(player, |status| status.index == 1);
+        thread::sleep(REPLAY_TICK * 3);
+        assert_eq!(*published.lock(), vec![1_000]);
+
+        // A seek lands on the first snapshot at or after the offset
+        player.seek(0.15).unwrap();
+        let status = wait_for(player, |status| status.index == 3);
+        assert_eq!(status.timestamp, Some(1_200));
+        assert!(status.paused && status.active);
+        thread::sleep(REPLAY_TICK * 3);
+        assert_eq!(*published.lock(), vec![1_000, 1_200]);
+
+        // Offsets past the end are clamped to the last snapshot
+        player.seek(60.0).unwrap();
+        wait_for(player, |status| status.index == 5);
+        assert!(!player.status().unwrap().finished);
+
+        player.seek(0.3).unwrap();
+        wait_for(player, |status| status.index == 4);
+        player.set_speed(10.0).unwrap();
+        player.set_paused(false).unwrap();
+        let status = wait_for(player, |status| !status.active);
+        assert!(status.finished);
+        assert_eq!(*published.lock(), vec![1_000, 1_200, 1_400, 1_300, 1_400]);
+    }
+
+    #[test]
+    fn speed_changes_apply_mid_gap() {
+        let (player, sampler, published) = player();
+        // An hour between the two snapshots at normal speed
+        start(player, sampler, recording_at(&[0, 3_600_000]), ReplayOptions::default());
+        wait_for(player, |status| status.index == 1);
+
+        assert!(player.set_speed(0.0).is_err());
+        assert!(player.set_speed(MAX_SPEED * 2.0).is_err());
+        // What's left of the hour at 1000x still takes about 3.6s, so skip most of it first
+        player.seek(3_599.0).unwrap();
+        player.set_speed(MAX_SPEED).unwrap();
+        let status = wait_for(player, |status| !status.active);
+        assert!(status.finished);
+        assert_eq!(status.speed, MAX_SPEED);
+        assert_eq!(*published.lock(), vec![0, 3_600_000]);
+    }
+
+    #[test]
+    fn starting_past_the_end_finishes_without_publishing() {
+        let (player, sampler, published) = player();
+        start(player, sampler, recording(), ReplayOptions { start_secs: 10.0, ..Default::default() });
+
+        let status = wait_for(player, |status| !status.active);
+        assert!(status.finished);
+        assert_eq!(status.index, 0);
+        assert!(published.lock().is_empty());
+        let empty = Recording { snapshots: Vec::new(), ..recording() };
+        assert!(player.start(sampler, "empty.csv", empty, ReplayOptions::default()).is_err());
+    }
+
+    #[test]
+    fn stop_ends_playback() {
+        let (player, sampler, published) = player();
+        start(player, sampler, recording_at(&[0, 3_600_000]), ReplayOptions::default());
+        wait_for(player, |status| status.index == 1);
+
+        let status = player.stop().unwrap();
+        assert!(!status.active && !status.finished);
+        assert_eq!(*published.lock(), vec![0]);
+        assert!(sampler.is_live());
+    }
+}
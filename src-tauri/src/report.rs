@@ -0,0 +1,401 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::plan::{PhaseEnd, PhaseResult, PhaseWorkload, PlanResult, PlanState, TestPlan};
+use crate::sampling::SeriesStats;
+
+// Pass/fail criteria for a phase. Unset criteria are not checked, and one whose sensor gave no
+// readings fails, except `no_throttling` without speed-limit data (Apple Silicon), which is skipped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Assertions {
+    pub max_cpu_temp: Option<f64>,      // °C
+    pub max_gpu_temp: Option<f64>,      // °C
+    pub no_throttling: Option<bool>,    // Fail on any OS CPU speed limit below 100%
+    pub max_fan_rpm: Option<f64>,
+    pub min_cpu_freq_mhz: Option<f64>,  // Average frequency over the phase
+}
+
+impl Assertions {
+    // Phase settings win over plan settings, field by field
+    fn merge(plan: Option<&Assertions>, phase: Option<&Assertions>, workload: PhaseWorkload) -> Assertions {
+        let plan = plan.cloned().unwrap_or_default();
+        let phase = phase.cloned().unwrap_or_default();
+
+        // A plan-wide frequency floor only makes sense while the CPU is loaded
+        let plan_min_freq = match workload {
+            PhaseWorkload::Cpu | PhaseWorkload::Combined => plan.min_cpu_freq_mhz,
+            _ => None,
+        };
+
+        Assertions {
+            max_cpu_temp: phase.max_cpu_temp.or(plan.max_cpu_temp),
+            max_gpu_temp: phase.max_gpu_temp.or(plan.max_gpu_temp),
+            no_throttling: phase.no_throttling.or(plan.no_throttling),
+            max_fan_rpm: phase.max_fan_rpm.or(plan.max_fan_rpm),
+            min_cpu_freq_mhz: phase.min_cpu_freq_mhz.or(plan_min_freq),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssertionResult {
+    pub phase_index: usize,
+    pub phase: String,
+    pub name: String,  // "completed" or the assertion field name
+    pub passed: bool,
+    pub skipped: bool,  // Not applicable on this machine; counts as passed
+    pub message: String,
+}
+
+// Pass/fail report of one test plan run
+#[derive(Debug, Clone, Serialize)]
+pub struct TestReport {
+    pub plan: String,
+    pub passed: bool,
+    pub state: PlanState,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub assertions: Vec<AssertionResult>,
+    pub result: PlanResult,
+}
+
+// Check a plan's results against its assertions
+pub fn evaluate(plan: &TestPlan, result: &PlanResult) -> TestReport {
+    let mut assertions = Vec::new();
+
+    for (index, phase) in plan.phases.iter().enumerate() {
+        let Some(phase_result) = result.phases.get(index) else {
+            assertions.push(AssertionResult {
+                phase_index: index,
+                phase: phase.name.clone(),
+                name: "completed".to_string(),
+                passed: false,
+                skipped: false,
+                message: "Phase did not run".to_string(),
+            });
+            continue;
+        };
+
+        let criteria = Assertions::merge(plan.assertions.as_ref(), phase.assertions.as_ref(), phase.workload);
+        check_phase(index, phase_result, &criteria, &mut assertions);
+    }
+
+    TestReport {
+        plan: result.name.clone(),
+        passed: result.state == PlanState::Completed && assertions.iter().all(|a| a.passed),
+        state: result.state,
+        started_at: result.started_at,
+        finished_at: result.finished_at,
+        assertions,
+        result: result.clone(),
+    }
+}
+
+fn check_phase(index: usize, phase: &PhaseResult, criteria: &Assertions, out: &mut Vec<AssertionResult>) {
+    let mut push = |name: &str, passed: bool, skipped: bool, message: String| {
+        out.push(AssertionResult {
+            phase_index: index,
+            phase: phase.name.clone(),
+            name: name.to_string(),
+            passed,
+            skipped,
+            message,
+        });
+    };
+
+    let completed = !matches!(phase.end, PhaseEnd::Stopped | PhaseEnd::Aborted | PhaseEnd::Failed);
+    let message = match &phase.message {
+        Some(message) => format!("Phase ended: {:?} ({})", phase.end, message),
+        None => format!("Phase ended: {:?}", phase.end),
+    };
+    push("completed", completed, false, message);
+
+    if let Some(limit) = criteria.max_cpu_temp {
+        let (passed, message) = check_max(&phase.cpu_temp, limit, "°C");
+        push("max_cpu_temp", passed, false, message);
+    }
+    if let Some(limit) = criteria.max_gpu_temp {
+        let (passed, message) = check_max(&phase.gpu_temp, limit, "°C");
+        push("max_gpu_temp", passed, false, message);
+    }
+    if let Some(limit) = criteria.max_fan_rpm {
+        let (passed, message) = check_max(&phase.fan_rpm, limit, " RPM");
+        push("max_fan_rpm", passed, false, message);
+    }
+
+    if criteria.no_throttling == Some(true) {
+        let throttled_secs: f64 = phase.throttle_events.iter().map(|event| event.duration_secs()).sum();
+        let lowest = phase.throttle_events.iter().map(|event| event.min_speed_limit).reduce(f64::min);
+        let (passed, skipped, message) = match lowest {
+            Some(lowest) => (false, false, format!(
+                "{} throttling events, {:.0}s throttled, speed limit down to {:.0}%",
+                phase.throttle_events.len(),
+                throttled_secs,
+                lowest
+            )),
+            // The OS doesn't report a speed limit here, so the check can't apply
+            None if phase.samples.iter().all(|sample| sample.cpu_speed_limit.is_none()) => {
+                (true, true, "No speed-limit data".to_string())
+            }
+            None => (true, false, "No throttling".to_string()),
+        };
+        push("no_throttling", passed, skipped, message);
+    }
+
+    if let Some(floor) = criteria.min_cpu_freq_mhz {
+        let (passed, message) = match phase.cpu_freq.avg {
+            Some(avg) => (avg >= floor, format!("Average {:.0} MHz, floor {:.0} MHz", avg, floor)),
+            None => (false, "No frequency readings".to_string()),
+        };
+        push("min_cpu_freq_mhz", passed, false, message);
+    }
+}
+
+// A missing sensor fails the check rather than passing it silently
+fn check_max(stats: &SeriesStats, limit: f64, unit: &str) -> (bool, String) {
+    match stats.max {
+        Some(max) => (max <= limit, format!("Peak {:.1}{}, limit {:.1}{}", max, unit, limit, unit)),
+        None => (false, "No readings".to_string()),
+    }
+}
+
+pub fn write_json(report: &TestReport, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+pub fn write_junit(report: &TestReport, path: &Path) -> Result<(), String> {
+    fs::write(path, to_junit(report)).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// JUnit XML: one test suite per phase, one test case per assertion
+pub fn to_junit(report: &TestReport) -> String {
+    let failures = report.assertions.iter().filter(|a| !a.passed).count();
+    let skipped = report.assertions.iter().filter(|a| a.skipped).count();
+    let total_secs: f64 = report.result.phases.iter().map(|p| p.elapsed_secs).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        escape(&report.plan),
+        report.assertions.len(),
+        failures,
+        skipped,
+        total_secs
+    );
+
+    for cases in report.assertions.chunk_by(|a, b| a.phase_index == b.phase_index) {
+        let phase = &cases[0].phase;
+        let time = report.result.phases.get(cases[0].phase_index).map_or(0.0, |p| p.elapsed_secs);
+
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            escape(phase),
+            cases.len(),
+            cases.iter().filter(|a| !a.passed).count(),
+            cases.iter().filter(|a| a.skipped).count(),
+            time
+        );
+        for case in cases {
+            let classname = format!("{}.{}", report.plan, phase);
+            if case.skipped {
+                let _ = writeln!(
+                    xml,
+                    "    <testcase classname=\"{}\" name=\"{}\"><skipped message=\"{}\"/></testcase>",
+                    escape(&classname),
+                    escape(&case.name),
+                    escape(&case.message)
+                );
+            } else if case.passed {
+                let _ = writeln!(
+                    xml,
+                    "    <testcase classname=\"{}\" name=\"{}\"><system-out>{}</system-out></testcase>",
+                    escape(&classname),
+                    escape(&case.name),
+                    escape(&case.message)
+                );
+            } else {
+                let _ = writeln!(
+                    xml,
+                    "    <testcase classname=\"{}\" name=\"{}\"><failure message=\"{}\"/></testcase>",
+                    escape(&classname),
+                    escape(&case.name),
+                    escape(&case.message)
+                );
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::SensorSample;
+
+    fn phase(name: &str, end: PhaseEnd, samples: Vec<SensorSample>) -> PhaseResult {
+        let mut cpu_temp = SeriesStats::default();
+        let mut cpu_freq = SeriesStats::default();
+        for sample in &samples {
+            cpu_temp.add_opt(sample.cpu_temp);
+            cpu_freq.add_opt(sample.cpu_freq_mhz);
+        }
+        PhaseResult {
+            name: name.to_string(),
+            workload: PhaseWorkload::Cpu,
+            started_at: 0,
+            elapsed_secs: 10.0,
+            end,
+            message: None,
+            cpu_temp,
+            gpu_temp: SeriesStats::default(),
+            fan_rpm: SeriesStats::default(),
+            cpu_freq,
+            throttle_events: crate::sampling::throttle_events(&samples),
+            cpu_steady_state: None,
+            gpu_steady_state: None,
+            cpu_stress: None,
+            gpu_stress: None,
+            memory_stress: None,
+            samples,
+        }
+    }
+
+    fn sample(timestamp: u64, cpu_temp: f64, speed_limit: Option<f64>) -> SensorSample {
+        SensorSample {
+            timestamp,
+            cpu_temp: Some(cpu_temp),
+            cpu_freq_mhz: Some(3000.0),
+            cpu_speed_limit: speed_limit,
+            ..Default::default()
+        }
+    }
+
+    fn check(phase: &PhaseResult, criteria: &Assertions) -> Vec<AssertionResult> {
+        let mut out = Vec::new();
+        check_phase(0, phase, criteria, &mut out);
+        out
+    }
+
+    fn find<'a>(results: &'a [AssertionResult], name: &str) -> &'a AssertionResult {
+        results.iter().find(|result| result.name == name).unwrap()
+    }
+
+    #[test]
+    fn temperature_limit() {
+        let run = phase("load", PhaseEnd::DurationElapsed, vec![sample(0, 70.0, None), sample(1000, 85.0, None)]);
+        let results = check(&run, &Assertions { max_cpu_temp: Some(90.0), max_gpu_temp: Some(90.0), ..Default::default() });
+
+        assert!(find(&results, "completed").passed);
+        assert!(find(&results, "max_cpu_temp").passed);
+        assert_eq!(find(&results, "max_cpu_temp").message, "Peak 85.0°C, limit 90.0°C");
+        // No GPU readings at all fails rather than passing silently
+        assert!(!find(&results, "max_gpu_temp").passed);
+
+        let results = check(&run, &Assertions { max_cpu_temp: Some(80.0), ..Default::default() });
+        assert!(!find(&results, "max_cpu_temp").passed);
+    }
+
+    #[test]
+    fn no_throttling_is_skipped_without_speed_limit_data() {
+        let criteria = Assertions { no_throttling: Some(true), ..Default::default() };
+
+        let unreported = phase("load", PhaseEnd::DurationElapsed, vec![sample(0, 70.0, None), sample(1000, 70.0, None)]);
+        let result = check(&unreported, &criteria);
+        assert!(find(&result, "no_throttling").passed);
+        assert!(find(&result, "no_throttling").skipped);
+        assert_eq!(find(&result, "no_throttling").message, "No speed-limit data");
+
+        let full_speed = phase("load", PhaseEnd::DurationElapsed, vec![sample(0, 70.0, Some(100.0)), sample(1000, 70.0, Some(100.0))]);
+        let result = check(&full_speed, &criteria);
+        assert!(find(&result, "no_throttling").passed);
+        assert!(!find(&result, "no_throttling").skipped);
+
+        let throttled = phase(
+            "load",
+            PhaseEnd::DurationElapsed,
+            vec![sample(0, 70.0, Some(100.0)), sample(1000, 90.0, Some(80.0)), sample(3000, 80.0, Some(100.0))],
+        );
+        let result = check(&throttled, &criteria);
+        assert!(!find(&result, "no_throttling").passed);
+        assert_eq!(find(&result, "no_throttling").message, "1 throttling events, 2s throttled, speed limit down to 80%");
+    }
+
+    #[test]
+    fn frequency_floor_and_early_end() {
+        let run = phase("load", PhaseEnd::Aborted, vec![sample(0, 70.0, None)]);
+        let results = check(&run, &Assertions { min_cpu_freq_mhz: Some(3200.0), ..Default::default() });
+        assert!(!find(&results, "completed").passed);
+        assert!(!find(&results, "min_cpu_freq_mhz").passed);
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn phase_assertions_override_the_plan() {
+        let plan = Assertions { max_cpu_temp: Some(80.0), min_cpu_freq_mhz: Some(2000.0), ..Default::default() };
+        let phase = Assertions { max_cpu_temp: Some(95.0), ..Default::default() };
+
+        let merged = Assertions::merge(Some(&plan), Some(&phase), PhaseWorkload::Cpu);
+        assert_eq!(merged.max_cpu_temp, Some(95.0));
+        assert_eq!(merged.min_cpu_freq_mhz, Some(2000.0));
+        // The plan-wide frequency floor doesn't apply to idle phases
+        assert_eq!(Assertions::merge(Some(&plan), None, PhaseWorkload::Idle).min_cpu_freq_mhz, None);
+    }
+
+    #[test]
+    fn junit_has_a_suite_per_phase() {
+        let plan = TestPlan::parse(
+            r#"
+            name = "Fan & <curve>"
+            assertions = { max_cpu_temp = 80.0, no_throttling = true }
+
+            [[phases]]
+            name = "warm"
+            workload = "idle"
+            duration_secs = 10
+
+            [[phases]]
+            name = "never ran"
+            workload = "idle"
+            duration_secs = 10
+            "#,
+        )
+        .unwrap();
+        let result = PlanResult {
+            name: plan.name.clone(),
+            state: PlanState::Stopped,
+            started_at: 0,
+            finished_at: Some(10_000),
+            phases: vec![phase("warm", PhaseEnd::DurationElapsed, vec![sample(0, 85.0, None)])],
+        };
+
+        let report = evaluate(&plan, &result);
+        assert!(!report.passed);
+        let xml = to_junit(&report);
+
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("<testsuites name=\"Fan &amp; &lt;curve&gt;\" tests=\"4\" failures=\"2\" skipped=\"1\" time=\"10.000\">"), "{}", xml);
+        assert!(xml.contains("<testsuite name=\"warm\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"10.000\">"), "{}", xml);
+        assert!(xml.contains("<testsuite name=\"never ran\" tests=\"1\" failures=\"1\" skipped=\"0\" time=\"0.000\">"), "{}", xml);
+        assert!(xml.contains("name=\"max_cpu_temp\"><failure message=\"Peak 85.0°C, limit 80.0°C\"/>"), "{}", xml);
+        assert!(xml.contains("name=\"no_throttling\"><skipped message=\"No speed-limit data\"/>"), "{}", xml);
+        assert!(xml.contains("name=\"completed\"><system-out>Phase ended: DurationElapsed</system-out>"), "{}", xml);
+        assert!(xml.ends_with("</testsuites>\n"));
+    }
+}
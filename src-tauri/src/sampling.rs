@@ -0,0 +1,403 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// One set of sensor readings taken by a background thread
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SensorSample {
+    pub timestamp: u64,           // Unix timestamp in milliseconds
+    pub cpu_temp: Option<f64>,    // °C
+    pub gpu_temp: Option<f64>,    // °C
+    pub gpu_usage: Option<f64>,   // %
+    pub fans: Vec<(usize, f64)>,  // (fan index, RPM), including fans reading 0
+    pub cpu_freq_mhz: Option<f64>,     // Average over all cores
+    pub cpu_speed_limit: Option<f64>,  // Percent of full speed the OS allows; below 100 means throttled
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub other: BTreeMap<String, f64>,  // The sampler's remaining channels: per-core temperatures, CPU usage, stress state
+}
+
+impl SensorSample {
+    // One channel by id: "cpu_temp", "gpu_temp", "gpu_usage", "cpu_freq", "cpu_speed_limit", "fan_<index>",
+    // or one of the sampler's other channels when the sample came from a snapshot
+    pub fn value(&self, id: &str) -> Option<f64> {
+        match id {
+            "cpu_temp" => self.cpu_temp,
+            "gpu_temp" => self.gpu_temp,
+            "gpu_usage" => self.gpu_usage,
+            "cpu_freq" => self.cpu_freq_mhz,
+            "cpu_speed_limit" => self.cpu_speed_limit,
+            _ => match id.strip_prefix("fan_").and_then(|index| index.parse::<usize>().ok()) {
+                Some(index) => self.fans.iter().find(|&&(fan, _)| fan == index).map(|&(_, rpm)| rpm),
+                None => self.other.get(id).copied(),
+            },
+        }
+    }
+
+    // Every channel that has a reading, keyed by id
+    pub fn channels(&self) -> BTreeMap<String, f64> {
+        let mut values = BTreeMap::new();
+        for (id, value) in [
+            ("cpu_temp", self.cpu_temp),
+            ("gpu_temp", self.gpu_temp),
+            ("gpu_usage", self.gpu_usage),
+            ("cpu_freq", self.cpu_freq_mhz),
+            ("cpu_speed_limit", self.cpu_speed_limit),
+        ] {
+            if let Some(value) = value {
+                values.insert(id.to_string(), value);
+            }
+        }
+        for &(fan, rpm) in &self.fans {
+            values.insert(format!("fan_{}", fan), rpm);
+        }
+        values.extend(self.other.iter().map(|(id, &value)| (id.clone(), value)));
+        values
+    }
+}
+
+// The `SensorSample` channels of a snapshot, e.g. to analyse a recording like a plan phase
+impl From<&Snapshot> for SensorSample {
+    fn from(snapshot: &Snapshot) -> Self {
+        let value = |id: &str| snapshot.values.get(id).copied();
+        SensorSample {
+            timestamp: snapshot.timestamp,
+            cpu_temp: value("cpu_temp"),
+            gpu_temp: value("gpu_temp"),
+            gpu_usage: value("gpu_usage"),
+            fans: snapshot
+                .values
+                .iter()
+                .filter_map(|(id, &rpm)| Some((id.strip_prefix("fan_")?.parse().ok()?, rpm)))
+                .collect(),
+            cpu_freq_mhz: value("cpu_freq"),
+            cpu_speed_limit: value("cpu_speed_limit"),
+            other: snapshot
+                .values
+                .iter()
+                .filter(|&(id, _)| !is_sample_field(id))
+                .map(|(id, &value)| (id.clone(), value))
+                .collect(),
+        }
+    }
+}
+
+// Whether `id` is kept in one of `SensorSample`'s own fields rather than in `other`
+fn is_sample_field(id: &str) -> bool {
+    matches!(id, "cpu_temp" | "gpu_temp" | "gpu_usage" | "cpu_freq" | "cpu_speed_limit")
+        || id.strip_prefix("fan_").is_some_and(|index| index.parse::<usize>().is_ok())
+}
+
+// Whether `id` names a channel the sampler records, and so one `SensorSample::value` can read
+pub fn is_channel(id: &str) -> bool {
+    let indexed = |prefix: &str| id.strip_prefix(prefix).is_some_and(|index| index.parse::<usize>().is_ok());
+    let stress = |suffix: &str| {
+        id.strip_suffix(suffix).is_some_and(|name| matches!(name, "cpu" | "gpu" | "memory"))
+    };
+    is_sample_field(id)
+        || id == "cpu_usage"
+        || indexed("cpu_usage_")
+        || indexed("core_temp_")
+        || stress("_stress_running")
+        || stress("_stress_ops_per_sec")
+}
+
+// (kind, unit) of a snapshot channel id, for metadata and labels
+pub fn channel_kind(id: &str) -> (&'static str, &'static str) {
+    if id.ends_with("_temp") || id.starts_with("core_temp_") {
+        ("temperature", "°C")
+    } else if id.starts_with("fan_") {
+        ("fan", "RPM")
+    } else if id == "cpu_freq" {
+        ("frequency", "MHz")
+    } else if id == "cpu_speed_limit" {
+        ("throttle", "%")
+    } else if id.contains("_usage") {
+        ("usage", "%")
+    } else if id.ends_with("_stress_running") {
+        ("stress_state", "")
+    } else if id.ends_with("_ops_per_sec") {
+        ("throughput", "ops/s")
+    } else {
+        ("other", "")
+    }
+}
+
+// Readable name of a snapshot channel id, e.g. "CPU core 3" for core_temp_3
+pub fn channel_label(id: &str) -> String {
+    let indexed = |prefix: &str| id.strip_prefix(prefix).filter(|index| index.parse::<usize>().is_ok());
+    if let Some(core) = indexed("core_temp_") {
+        return format!("CPU core {}", core);
+    }
+    if let Some(cpu) = indexed("cpu_usage_") {
+        return format!("CPU {}", cpu);
+    }
+    if let Some(fan) = indexed("fan_") {
+        return format!("Fan {}", fan);
+    }
+    let stress = id.strip_suffix("_stress_running").or(id.strip_suffix("_stress_ops_per_sec"));
+    let device = match stress.unwrap_or(id).split('_').next() {
+        Some("cpu") => "CPU",
+        Some("gpu") => "GPU",
+        Some("memory") => "Memory",
+        _ => return id.to_string(),
+    };
+    match (stress, id) {
+        (Some(_), _) => format!("{} stress test", device),
+        (None, "cpu_speed_limit") => "CPU speed limit".to_string(),
+        _ => device.to_string(),
+    }
+}
+
+// Sensor reader shared by the watchdog and the test plan runner
+pub type SensorReader = Arc<dyn Fn() -> SensorSample + Send + Sync>;
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Running min/max/average of one series
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct SeriesStats {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub avg: Option<f64>,
+    pub count: usize,
+}
+
+impl SeriesStats {
+    pub fn add(&mut self, value: f64) {
+        let sum = self.avg.unwrap_or(0.0) * self.count as f64 + value;
+        self.count += 1;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+        self.avg = Some(sum / self.count as f64);
+    }
+
+    pub fn add_opt(&mut self, value: Option<f64>) {
+        if let Some(value) = value {
+            self.add(value);
+        }
+    }
+}
+
+// A stretch of samples during which the OS limited CPU speed
+#[derive(Debug, Clone, Serialize)]
+pub struct ThrottleEvent {
+    pub started_at: u64,          // Unix timestamp in milliseconds
+    pub ended_at: u64,            // First unthrottled sample, or the last sample if throttling never ended
+    pub min_speed_limit: f64,     // Lowest speed limit seen, in percent
+}
+
+impl ThrottleEvent {
+    pub fn duration_secs(&self) -> f64 {
+        self.ended_at.saturating_sub(self.started_at) as f64 / 1000.0
+    }
+}
+
+// Find the throttling events in a series of samples
+pub fn throttle_events(samples: &[SensorSample]) -> Vec<ThrottleEvent> {
+    let mut events = Vec::new();
+    let mut current: Option<ThrottleEvent> = None;
+
+    for sample in samples {
+        match sample.cpu_speed_limit.filter(|&limit| limit < 100.0) {
+            Some(limit) => {
+                let event = current.get_or_insert(ThrottleEvent {
+                    started_at: sample.timestamp,
+                    ended_at: sample.timestamp,
+                    min_speed_limit: limit,
+                });
+                event.ended_at = sample.timestamp;
+                event.min_speed_limit = event.min_speed_limit.min(limit);
+            }
+            None => {
+                if let Some(mut event) = current.take() {
+                    event.ended_at = sample.timestamp;
+                    events.push(event);
+                }
+            }
+        }
+    }
+
+    events.extend(current);
+    events
+}
+
+// Everything the background sampler read in one tick, keyed by channel id.
+// Holds the `SensorSample` channels plus per-CPU usage and per-core temperatures.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub timestamp: u64,  // Unix timestamp in milliseconds
+    pub values: BTreeMap<String, f64>,
+}
+
+pub type SnapshotSink = Arc<dyn Fn(&Snapshot) + Send + Sync>;
+
+// Background sampler: reads a snapshot every interval and hands it to each subscriber
+#[derive(Default)]
+pub struct Sampler {
+    sinks: Mutex<Vec<SnapshotSink>>,
+    latest: Mutex<Option<Snapshot>>,
+    live: Mutex<Option<Snapshot>>,  // Latest live reading, kept even while suspended
+    suspended: AtomicBool,  // Live readings are dropped, e.g. while a recording is replayed
+}
+
+impl Sampler {
+    // Runs for the life of the app
+    pub fn start<F>(&'static self, interval: Duration, mut read: F)
+    where
+        F: FnMut() -> Snapshot + Send + 'static,
+    {
+        thread::spawn(move || loop {
+            let tick = Instant::now();
+            // Keep reading while suspended so rates like CPU usage stay current
+            let snapshot = read();
+            if !self.suspended.load(Ordering::SeqCst) {
+                self.publish(&snapshot);
+            }
+            *self.live.lock() = Some(snapshot);
+            thread::sleep(interval.saturating_sub(tick.elapsed()));
+        });
+    }
+
+    pub fn subscribe(&self, sink: SnapshotSink) {
+        self.sinks.lock().push(sink);
+    }
+
+    pub fn publish(&self, snapshot: &Snapshot) {
+        *self.latest.lock() = Some(snapshot.clone());
+        // Clone the list so a slow sink doesn't block subscribing
+        let sinks = self.sinks.lock().clone();
+        for sink in sinks {
+            sink(snapshot);
+        }
+    }
+
+    pub fn latest(&self) -> Option<Snapshot> {
+        self.latest.lock().clone()
+    }
+
+    // Latest reading of the real sensors, even during a replay; None before the sampler has started
+    pub fn latest_live(&self) -> Option<Snapshot> {
+        self.live.lock().clone()
+    }
+
+    // Stop or resume publishing live readings; `publish` still works for other sources
+    pub fn suspend_live(&self, suspended: bool) {
+        self.suspended.store(suspended, Ordering::SeqCst);
+    }
+
+    pub fn is_live(&self) -> bool {
+        !self.suspended.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(values: &[Option<f64>]) -> Vec<SensorSample> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &cpu_speed_limit)| SensorSample { timestamp: i as u64 * 1000, cpu_speed_limit, ..Default::default() })
+            .collect()
+    }
+
+    #[test]
+    fn throttle_events_span_limited_samples() {
+        let samples = limits(&[Some(100.0), Some(80.0), Some(70.0), Some(100.0), None, Some(90.0)]);
+        let events = throttle_events(&samples);
+        assert_eq!(events.len(), 2);
+
+        // Ends at the first full-speed sample
+        assert_eq!((events[0].started_at, events[0].ended_at, events[0].min_speed_limit), (1000, 3000, 70.0));
+        assert_eq!(events[0].duration_secs(), 2.0);
+        // Still throttled at the end, so it ends at the last sample
+        assert_eq!((events[1].started_at, events[1].ended_at, events[1].min_speed_limit), (5000, 5000, 90.0));
+    }
+
+    #[test]
+    fn no_throttle_events_without_a_limit() {
+        assert!(throttle_events(&limits(&[Some(100.0), None, Some(100.0)])).is_empty());
+        assert!(throttle_events(&[]).is_empty());
+    }
+
+    #[test]
+    fn missing_reading_ends_a_throttle_event() {
+        let events = throttle_events(&limits(&[Some(50.0), None, Some(60.0)]));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].ended_at, 1000);
+    }
+
+    #[test]
+    fn sample_channels_round_trip_through_a_snapshot() {
+        let sample = SensorSample {
+            timestamp: 1234,
+            cpu_temp: Some(55.0),
+            gpu_usage: Some(12.0),
+            fans: vec![(0, 1200.0), (1, 0.0)],
+            cpu_speed_limit: Some(100.0),
+            ..Default::default()
+        };
+        let snapshot = Snapshot { timestamp: sample.timestamp, values: sample.channels() };
+        let back = SensorSample::from(&snapshot);
+
+        assert_eq!(back.value("cpu_temp"), Some(55.0));
+        assert_eq!(back.value("gpu_temp"), None);
+        assert_eq!(back.value("fan_1"), Some(0.0));
+        assert_eq!(back.fans, sample.fans);
+        assert_eq!(back.channels(), sample.channels());
+    }
+
+    #[test]
+    fn snapshot_channels_outside_the_sample_fields_are_kept() {
+        let mut values = BTreeMap::new();
+        values.insert("cpu_temp".to_string(), 60.0);
+        values.insert("core_temp_2".to_string(), 64.0);
+        values.insert("cpu_usage".to_string(), 35.0);
+        let sample = SensorSample::from(&Snapshot { timestamp: 1, values: values.clone() });
+
+        assert_eq!(sample.cpu_temp, Some(60.0));
+        assert_eq!(sample.value("core_temp_2"), Some(64.0));
+        assert_eq!(sample.value("cpu_usage"), Some(35.0));
+        assert!(!sample.other.contains_key("cpu_temp"));
+        assert_eq!(sample.channels(), values);
+    }
+
+    #[test]
+    fn channel_names() {
+        assert!(is_channel("fan_2"));
+        assert!(is_channel("core_temp_3"));
+        assert!(is_channel("cpu_usage"));
+        assert!(is_channel("cpu_usage_11"));
+        assert!(is_channel("memory_stress_running"));
+        assert!(!is_channel("fan_x"));
+        assert!(!is_channel("core_temp_"));
+        assert!(!is_channel("disk_stress_running"));
+        assert_eq!(channel_kind("core_temp_3"), ("temperature", "°C"));
+        assert_eq!(channel_kind("cpu_usage_0"), ("usage", "%"));
+        assert_eq!(channel_kind("gpu_stress_ops_per_sec"), ("throughput", "ops/s"));
+        assert_eq!(channel_label("core_temp_3"), "CPU core 3");
+        assert_eq!(channel_label("memory_stress_running"), "Memory stress test");
+        assert_eq!(channel_label("cpu_speed_limit"), "CPU speed limit");
+        assert_eq!(channel_label("mystery"), "mystery");
+    }
+
+    #[test]
+    fn series_stats() {
+        let mut stats = SeriesStats::default();
+        for value in [3.0, 1.0, 2.0] {
+            stats.add(value);
+        }
+        stats.add_opt(None);
+        assert_eq!((stats.min, stats.max, stats.avg, stats.count), (Some(1.0), Some(3.0), Some(2.0), 3));
+    }
+}
@@ -0,0 +1,1064 @@
+#[cfg(target_os = "macos")]
+use io_kit_sys::types::{io_connect_t, io_iterator_t};
+#[cfg(target_os = "macos")]
+use io_kit_sys::*;
+#[cfg(target_os = "macos")]
+use mach::kern_return::*;
+#[cfg(target_os = "macos")]
+use mach::traps::mach_task_self;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+#[cfg(target_os = "macos")]
+use std::mem;
+#[cfg(target_os = "macos")]
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{CpuExt, CpuRefreshKind, System, SystemExt};
+
+use crate::recording::MachineInfo;
+use crate::sampling::{self, SensorSample, Snapshot};
+use crate::simulation::{self, SimLoad, SimReading, SimulatedMachine};
+use crate::stress::StressState;
+use crate::{GPU_STRESS_TEST, MEMORY_STRESS_TEST, STRESS_TEST};
+
+// Constants for SMC keys
+#[cfg(target_os = "macos")]
+const KERNEL_INDEX_SMC: u32 = 2;
+#[cfg(target_os = "macos")]
+const SMC_CMD_READ_BYTES: u8 = 5;
+#[cfg(target_os = "macos")]
+const SMC_CMD_READ_KEYINFO: u8 = 9;
+
+// Fan IDs
+pub const CPU_FAN_ID: u8 = 0;  // CPU fan identifier
+pub const GPU_FAN_ID: u8 = 1;  // GPU fan identifier
+
+// CPU temperature sensor keys in priority order
+const CPU_TEMP_KEYS: [&str; 5] = [
+    "TC0P",  // CPU Proximity
+    "TC0D",  // CPU Die
+    "TC0E",  // CPU Electric
+    "TC0F",  // CPU Package
+    "TC0c",  // CPU Core
+];
+
+// Simulated machine replacing every sensor, when enabled with --simulate or TEMPDETECT_SIMULATE
+static SIMULATION: Lazy<Option<SimulatedMachine>> = Lazy::new(|| {
+    let args: Vec<String> = std::env::args().collect();
+    match simulation::requested(&args).and_then(|config| config.map(SimulatedMachine::new).transpose()) {
+        Ok(machine) => machine,
+        Err(e) => {
+            eprintln!("Simulation disabled: {}", e);
+            None
+        }
+    }
+});
+
+pub fn is_simulated() -> bool {
+    SIMULATION.is_some()
+}
+
+// Current reading of the simulated machine, which heats up with our own stress tests
+pub fn simulated() -> Option<SimReading> {
+    let machine = SIMULATION.as_ref()?;
+    let load = SimLoad::from_stress(&STRESS_TEST.status(), &GPU_STRESS_TEST.status(), &MEMORY_STRESS_TEST.status());
+    Some(machine.read(&load))
+}
+
+#[cfg(target_os = "macos")]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct SMCKeyData {
+    key: u32,
+    vers: [u8; 6],
+    p_limit_data: [u8; 16],
+    key_info: SMCKeyInfoData,
+    result: u8,
+    status: u8,
+    data8: u8,
+    data32: u32,
+    bytes: [u8; 32],
+}
+
+#[cfg(target_os = "macos")]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct SMCKeyInfoData {
+    data_size: u32,
+    data_type: [u8; 4],  // 4-byte data type
+    data_attributes: u8,
+}
+
+// Union for float conversion
+#[cfg(target_os = "macos")]
+#[repr(C)]
+union FloatUnion {
+    f: f32,
+    b: [u8; 4],
+}
+
+#[cfg(target_os = "macos")]
+#[derive(Clone)]
+pub struct SMC {
+    connection: io_connect_t,
+}
+
+#[cfg(target_os = "macos")]
+impl SMC {
+    pub fn new() -> Result<Self, String> {
+        unsafe {
+            let mut iterator: io_iterator_t = 0;
+            let matching = IOServiceMatching(c"AppleSMC".as_ptr());
+            
+            if matching.is_null() {
+                return Err("Failed to create matching dictionary".to_string());
+            }
+
+            let result = IOServiceGetMatchingServices(
+                kIOMasterPortDefault,
+                matching,
+                &mut iterator,
+            );
+
+            if result != KERN_SUCCESS {
+                return Err(format!("Failed to get matching services: {}", result));
+            }
+
+            let device = IOIteratorNext(iterator);
+            IOObjectRelease(iterator);
+
+            if device == 0 {
+                return Err("Failed to find SMC device".to_string());
+            }
+
+            let mut connection: io_connect_t = 0;
+            let result = IOServiceOpen(device, mach_task_self(), 0, &mut connection);
+            IOObjectRelease(device);
+
+            if result != KERN_SUCCESS {
+                return Err(format!("Failed to open SMC connection: {}", result));
+            }
+
+            Ok(SMC { connection })
+        }
+    }
+
+    pub fn read_key(&self, key: &str) -> Result<f64, String> {
+        if key.len() != 4 {
+            return Err("Invalid key length".to_string());
+        }
+
+        let mut input: SMCKeyData = unsafe { mem::zeroed() };
+        let mut output: SMCKeyData = unsafe { mem::zeroed() };
+        
+        // Set key value
+        let key_bytes = key.as_bytes();
+        input.key = u32::from_be_bytes([
+            key_bytes[0],
+            key_bytes[1],
+            key_bytes[2],
+            key_bytes[3],
+        ]);
+
+        // Get key info
+        input.data8 = SMC_CMD_READ_KEYINFO;
+        let mut output_size = mem::size_of::<SMCKeyData>();
+        
+        let result = unsafe {
+            IOConnectCallStructMethod(
+                self.connection,
+                KERNEL_INDEX_SMC,
+                &input as *const _ as *const _,
+                mem::size_of::<SMCKeyData>(),
+                &mut output as *mut _ as *mut _,
+                &mut output_size,
+            )
+        };
+
+        if result != KERN_SUCCESS {
+            return Err(format!("Failed to get key info: {}", result));
+        }
+
+        // Get data type
+        let data_type: String = output.key_info.data_type.iter()
+            .rev()  // Reverse byte order
+            .take_while(|&&b| b != 0)
+            .map(|&b| b as char)
+            .collect();
+        let data_type = data_type.trim();
+
+        // Read data
+        input.key_info.data_size = output.key_info.data_size;
+        input.data8 = SMC_CMD_READ_BYTES;
+
+        let result = unsafe {
+            IOConnectCallStructMethod(
+                self.connection,
+                KERNEL_INDEX_SMC,
+                &input as *const _ as *const _,
+                mem::size_of::<SMCKeyData>(),
+                &mut output as *mut _ as *mut _,
+                &mut output_size,
+            )
+        };
+
+        if result != KERN_SUCCESS {
+            return Err(format!("Failed to read data: {}", result));
+        }
+
+        // Convert value based on data type
+        match data_type {
+            "sp78" => {
+                let int_part = output.bytes[0] as i8 as f64;
+                let frac_part = (output.bytes[1] as f64) / 128.0;
+                Ok(int_part + frac_part)
+            },
+            "fpe2" => {
+                let raw_val = ((output.bytes[0] as u16) << 8 | output.bytes[1] as u16) as f64;
+                Ok(raw_val / 4.0)
+            },
+            "flt" => {
+                let mut flt = FloatUnion { b: [0; 4] };
+                unsafe {
+                    flt.b[0] = output.bytes[0];
+                    flt.b[1] = output.bytes[1];
+                    flt.b[2] = output.bytes[2];
+                    flt.b[3] = output.bytes[3];
+                    Ok(flt.f as f64)
+                }
+            },
+            _ => {
+                // For unknown type, try to process temperature data as sp78
+                let int_part = output.bytes[0] as i8 as f64;
+                let frac_part = (output.bytes[1] as f64) / 128.0;
+                Ok(int_part + frac_part)
+            }
+        }
+    }
+
+    pub fn get_fan_speed(&self, fan_num: u8) -> Result<f64, String> {
+        let key = format!("F{}Ac", fan_num);
+        
+        let mut input: SMCKeyData = unsafe { mem::zeroed() };
+        let mut output: SMCKeyData = unsafe { mem::zeroed() };
+        
+        // Set key value
+        let key_bytes = key.as_bytes();
+        input.key = u32::from_be_bytes([
+            key_bytes[0],
+            key_bytes[1],
+            key_bytes[2],
+            key_bytes[3],
+        ]);
+
+        // Get key info
+        input.data8 = SMC_CMD_READ_KEYINFO;
+        let mut output_size = mem::size_of::<SMCKeyData>();
+        
+        let result = unsafe {
+            IOConnectCallStructMethod(
+                self.connection,
+                KERNEL_INDEX_SMC,
+                &input as *const _ as *const _,
+                mem::size_of::<SMCKeyData>(),
+                &mut output as *mut _ as *mut _,
+                &mut output_size,
+            )
+        };
+
+        if result != KERN_SUCCESS {
+            return Err(format!("Failed to get key info: {}", result));
+        }
+
+        // Get data type
+        let data_type: String = output.key_info.data_type.iter()
+            .rev()  // Reverse byte order
+            .take_while(|&&b| b != 0)
+            .map(|&b| b as char)
+            .collect();
+        let data_type = data_type.trim();
+
+        // Read data
+        input.key_info.data_size = output.key_info.data_size;
+        input.data8 = SMC_CMD_READ_BYTES;
+
+        let result = unsafe {
+            IOConnectCallStructMethod(
+                self.connection,
+                KERNEL_INDEX_SMC,
+                &input as *const _ as *const _,
+                mem::size_of::<SMCKeyData>(),
+                &mut output as *mut _ as *mut _,
+                &mut output_size,
+            )
+        };
+
+        if result != KERN_SUCCESS {
+            return Err(format!("Failed to read data: {}", result));
+        }
+
+        // Convert value based on data type
+        let rpm = match data_type {
+            "fpe2" => {
+                let int_val = ((output.bytes[0] as u16) << 8 | output.bytes[1] as u16) as f64;
+                int_val / 4.0
+            },
+            "flt" => {
+                let mut flt = FloatUnion { b: [0; 4] };
+                unsafe {
+                    flt.b[0] = output.bytes[0];
+                    flt.b[1] = output.bytes[1];
+                    flt.b[2] = output.bytes[2];
+                    flt.b[3] = output.bytes[3];
+                    flt.f as f64
+                }
+            },
+            _ => {
+                let int_val = ((output.bytes[0] as u16) << 8 | output.bytes[1] as u16) as f64;
+                int_val / 4.0
+            }
+        };
+
+        // Verify rpm value is reasonable (0-20000 RPM)
+        if (0.0..=20000.0).contains(&rpm) {
+            Ok(rpm)
+        } else {
+            Ok(0.0)
+        }
+    }
+
+    pub fn get_all_fan_speeds(&self) -> Result<Vec<(usize, f64)>, String> {
+        let mut speeds = Vec::new();
+        
+        // Get CPU fan speed
+        match self.get_fan_speed(CPU_FAN_ID) {
+            Ok(rpm) if rpm > 0.0 => {
+                println!("CPU fan speed: {:.0} RPM", rpm);
+                speeds.push((CPU_FAN_ID as usize, rpm));
+            },
+            Ok(_) => println!("CPU fan not running"),
+            Err(e) => println!("Read CPU fan speed error: {}", e),
+        }
+        
+        // Get GPU fan speed
+        match self.get_fan_speed(GPU_FAN_ID) {
+            Ok(rpm) if rpm > 0.0 => {
+                println!("GPU fan speed: {:.0} RPM", rpm);
+                speeds.push((GPU_FAN_ID as usize, rpm));
+            },
+            Ok(_) => println!("GPU fan not running"),
+            Err(e) => println!("Read GPU fan speed error: {}", e),
+        }
+        
+        Ok(speeds)
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for SMC {
+    fn drop(&mut self) {
+        if self.connection != 0 {
+            unsafe {
+                IOServiceClose(self.connection);
+            }
+        }
+    }
+}
+
+// There's no SMC without IOKit, so every SMC reading takes its error path
+#[cfg(not(target_os = "macos"))]
+#[derive(Clone)]
+pub struct SMC;
+
+#[cfg(not(target_os = "macos"))]
+impl SMC {
+    pub fn new() -> Result<Self, String> {
+        Err("The SMC is only available on macOS".to_string())
+    }
+
+    pub fn read_key(&self, _key: &str) -> Result<f64, String> {
+        Err("The SMC is only available on macOS".to_string())
+    }
+
+    pub fn get_fan_speed(&self, _fan_num: u8) -> Result<f64, String> {
+        Err("The SMC is only available on macOS".to_string())
+    }
+
+    pub fn get_all_fan_speeds(&self) -> Result<Vec<(usize, f64)>, String> {
+        Err("The SMC is only available on macOS".to_string())
+    }
+}
+
+impl SMC {
+    pub fn get_cpu_temp(&self) -> Result<f64, String> {
+        let mut total_temp = 0.0;
+        let mut valid_temps = 0;
+
+        // Try reading temperature from each core
+        for core in 0..cpu_cores() {
+            let key = format!("TC{}C", core);
+            if let Ok(temp) = self.read_key(&key) {
+                if temp > 0.0 && temp < 150.0 {  // Check for valid temperature range
+                    total_temp += temp;
+                    valid_temps += 1;
+                }
+            }
+        }
+
+        // If we got valid core temperatures, return the average
+        if valid_temps > 0 {
+            return Ok(total_temp / valid_temps as f64);
+        }
+
+        // If no core temperatures available, try other sensors in priority order
+        for key in CPU_TEMP_KEYS.iter() {
+            if let Ok(temp) = self.read_key(key) {
+                if temp > 0.0 && temp < 150.0 {
+                    return Ok(temp);
+                }
+            }
+        }
+        
+        Err("Unable to read CPU temperature".to_string())
+    }
+}
+
+// ioreg query for the GPU performance statistics
+pub const GPU_STATS_QUERY: &str = "ioreg -l |grep \"PerformanceStatistics\" | cut -d '{' -f 2 | tr '|' ',' | tr -d '}' | tr ',' '\n'|grep 'Temp\\|Fan\\|GPU Activity'";
+
+// Parse GPU usage, temperature and fan speed from the ioreg output
+pub fn parse_gpu_stats(output_str: &str) -> (i32, i32, i32) {
+    let mut gpu_usage = 0;
+    let mut gpu_temp = 0;
+    let mut fan_speed = 0;
+
+    for line in output_str.lines() {
+        let line = line.trim();
+        if line.contains("GPU Activity") {
+            if let Some(value) = line.split('=').nth(1) {
+                gpu_usage = value.trim().parse::<f64>().unwrap_or(0.0).round() as i32;
+            }
+        } else if line.contains("Temp") {
+            if let Some(value) = line.split('=').nth(1) {
+                gpu_temp = value.trim().parse::<f64>().unwrap_or(0.0).round() as i32;
+            }
+        } else if line.contains("Fan") {
+            if let Some(value) = line.split('=').nth(1) {
+                fan_speed = value.trim().parse::<f64>().unwrap_or(0.0).round() as i32;
+            }
+        }
+    }
+
+    (gpu_usage, gpu_temp, fan_speed)
+}
+
+// Blocking GPU (usage %, temperature °C) read for background threads
+fn read_gpu_stats() -> (Option<f64>, Option<f64>) {
+    #[cfg(target_os = "macos")]
+    {
+        let Ok(output) = Command::new("sh").arg("-c").arg(GPU_STATS_QUERY).output() else {
+            return (None, None);
+        };
+        let (gpu_usage, gpu_temp, _) = parse_gpu_stats(&String::from_utf8_lossy(&output.stdout));
+        let gpu_temp = if gpu_temp > 0 { Some(gpu_temp as f64) } else { None };
+        (Some(gpu_usage as f64), gpu_temp)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        (None, None)
+    }
+}
+
+// CPU speed limit the OS currently enforces, in percent (`pmset -g therm`)
+fn read_cpu_speed_limit() -> Option<f64> {
+    #[cfg(target_os = "macos")]
+    {
+        let output = Command::new("pmset").args(["-g", "therm"]).output().ok()?;
+        parse_cpu_speed_limit(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        None
+    }
+}
+
+// Parses the "CPU_Speed_Limit = 100" line; Apple Silicon Macs don't report one
+#[cfg(target_os = "macos")]
+fn parse_cpu_speed_limit(output: &str) -> Option<f64> {
+    output
+        .lines()
+        .find(|line| line.contains("CPU_Speed_Limit"))
+        .and_then(|line| line.split('=').nth(1))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+// Average CPU frequency over all cores, in MHz
+fn read_cpu_frequency() -> Option<f64> {
+    let mut sys = System::new();
+    sys.refresh_cpu_specifics(CpuRefreshKind::new().with_frequency());
+    let cpus = sys.cpus();
+    if cpus.is_empty() {
+        return None;
+    }
+    Some(cpus.iter().map(|cpu| cpu.frequency() as f64).sum::<f64>() / cpus.len() as f64)
+}
+
+// Sensor readings for background threads (stress test watchdog, test plans)
+pub fn read_sensor_sample() -> SensorSample {
+    if let Some(reading) = simulated() {
+        return reading.sample();
+    }
+
+    let (gpu_usage, gpu_temp) = read_gpu_stats();
+    let mut sample = SensorSample {
+        timestamp: sampling::now_millis(),
+        gpu_temp,
+        gpu_usage,
+        cpu_freq_mhz: read_cpu_frequency(),
+        cpu_speed_limit: read_cpu_speed_limit(),
+        ..Default::default()
+    };
+
+    if let Ok(smc) = SMC::new() {
+        sample.cpu_temp = smc.get_cpu_temp().ok();
+        // Unlike get_all_fan_speeds, keep fans reading 0 RPM so stalls are visible
+        for fan in [CPU_FAN_ID, GPU_FAN_ID] {
+            if let Ok(rpm) = smc.get_fan_speed(fan) {
+                sample.fans.push((fan as usize, rpm));
+            }
+        }
+    }
+
+    sample
+}
+
+// Everything the background sampler records: the sensor sample plus per-CPU usage and per-core temperatures.
+// `sys` is kept between calls so CPU usage covers the time since the last snapshot.
+pub fn read_snapshot(sys: &mut System) -> Snapshot {
+    let (sample, usage, core_temps) = match simulated() {
+        Some(reading) => (reading.sample(), reading.cpu_usage, reading.core_temps),
+        None => {
+            sys.refresh_cpu();
+            let usage = sys.cpus().iter().map(|cpu| cpu.cpu_usage().clamp(0.0, 100.0) as f64).collect();
+            (read_sensor_sample(), usage, read_core_temps())
+        }
+    };
+    let mut values = sample.channels();
+
+    if !usage.is_empty() {
+        values.insert("cpu_usage".to_string(), usage.iter().sum::<f64>() / usage.len() as f64);
+    }
+    for (index, value) in usage.into_iter().enumerate() {
+        values.insert(format!("cpu_usage_{}", index), value);
+    }
+    for (core, temp) in core_temps.into_iter().enumerate() {
+        if !temp.is_nan() {
+            values.insert(format!("core_temp_{}", core), temp);
+        }
+    }
+
+    // Stress state as numbers so every consumer can treat it like any other channel
+    for (name, slot) in [("cpu", &STRESS_TEST), ("gpu", &GPU_STRESS_TEST), ("memory", &MEMORY_STRESS_TEST)] {
+        let status = slot.status();
+        let running = status.state == StressState::Running;
+        values.insert(format!("{}_stress_running", name), if running { 1.0 } else { 0.0 });
+        values.insert(format!("{}_stress_ops_per_sec", name), if running { status.ops_per_sec } else { 0.0 });
+    }
+
+    Snapshot { timestamp: sample.timestamp, values }
+}
+
+// Per-core SMC temperatures, NaN where a core has no valid reading
+fn read_core_temps() -> Vec<f64> {
+    let Ok(smc) = SMC::new() else {
+        return Vec::new();
+    };
+    (0..cpu_cores())
+        .map(|core| match smc.read_key(&format!("TC{}C", core)) {
+            Ok(temp) if temp > 0.0 && temp < 150.0 => temp,
+            _ => f64::NAN,
+        })
+        .collect()
+}
+
+// How long the on-demand readings below are reused, so several views polling at once share one read
+const READING_CACHE: Duration = Duration::from_millis(500);
+
+struct ReadingCache<T> {
+    reading: Mutex<Option<(Instant, T)>>,
+}
+
+impl<T: Clone> ReadingCache<T> {
+    const fn new() -> Self {
+        ReadingCache { reading: Mutex::new(None) }
+    }
+
+    fn get(&self) -> Option<T> {
+        match &*self.reading.lock() {
+            Some((at, reading)) if at.elapsed() < READING_CACHE => Some(reading.clone()),
+            _ => None,
+        }
+    }
+
+    fn set(&self, reading: T) {
+        *self.reading.lock() = Some((Instant::now(), reading));
+    }
+}
+
+// (per thread, per core, total)
+static CPU_USAGE_CACHE: ReadingCache<(Vec<i32>, Vec<i32>, i32)> = ReadingCache::new();
+static CPU_TEMP_CACHE: ReadingCache<f64> = ReadingCache::new();
+static FAN_SPEED_CACHE: ReadingCache<Vec<(usize, f64)>> = ReadingCache::new();  // (fan index, RPM)
+static GPU_STATS_CACHE: ReadingCache<(i32, i32, i32)> = ReadingCache::new();  // GPU usage, temperature, fan speed
+
+// CPU usage in percent per thread, per physical core and in total. Blocks for a second when not cached.
+pub fn cpu_usage() -> (Vec<i32>, Vec<i32>, i32) {
+    if let Some(reading) = simulated() {
+        let threads: Vec<i32> = reading.cpu_usage.iter().map(|&usage| usage.round() as i32).collect();
+        let per_core = threads.len() / reading.core_temps.len();
+        let cores: Vec<i32> = threads.chunks(per_core).map(|core| core.iter().sum::<i32>() / core.len() as i32).collect();
+        let total = cores.iter().sum::<i32>() / cores.len() as i32;
+        return (threads, cores, total);
+    }
+    if let Some(usage) = CPU_USAGE_CACHE.get() {
+        return usage;
+    }
+
+    let mut sys = System::new_all();
+    let num_cores = cpu_cores();
+    let num_threads = cpu_threads();
+
+    // Usage is measured between two refreshes; a second apart gives stable values
+    sys.refresh_cpu();
+    thread::sleep(Duration::from_millis(1000));
+    sys.refresh_cpu();
+
+    let thread_usage: Vec<i32> = sys.cpus().iter().map(|cpu| cpu.cpu_usage().clamp(0.0, 100.0) as i32).collect();
+
+    // Calculate usage for each physical core
+    let mut core_usage = Vec::with_capacity(num_cores);
+    let mut thread_idx = 0;
+
+    while thread_idx < num_threads {
+        if thread_idx + 1 < num_threads && thread_idx / 2 < num_cores {
+            // For hyper-threaded cores, take average of two threads
+            let core_load = (thread_usage[thread_idx] + thread_usage[thread_idx + 1]) / 2;
+            core_usage.push(core_load);
+            thread_idx += 2;
+        } else if thread_idx < num_threads && thread_idx < num_cores {
+            // For single-threaded cores, use thread usage directly
+            core_usage.push(thread_usage[thread_idx]);
+            thread_idx += 1;
+        } else {
+            break;
+        }
+    }
+
+    let total_usage = if core_usage.is_empty() {
+        0
+    } else {
+        core_usage.iter().sum::<i32>() / core_usage.len() as i32
+    };
+
+    let usage = (thread_usage, core_usage, total_usage);
+    CPU_USAGE_CACHE.set(usage.clone());
+    usage
+}
+
+// Average CPU temperature in °C, 0 when it can't be read
+pub fn cpu_temp() -> f64 {
+    if let Some(reading) = simulated() {
+        return reading.cpu_temp;
+    }
+    if let Some(temp) = CPU_TEMP_CACHE.get() {
+        return temp;
+    }
+
+    let temp = match SMC::new().and_then(|smc| smc.get_cpu_temp()) {
+        Ok(temp) => temp,
+        Err(e) => {
+            println!("读取CPU温度失败: {}", e);
+            0.0
+        }
+    };
+    CPU_TEMP_CACHE.set(temp);
+    temp
+}
+
+// Every fan as (index, RPM); empty when the SMC can't be read
+pub fn fan_speeds() -> Vec<(usize, f64)> {
+    if let Some(reading) = simulated() {
+        return reading.fans;
+    }
+    if let Some(speeds) = FAN_SPEED_CACHE.get() {
+        return speeds;
+    }
+
+    let speeds = SMC::new().and_then(|smc| smc.get_all_fan_speeds()).unwrap_or_default();
+    FAN_SPEED_CACHE.set(speeds.clone());
+    speeds
+}
+
+// GPU usage in percent, temperature in °C and fan speed in RPM, all 0 off macOS
+pub fn gpu_stats() -> Result<(i32, i32, i32), String> {
+    if let Some(reading) = simulated() {
+        let fan = reading.fans.get(GPU_FAN_ID as usize).or(reading.fans.first()).map_or(0.0, |&(_, rpm)| rpm);
+        return Ok((reading.gpu_usage.round() as i32, reading.gpu_temp.round() as i32, fan.round() as i32));
+    }
+    if let Some(stats) = GPU_STATS_CACHE.get() {
+        return Ok(stats);
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = Command::new("sh").arg("-c").arg(GPU_STATS_QUERY).output().map_err(|e| e.to_string())?;
+        let output_str = String::from_utf8(output.stdout).map_err(|e| e.to_string())?;
+        let (gpu_usage, gpu_temp, mut fan_speed) = parse_gpu_stats(&output_str);
+
+        // If ioreg reports 0 fan speed, try getting it from SMC
+        if fan_speed == 0 {
+            if let Ok(rpm) = SMC::new().and_then(|smc| smc.get_fan_speed(GPU_FAN_ID)) {
+                fan_speed = rpm.round() as i32;
+            }
+        }
+
+        GPU_STATS_CACHE.set((gpu_usage, gpu_temp, fan_speed));
+        Ok((gpu_usage, gpu_temp, fan_speed))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok((0, 0, 0))
+    }
+}
+
+// Per-core temperatures as (core, °C), falling back to the CPU proximity and die sensors
+pub fn core_temps() -> Result<Vec<(usize, i32)>, String> {
+    if let Some(reading) = simulated() {
+        return Ok(reading.core_temps.iter().map(|temp| temp.round() as i32).enumerate().collect());
+    }
+
+    let num_cores = cpu_cores();
+    let smc = SMC::new()?;
+    let mut temps = Vec::new();
+
+    // First try to get temperature for each core
+    for core in 0..num_cores {
+        let key = format!("TC{}C", core);
+        match smc.read_key(&key) {
+            Ok(temp) if temp > 0.0 && temp < 150.0 => {
+                println!("Core {} temperature: {:.1}°C", core, temp);
+                temps.push((core, temp.round() as i32));
+            },
+            _ => {
+                // If reading fails, try using TC0P (CPU Proximity) temperature
+                if temps.is_empty() {
+                    if let Ok(temp) = smc.read_key("TC0P") {
+                        if temp > 0.0 && temp < 150.0 {
+                            println!("Using CPU proximity temperature: {:.1}°C", temp);
+                            temps.push((core, temp.round() as i32));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // If no temperatures are obtained, try other sensors
+    if temps.is_empty() {
+        for key in &["TC0D", "TC0F", "TC0E"] {
+            if let Ok(temp) = smc.read_key(key) {
+                if temp > 0.0 && temp < 150.0 {
+                    println!("Using {} temperature: {:.1}°C", key, temp);
+                    // Apply the same temperature to all cores
+                    for core in 0..num_cores {
+                        temps.push((core, temp.round() as i32));
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(temps)
+}
+
+// One raw SMC key, rounded
+pub fn read_smc_key(key: &str) -> Result<i32, String> {
+    if is_simulated() {
+        return Err(format!("SMC key {} is not simulated", key));
+    }
+    let smc = SMC::new()?;
+    Ok(smc.read_key(key)?.round() as i32)
+}
+
+// Description of this machine for recording headers and reports
+pub fn machine_info() -> MachineInfo {
+    let mut sys = System::new();
+    sys.refresh_memory();
+    let cpu = cpu_info().ok();
+    let gpu = gpu_info().ok();
+
+    MachineInfo {
+        hostname: sys.host_name(),
+        os: sys.long_os_version(),
+        cpu_vendor: cpu.as_ref().map(|cpu| cpu.vendor.clone()),
+        cpu_model: cpu.as_ref().map(|cpu| cpu.model.clone()),
+        cpu_cores: cpu.as_ref().map(|cpu| cpu.cores),
+        cpu_threads: cpu.as_ref().map(|cpu| cpu.threads),
+        gpu_vendor: gpu.as_ref().map(|gpu| gpu.vendor.clone()),
+        gpu_model: gpu.as_ref().map(|gpu| gpu.model.clone()),
+        memory_bytes: Some(sys.total_memory()),
+    }
+}
+
+pub fn cpu_cores() -> usize {
+    if let Some(machine) = SIMULATION.as_ref() {
+        return machine.config().cores;
+    }
+    System::new_all().physical_core_count().unwrap_or(1)
+}
+
+pub fn cpu_threads() -> usize {
+    if let Some(machine) = SIMULATION.as_ref() {
+        return machine.config().logical_cpus();
+    }
+    System::new_all().cpus().len()
+}
+
+// Default thread count for stress tests: the simulated machine's logical CPUs when simulating
+pub fn logical_cpus() -> usize {
+    SIMULATION.as_ref().map_or_else(num_cpus::get, |machine| machine.config().logical_cpus())
+}
+
+// Add new structure to store GPU information
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuInfo {
+    pub vendor: String,
+    pub model: String,
+}
+
+pub fn gpu_info() -> Result<GpuInfo, String> {
+    if SIMULATION.is_some() {
+        return Ok(GpuInfo {
+            vendor: "Simulated".to_string(),
+            model: "GPU".to_string(),
+        });
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = Command::new("system_profiler")
+            .arg("SPDisplaysDataType")
+            .output()
+            .map_err(|e| e.to_string())?;
+
+        let output_str = String::from_utf8(output.stdout)
+            .map_err(|e| e.to_string())?;
+
+        let mut model = String::new();
+        let mut found_dgpu = false;
+
+        // Parse output, find GPU information
+        for line in output_str.lines() {
+            let line = line.trim();
+            
+            // Check if it's a GPU model
+            if line.contains("Chipset Model:") {
+                let current_model = line.replace("Chipset Model:", "").trim().to_string();
+                
+                // Improve discrete GPU detection logic
+                let is_dgpu = current_model.contains("GeForce") || 
+                             current_model.contains("NVIDIA") ||
+                             current_model.contains("Radeon") && !current_model.contains("Intel") && !current_model.contains("Integrated") ||
+                             (current_model.contains("AMD") && !current_model.contains("AMD Radeon Pro"));
+
+                // If discrete GPU is found, use it directly and exit loop
+                if is_dgpu {
+                    model = current_model;
+                    found_dgpu = true;
+                    break;
+                } 
+                // If no GPU has been found yet, save current GPU
+                else if model.is_empty() {
+                    model = current_model;
+                }
+            }
+        }
+
+        // If no GPU model is found
+        if model.is_empty() {
+            model = "Unknown GPU".to_string();
+        }
+
+        // Determine vendor and clean model
+        let vendor = if model.contains("AMD") || model.contains("Radeon") {
+            // ... AMD processing logic remains unchanged ...
+            "AMD".to_string()
+        } else if model.contains("NVIDIA") || model.contains("GeForce") {
+            // ... NVIDIA processing logic remains unchanged ...
+            "NVIDIA".to_string()
+        } else if model.contains("Intel") {
+            // ... Intel processing logic remains unchanged ...
+            "Intel".to_string()
+        } else if model.contains("Apple") {
+            // ... Apple processing logic remains unchanged ...
+            "Apple".to_string()
+        } else {
+            "Unknown".to_string()
+        };
+
+        // If it's an integrated GPU, add identifier to model
+        if !found_dgpu && vendor != "Unknown" {
+            model = format!("Integrated {}", model);
+        }
+
+        Ok(GpuInfo { vendor, model })
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok(GpuInfo {
+            vendor: "Unknown".to_string(),
+            model: "GPU".to_string(),
+        })
+    }
+}
+
+// Add new structure to store CPU information
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuInfo {
+    pub vendor: String,
+    pub model: String,
+    pub cores: usize,
+    pub threads: usize,
+}
+
+pub fn cpu_info() -> Result<CpuInfo, String> {
+    if let Some(machine) = SIMULATION.as_ref() {
+        return Ok(CpuInfo {
+            vendor: "Simulated".to_string(),
+            model: format!("{}-core CPU", machine.config().cores),
+            cores: machine.config().cores,
+            threads: machine.config().logical_cpus(),
+        });
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = Command::new("sysctl")
+            .arg("-n")
+            .arg("machdep.cpu.brand_string")
+            .output()
+            .map_err(|e| e.to_string())?;
+
+        let brand_string = String::from_utf8(output.stdout)
+            .map_err(|e| e.to_string())?
+            .trim()
+            .to_string();
+
+        let cores = cpu_cores();
+        let threads = cpu_threads();
+
+        // Extract vendor
+        let vendor = if brand_string.contains("Intel") {
+            "Intel".to_string()
+        } else if brand_string.contains("AMD") {
+            "AMD".to_string()
+        } else if brand_string.contains("Apple") {
+            "Apple".to_string()
+        } else {
+            "Unknown".to_string()
+        };
+
+        // Extract model, process different types of processors
+        let model = if brand_string.contains("Intel") {
+            // Process Intel processor
+            let parts: Vec<&str> = brand_string.split(' ').collect();
+            let mut model_parts = Vec::new();
+            let mut found_model = false;
+
+            for part in parts {
+                // Find processor model start position (i3/i5/i7/i9, Xeon, etc.)
+                if part.starts_with('i') || part == "Xeon" || part == "Celeron" || part == "Pentium" {
+                    found_model = true;
+                }
+                
+                // Collect model information until encountering @, CPU, etc. termination words
+                if found_model {
+                    if part.contains('@') || part == "CPU" {
+                        break;
+                    }
+                    model_parts.push(part);
+                }
+            }
+
+            if model_parts.is_empty() {
+                "Unknown".to_string()
+            } else {
+                model_parts.join(" ")
+            }
+        } else if brand_string.contains("AMD") {
+            // Process AMD processor
+            let parts: Vec<&str> = brand_string.split(' ').collect();
+            let mut model_parts = Vec::new();
+            let mut found_model = false;
+
+            for part in parts {
+                // Find processor model start position (Ryzen, EPYC, etc.)
+                if part == "Ryzen" || part == "EPYC" || part == "Athlon" {
+                    found_model = true;
+                }
+                
+                // Collect model information until encountering @, CPU, etc. termination words
+                if found_model {
+                    if part.contains('@') || part == "CPU" {
+                        break;
+                    }
+                    model_parts.push(part);
+                }
+            }
+
+            if model_parts.is_empty() {
+                "Unknown".to_string()
+            } else {
+                model_parts.join(" ")
+            }
+        } else if brand_string.contains("Apple") {
+            // Process Apple Silicon
+            if let Some(m_pos) = brand_string.find('M') {
+                let model_str = &brand_string[m_pos..];
+                if let Some(end) = model_str.find(' ') {
+                    model_str[..end].to_string()
+                } else {
+                    model_str.to_string()
+                }
+            } else {
+                "Unknown".to_string()
+            }
+        } else {
+            "Unknown".to_string()
+        };
+
+        Ok(CpuInfo {
+            vendor,
+            model,
+            cores,
+            threads,
+        })
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok(CpuInfo {
+            vendor: "Unknown".to_string(),
+            model: "CPU".to_string(),
+            cores: cpu_cores(),
+            threads: cpu_threads(),
+        })
+    }
+}
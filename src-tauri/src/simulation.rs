@@ -0,0 +1,360 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use crate::gpu_stress::GpuIntensity;
+use crate::sampling::{self, SensorSample};
+use crate::stress::{StressState, StressStatus};
+
+// Longest step taken at once, so a long gap between reads can't overshoot the model
+const MAX_STEP_SECS: f64 = 1.0;
+
+// Share of the GPU's heat that reaches the CPU through a shared heatsink
+const GPU_TO_CPU_COUPLING: f64 = 0.15;
+
+// A made-up machine. Every temperature follows dT/dt = (target - T) / τ, where the
+// target rises with load and falls as the fans spin up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    pub cores: usize,
+    pub threads_per_core: usize,
+    pub fans: usize,
+    pub ambient_celsius: f64,
+    pub cpu_idle_rise: f64,    // °C above ambient at idle
+    pub cpu_load_rise: f64,    // Extra °C per fully loaded core, with the fans at minimum
+    pub cpu_tau_secs: f64,
+    pub gpu_idle_rise: f64,
+    pub gpu_load_rise: f64,
+    pub gpu_tau_secs: f64,
+    pub fan_min_rpm: f64,
+    pub fan_max_rpm: f64,
+    pub fan_curve_start: f64,  // °C where the fans start to ramp up
+    pub fan_curve_end: f64,    // °C where they reach full speed
+    pub fan_tau_secs: f64,
+    pub fan_cooling: f64,      // Fraction of the load rise removed at full fan speed
+    pub throttle_celsius: f64, // Above this the CPU speed limit drops
+    pub idle_mhz: f64,
+    pub boost_mhz: f64,
+    pub noise_celsius: f64,    // Amplitude of the reading jitter
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            cores: 8,
+            threads_per_core: 2,
+            fans: 2,
+            ambient_celsius: 28.0,
+            cpu_idle_rise: 14.0,
+            cpu_load_rise: 70.0,
+            cpu_tau_secs: 25.0,
+            gpu_idle_rise: 10.0,
+            gpu_load_rise: 55.0,
+            gpu_tau_secs: 40.0,
+            fan_min_rpm: 1200.0,
+            fan_max_rpm: 5800.0,
+            fan_curve_start: 55.0,
+            fan_curve_end: 90.0,
+            fan_tau_secs: 4.0,
+            fan_cooling: 0.35,
+            throttle_celsius: 95.0,
+            idle_mhz: 1200.0,
+            boost_mhz: 3600.0,
+            noise_celsius: 0.3,
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cores == 0 || self.threads_per_core == 0 {
+            return Err("A simulated CPU needs at least one core and one thread per core".to_string());
+        }
+        if self.cpu_tau_secs <= 0.0 || self.gpu_tau_secs <= 0.0 || self.fan_tau_secs <= 0.0 {
+            return Err("Simulated time constants must be positive".to_string());
+        }
+        if self.fan_max_rpm < self.fan_min_rpm || self.fan_curve_end <= self.fan_curve_start {
+            return Err("The simulated fan curve must rise".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn logical_cpus(&self) -> usize {
+        self.cores * self.threads_per_core
+    }
+}
+
+// What the app's own stress workers are asking of the simulated machine
+#[derive(Debug, Clone, Default)]
+pub struct SimLoad {
+    pub cpu_threads: usize,
+    pub cpu_duty: f64,  // 0–1
+    pub gpu: f64,       // 0–1
+    pub memory: bool,
+}
+
+impl SimLoad {
+    pub fn from_stress(cpu: &StressStatus, gpu: &StressStatus, memory: &StressStatus) -> Self {
+        let running = |status: &StressStatus| status.state == StressState::Running;
+        let gpu_share = |intensity: GpuIntensity| match intensity {
+            GpuIntensity::Low => 0.4,
+            GpuIntensity::Medium => 0.7,
+            GpuIntensity::High | GpuIntensity::Extreme => 1.0,
+        };
+
+        SimLoad {
+            cpu_threads: if running(cpu) { cpu.threads } else { 0 },
+            cpu_duty: cpu.load_percent.unwrap_or(100) as f64 / 100.0,
+            gpu: if running(gpu) { gpu.gpu_intensity.map_or(1.0, gpu_share) } else { 0.0 },
+            memory: running(memory),
+        }
+    }
+}
+
+// One reading of the whole simulated machine
+#[derive(Debug, Clone, Serialize)]
+pub struct SimReading {
+    pub timestamp: u64,
+    pub core_temps: Vec<f64>,
+    pub cpu_temp: f64,
+    pub cpu_usage: Vec<f64>,  // Per logical CPU, %
+    pub cpu_freq_mhz: f64,
+    pub cpu_speed_limit: f64,
+    pub gpu_temp: f64,
+    pub gpu_usage: f64,
+    pub fans: Vec<(usize, f64)>,
+}
+
+impl SimReading {
+    pub fn sample(&self) -> SensorSample {
+        SensorSample {
+            timestamp: self.timestamp,
+            cpu_temp: Some(self.cpu_temp),
+            gpu_temp: Some(self.gpu_temp),
+            gpu_usage: Some(self.gpu_usage),
+            fans: self.fans.clone(),
+            cpu_freq_mhz: Some(self.cpu_freq_mhz),
+            cpu_speed_limit: Some(self.cpu_speed_limit),
+            other: BTreeMap::new(),
+        }
+    }
+}
+
+struct SimState {
+    last: Instant,
+    core_temps: Vec<f64>,
+    gpu_temp: f64,
+    fan_rpm: Vec<f64>,
+    speed_limit: f64,
+    rng: u64,
+}
+
+// Advances with wall-clock time on every read, so any number of readers see one machine
+pub struct SimulatedMachine {
+    config: SimulationConfig,
+    state: Mutex<SimState>,
+}
+
+impl SimulatedMachine {
+    pub fn new(config: SimulationConfig) -> Result<Self, String> {
+        config.validate()?;
+        let idle_cpu = config.ambient_celsius + config.cpu_idle_rise;
+        let state = SimState {
+            last: Instant::now(),
+            core_temps: vec![idle_cpu; config.cores],
+            gpu_temp: config.ambient_celsius + config.gpu_idle_rise,
+            fan_rpm: vec![config.fan_min_rpm; config.fans],
+            speed_limit: 100.0,
+            rng: 0x2545_f491_4f6c_dd1d,
+        };
+        Ok(SimulatedMachine { config, state: Mutex::new(state) })
+    }
+
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    pub fn read(&self, load: &SimLoad) -> SimReading {
+        let mut state = self.state.lock();
+        let now = Instant::now();
+        let mut remaining = now.duration_since(state.last).as_secs_f64();
+        state.last = now;
+
+        let usage = self.logical_usage(load, state.speed_limit);
+        while remaining > 0.0 {
+            let dt = remaining.min(MAX_STEP_SECS);
+            self.step(&mut state, load, &usage, dt);
+            remaining -= dt;
+        }
+
+        let config = &self.config;
+        let mut rng = state.rng;
+        let mut jitter = || (next_random(&mut rng) - 0.5) * 2.0 * config.noise_celsius;
+        let core_temps: Vec<f64> = state.core_temps.iter().map(|t| t + jitter()).collect();
+        let gpu_temp = state.gpu_temp + jitter();
+        state.rng = rng;
+
+        let busy = usage.iter().sum::<f64>() / usage.len() as f64 / 100.0;
+        let freq = (config.idle_mhz + (config.boost_mhz - config.idle_mhz) * busy.sqrt()) * state.speed_limit / 100.0;
+
+        SimReading {
+            timestamp: sampling::now_millis(),
+            cpu_temp: core_temps.iter().sum::<f64>() / core_temps.len() as f64,
+            core_temps,
+            cpu_usage: usage,
+            cpu_freq_mhz: freq.round(),
+            cpu_speed_limit: state.speed_limit.round(),
+            gpu_temp,
+            gpu_usage: (load.gpu * 100.0).round(),
+            fans: state.fan_rpm.iter().map(|rpm| rpm.round()).enumerate().collect(),
+        }
+    }
+
+    // Stress threads fill one thread per core first, then the SMT siblings.
+    // Throttling stretches each busy slice, so the same work shows as higher usage.
+    fn logical_usage(&self, load: &SimLoad, speed_limit: f64) -> Vec<f64> {
+        let logical = self.config.logical_cpus();
+        let mut usage = vec![2.0; logical];
+        let duty = (load.cpu_duty / (speed_limit / 100.0)).min(1.0);
+        for thread in 0..load.cpu_threads.min(logical) {
+            let cpu = (thread % self.config.cores) * self.config.threads_per_core + thread / self.config.cores;
+            usage[cpu] = (2.0 + duty * 98.0).min(100.0).round();
+        }
+        if load.memory {
+            for value in usage.iter_mut() {
+                *value = (*value + 10.0).min(100.0);
+            }
+        }
+        usage
+    }
+
+    fn step(&self, state: &mut SimState, load: &SimLoad, usage: &[f64], dt: f64) {
+        let config = &self.config;
+        let decay = |tau: f64| 1.0 - (-dt / tau).exp();
+
+        let fan_level = match state.fan_rpm.first() {
+            Some(rpm) => ((rpm - config.fan_min_rpm) / (config.fan_max_rpm - config.fan_min_rpm).max(1.0)).clamp(0.0, 1.0),
+            None => 0.0,
+        };
+        let cooling = 1.0 - config.fan_cooling * fan_level;
+
+        // Heat follows the work actually done, which throttling caps
+        let throttle = state.speed_limit / 100.0;
+        let gpu_heat = load.gpu * config.gpu_load_rise * cooling;
+        // A sibling thread adds a fraction of a core's heat
+        let core_busy: Vec<f64> = usage
+            .chunks(config.threads_per_core)
+            .map(|threads| threads.iter().enumerate().map(|(i, u)| u / 100.0 * if i == 0 { 1.0 } else { 0.25 }).sum())
+            .collect();
+        let package_busy = core_busy.iter().sum::<f64>() / core_busy.len() as f64;
+        for (temp, busy) in state.core_temps.iter_mut().zip(&core_busy) {
+            // Part of each core's heat spreads across the package
+            let busy = 0.7 * busy + 0.3 * package_busy;
+            let target = config.ambient_celsius
+                + config.cpu_idle_rise
+                + busy.min(1.25) * throttle * config.cpu_load_rise * cooling
+                + if load.memory { 3.0 } else { 0.0 }
+                + gpu_heat * GPU_TO_CPU_COUPLING;
+            *temp += (target - *temp) * decay(config.cpu_tau_secs);
+        }
+        let gpu_target = config.ambient_celsius + config.gpu_idle_rise + gpu_heat;
+        state.gpu_temp += (gpu_target - state.gpu_temp) * decay(config.gpu_tau_secs);
+
+        // Fan curve on the hotter of CPU and GPU
+        let cpu_temp = state.core_temps.iter().sum::<f64>() / state.core_temps.len() as f64;
+        let hottest = cpu_temp.max(state.gpu_temp);
+        let level = ((hottest - config.fan_curve_start) / (config.fan_curve_end - config.fan_curve_start)).clamp(0.0, 1.0);
+        let fan_target = config.fan_min_rpm + (config.fan_max_rpm - config.fan_min_rpm) * level;
+        for rpm in state.fan_rpm.iter_mut() {
+            *rpm += (fan_target - *rpm) * decay(config.fan_tau_secs);
+        }
+
+        // Throttle 10% per °C over the limit; recover at 20%/s once below it
+        let limit = if cpu_temp > config.throttle_celsius {
+            (100.0 - (cpu_temp - config.throttle_celsius) * 10.0).max(40.0)
+        } else {
+            100.0
+        };
+        state.speed_limit = if limit < state.speed_limit { limit } else { (state.speed_limit + 20.0 * dt).min(limit) };
+    }
+}
+
+// xorshift64*, mapped to [0, 1)
+fn next_random(state: &mut u64) -> f64 {
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+    (state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Environment variable that turns the simulation on: "true" for the default machine or a core count
+pub const SIMULATE_ENV: &str = "TEMPDETECT_SIMULATE";
+
+// Simulation settings from `--simulate[=CORES]` or TEMPDETECT_SIMULATE; None for real sensors.
+// A number is always a core count, so `--simulate=1` is a single-core machine; use
+// `--simulate` or `true` for the default machine.
+pub fn requested(args: &[String]) -> Result<Option<SimulationConfig>, String> {
+    let flag = args.iter().find_map(|arg| match arg.as_str() {
+        "--simulate" => Some(String::new()),
+        _ => arg.strip_prefix("--simulate=").map(str::to_string),
+    });
+    let value = match flag {
+        Some(value) => value,
+        None => match std::env::var(SIMULATE_ENV) {
+            Ok(value) if !matches!(value.trim(), "" | "0" | "false") => value,
+            _ => return Ok(None),
+        },
+    };
+
+    let mut config = SimulationConfig::default();
+    match value.trim() {
+        "" | "true" => {}
+        cores => {
+            config.cores = cores
+                .parse()
+                .map_err(|_| format!("Expected a simulated core count, got \"{}\"", cores))?;
+        }
+    }
+    config.validate()?;
+    Ok(Some(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stress::StressSlot;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn requested_core_count() {
+        let default_cores = SimulationConfig::default().cores;
+        assert_eq!(requested(&args(&["app", "--simulate"])).unwrap().unwrap().cores, default_cores);
+        assert_eq!(requested(&args(&["app", "--simulate=true"])).unwrap().unwrap().cores, default_cores);
+        // A number is a core count, even 1
+        assert_eq!(requested(&args(&["app", "--simulate=1"])).unwrap().unwrap().cores, 1);
+        assert_eq!(requested(&args(&["app", "--simulate=4"])).unwrap().unwrap().cores, 4);
+        assert!(requested(&args(&["app", "--simulate=0"])).is_err());
+        assert!(requested(&args(&["app", "--simulate=many"])).is_err());
+    }
+
+    #[test]
+    fn gpu_load_follows_the_intensity() {
+        let idle = StressSlot::new("Test").status();
+        let mut gpu = idle.clone();
+        gpu.state = StressState::Running;
+        gpu.workload = Some("noop (high) on No-op GPU".to_string());
+
+        gpu.gpu_intensity = Some(GpuIntensity::Low);
+        assert_eq!(SimLoad::from_stress(&idle, &gpu, &idle).gpu, 0.4);
+        gpu.gpu_intensity = Some(GpuIntensity::Extreme);
+        assert_eq!(SimLoad::from_stress(&idle, &gpu, &idle).gpu, 1.0);
+
+        gpu.state = StressState::Completed;
+        assert_eq!(SimLoad::from_stress(&idle, &gpu, &idle).gpu, 0.0);
+    }
+}
@@ -0,0 +1,276 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::sampling::SensorSample;
+
+// When a series counts as settled. Every threshold that is set must hold over a full window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SteadyStateConfig {
+    pub window_secs: f64,
+    pub max_slope_per_min: Option<f64>,  // Least-squares slope over the window, in sensor units per minute
+    pub max_std_dev: Option<f64>,        // Standard deviation over the window
+}
+
+impl SteadyStateConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.window_secs.is_finite() && self.window_secs > 0.0) {
+            return Err(format!("Steady-state window must be a positive number of seconds, got {}", self.window_secs));
+        }
+        let thresholds = [self.max_slope_per_min, self.max_std_dev];
+        if thresholds.into_iter().flatten().any(|threshold| threshold.is_nan() || threshold < 0.0) {
+            return Err("Steady-state thresholds must not be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for SteadyStateConfig {
+    fn default() -> Self {
+        SteadyStateConfig {
+            window_secs: 60.0,
+            max_slope_per_min: Some(0.5),
+            max_std_dev: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SteadyState {
+    pub time_to_steady_secs: f64,           // From the first sample to the start of the first steady window
+    pub detected_after_secs: f64,           // From the first sample to the end of that window
+    pub mean: f64,                          // Mean from the steady onset to the latest sample
+    pub time_constant_secs: Option<f64>,    // Fitted first-order time constant of the approach
+}
+
+// Feeds on one series sample by sample, so it works live and on recorded data alike
+pub struct SteadyStateDetector {
+    config: SteadyStateConfig,
+    start: Option<f64>,
+    window: VecDeque<(f64, f64)>,
+    approach: Vec<(f64, f64)>,  // Samples before the onset, for the time-constant fit
+    onset: Option<(f64, f64)>,  // (onset, detection) times relative to the first sample
+    sum: f64,
+    count: usize,
+}
+
+impl SteadyStateDetector {
+    pub fn new(config: SteadyStateConfig) -> Self {
+        SteadyStateDetector {
+            config,
+            start: None,
+            window: VecDeque::new(),
+            approach: Vec::new(),
+            onset: None,
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    // Add a sample at `t` seconds; returns the steady state once it has been reached
+    pub fn push(&mut self, t: f64, value: f64) -> Option<SteadyState> {
+        let t = t - *self.start.get_or_insert(t);
+
+        if self.onset.is_some() {
+            self.sum += value;
+            self.count += 1;
+            return self.result();
+        }
+
+        self.window.push_back((t, value));
+        // Keep one sample at or beyond the window edge so the window stays covered
+        while self.window.get(1).is_some_and(|&(front, _)| t - front >= self.config.window_secs) {
+            self.approach.extend(self.window.pop_front());
+        }
+
+        let onset = self.window.front().map_or(t, |&(front, _)| front);
+        if t - onset >= self.config.window_secs && self.window_is_steady() {
+            self.onset = Some((onset, t));
+            self.sum = self.window.iter().map(|&(_, v)| v).sum();
+            self.count = self.window.len();
+        }
+
+        self.result()
+    }
+
+    pub fn result(&self) -> Option<SteadyState> {
+        let (onset, detected) = self.onset?;
+        let mean = self.sum / self.count as f64;
+
+        Some(SteadyState {
+            time_to_steady_secs: onset,
+            detected_after_secs: detected,
+            mean,
+            time_constant_secs: fit_time_constant(&self.approach, mean),
+        })
+    }
+
+    fn window_is_steady(&self) -> bool {
+        let n = self.window.len() as f64;
+        if n < 2.0 {
+            return false;
+        }
+
+        let mean_t = self.window.iter().map(|&(t, _)| t).sum::<f64>() / n;
+        let mean_v = self.window.iter().map(|&(_, v)| v).sum::<f64>() / n;
+
+        if let Some(max_slope) = self.config.max_slope_per_min {
+            let (cov, var_t) = self.window.iter().fold((0.0, 0.0), |(cov, var_t), &(t, v)| {
+                (cov + (t - mean_t) * (v - mean_v), var_t + (t - mean_t) * (t - mean_t))
+            });
+            if var_t == 0.0 || (cov / var_t * 60.0).abs() > max_slope {
+                return false;
+            }
+        }
+
+        if let Some(max_std_dev) = self.config.max_std_dev {
+            let variance = self.window.iter().map(|&(_, v)| (v - mean_v) * (v - mean_v)).sum::<f64>() / n;
+            if variance.sqrt() > max_std_dev {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+// Fit T(t) = T_ss - (T_ss - T_0)·e^(-t/τ) by regressing ln|T_ss - T| on t.
+// Works for heating and cooling alike; the curve only has to approach `steady_mean` from one side.
+pub fn fit_time_constant(approach: &[(f64, f64)], steady_mean: f64) -> Option<f64> {
+    let &(_, first) = approach.first()?;
+    let amplitude = steady_mean - first;
+
+    // Skip samples already within noise of the steady value; the log blows up there
+    let floor = (amplitude.abs() * 0.05).max(0.1);
+    let points: Vec<(f64, f64)> = approach
+        .iter()
+        .map(|&(t, v)| (t, steady_mean - v))
+        .filter(|&(_, gap)| gap.signum() == amplitude.signum() && gap.abs() > floor)
+        .map(|(t, gap)| (t, gap.abs().ln()))
+        .collect();
+    if points.len() < 3 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|&(t, _)| t).sum::<f64>() / n;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / n;
+    let (cov, var_t) = points.iter().fold((0.0, 0.0), |(cov, var_t), &(t, y)| {
+        (cov + (t - mean_t) * (y - mean_y), var_t + (t - mean_t) * (t - mean_t))
+    });
+    let slope = cov / var_t;
+
+    if slope < 0.0 {
+        Some(-1.0 / slope)
+    } else {
+        None
+    }
+}
+
+// Run the detector over a whole recorded series of (seconds, value) points
+pub fn analyze(series: &[(f64, f64)], config: &SteadyStateConfig) -> Option<SteadyState> {
+    let mut detector = SteadyStateDetector::new(config.clone());
+    for &(t, value) in series {
+        detector.push(t, value);
+    }
+    detector.result()
+}
+
+// One sensor channel of recorded samples as (seconds since the first sample, value) points
+pub fn series(samples: &[SensorSample], sensor: &str) -> Vec<(f64, f64)> {
+    let Some(first) = samples.first() else {
+        return Vec::new();
+    };
+    samples
+        .iter()
+        .filter_map(|sample| {
+            let t = sample.timestamp.saturating_sub(first.timestamp) as f64 / 1000.0;
+            sample.value(sensor).map(|value| (t, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // First-order approach from `start` to `end` with time constant `tau`, one point per second
+    fn approach(start: f64, end: f64, tau: f64, secs: usize) -> Vec<(f64, f64)> {
+        (0..secs)
+            .map(|t| (t as f64, end - (end - start) * (-(t as f64) / tau).exp()))
+            .collect()
+    }
+
+    #[test]
+    fn config_validation() {
+        assert!(SteadyStateConfig::default().validate().is_ok());
+        for window_secs in [0.0, -60.0, f64::NAN, f64::INFINITY] {
+            assert!(SteadyStateConfig { window_secs, ..Default::default() }.validate().is_err(), "{}", window_secs);
+        }
+        assert!(SteadyStateConfig { max_std_dev: Some(-1.0), ..Default::default() }.validate().is_err());
+        assert!(SteadyStateConfig { max_slope_per_min: Some(f64::NAN), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn detects_steady_state_after_heating() {
+        let series = approach(40.0, 80.0, 30.0, 600);
+        let steady = analyze(&series, &SteadyStateConfig::default()).unwrap();
+
+        // The window slope drops below 0.5 °C/min a few time constants in
+        assert!(steady.time_to_steady_secs > 60.0 && steady.time_to_steady_secs < 200.0, "{:?}", steady);
+        assert_eq!(steady.detected_after_secs - steady.time_to_steady_secs, 60.0);
+        assert!((steady.mean - 80.0).abs() < 0.5, "{:?}", steady);
+        let tau = steady.time_constant_secs.unwrap();
+        assert!((tau - 30.0).abs() < 3.0, "tau {}", tau);
+    }
+
+    #[test]
+    fn ramp_never_settles() {
+        let series: Vec<(f64, f64)> = (0..600).map(|t| (t as f64, 40.0 + t as f64 / 60.0)).collect();
+        assert!(analyze(&series, &SteadyStateConfig::default()).is_none());
+    }
+
+    #[test]
+    fn std_dev_threshold_rejects_noise() {
+        let noisy: Vec<(f64, f64)> = (0..300).map(|t| (t as f64, if t % 2 == 0 { 60.0 } else { 64.0 })).collect();
+        let config = SteadyStateConfig { max_slope_per_min: None, max_std_dev: Some(1.0), ..Default::default() };
+        assert!(analyze(&noisy, &config).is_none());
+
+        let config = SteadyStateConfig { max_std_dev: Some(2.5), ..config };
+        let steady = analyze(&noisy, &config).unwrap();
+        assert_eq!(steady.time_to_steady_secs, 0.0);
+        assert!((steady.mean - 62.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn too_short_for_a_window() {
+        let series = approach(50.0, 50.0, 10.0, 30);
+        assert!(analyze(&series, &SteadyStateConfig::default()).is_none());
+        assert!(analyze(&[], &SteadyStateConfig::default()).is_none());
+    }
+
+    #[test]
+    fn fits_cooling_time_constant() {
+        let series = approach(90.0, 40.0, 20.0, 60);
+        let tau = fit_time_constant(&series, 40.0).unwrap();
+        assert!((tau - 20.0).abs() < 0.5, "tau {}", tau);
+    }
+
+    #[test]
+    fn no_time_constant_without_an_approach() {
+        assert!(fit_time_constant(&[], 50.0).is_none());
+        assert!(fit_time_constant(&[(0.0, 50.0), (1.0, 50.0), (2.0, 50.0)], 50.0).is_none());
+        // Moving away from the steady value isn't an exponential approach
+        let diverging: Vec<(f64, f64)> = (0..10).map(|t| (t as f64, 50.0 - t as f64)).collect();
+        assert!(fit_time_constant(&diverging, 60.0).is_none());
+    }
+
+    #[test]
+    fn series_uses_seconds_since_first_sample() {
+        let samples: Vec<SensorSample> = [(1_000, Some(50.0)), (2_500, None), (4_000, Some(52.0))]
+            .into_iter()
+            .map(|(timestamp, cpu_temp)| SensorSample { timestamp, cpu_temp, ..Default::default() })
+            .collect();
+        assert_eq!(series(&samples, "cpu_temp"), vec![(0.0, 50.0), (3.0, 52.0)]);
+    }
+}
@@ -0,0 +1,680 @@
+use parking_lot::Mutex;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::recording::MachineInfo;
+use crate::sampling::{self, Snapshot};
+
+// How often raw samples are rolled up and expired while recording
+const MAINTENANCE_INTERVAL_MS: u64 = 10 * 60 * 1000;
+
+const DAY_MS: u64 = 86_400_000;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER,
+    machine TEXT
+);
+CREATE TABLE IF NOT EXISTS sensors (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,
+    unit TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS samples (
+    session_id INTEGER NOT NULL,
+    sensor_id INTEGER NOT NULL,
+    ts INTEGER NOT NULL,
+    value REAL NOT NULL,
+    rolled INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS samples_by_sensor ON samples (sensor_id, ts);
+CREATE INDEX IF NOT EXISTS samples_by_ts ON samples (ts);
+CREATE INDEX IF NOT EXISTS samples_pending ON samples (ts) WHERE rolled = 0;
+CREATE TABLE IF NOT EXISTS rollups (
+    session_id INTEGER NOT NULL,
+    sensor_id INTEGER NOT NULL,
+    bucket INTEGER NOT NULL,
+    avg REAL NOT NULL,
+    min REAL NOT NULL,
+    max REAL NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (session_id, sensor_id, bucket)
+);
+CREATE INDEX IF NOT EXISTS rollups_by_sensor ON rollups (sensor_id, bucket);
+";
+
+// How long data is kept. Raw samples are always rolled up before they expire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub raw_days: Option<u32>,     // None keeps raw samples forever
+    pub rollup_secs: u64,          // Width of the averaged buckets
+    pub rollup_days: Option<u32>,  // None keeps the averages forever
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            raw_days: Some(7),
+            rollup_secs: 60,
+            rollup_days: None,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rollup_secs == 0 {
+            return Err("Rollup buckets must be at least one second wide".to_string());
+        }
+        if let (Some(raw), Some(rollup)) = (self.raw_days, self.rollup_days) {
+            if rollup < raw {
+                return Err("Averages must be kept at least as long as raw samples".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: i64,
+    pub name: String,
+    pub kind: String,  // "monitor" for plain background sampling, "plan", or whatever the caller chose
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub machine: Option<MachineInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SensorInfo {
+    pub name: String,
+    pub kind: String,
+    pub unit: String,
+}
+
+// One sensor's aggregate over one session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionTrend {
+    pub session: SessionInfo,
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+    pub count: u64,
+}
+
+// One sensor's aggregate over one time bucket, across sessions
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendPoint {
+    pub bucket: u64,  // Unix timestamp in milliseconds of the bucket start
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreStatus {
+    pub open: bool,
+    pub path: String,
+    pub retention: RetentionPolicy,
+    pub session: Option<SessionInfo>,
+    pub samples_written: u64,
+    pub error: Option<String>,  // Last write failure; recording carries on with the next snapshot
+}
+
+struct OpenStore {
+    conn: Connection,
+    sensor_ids: HashMap<String, i64>,
+    machine: MachineInfo,
+    session: SessionInfo,
+    last_maintenance: u64,
+    status: StoreStatus,
+}
+
+// Optional on-disk history: every sampler snapshot, grouped into sessions
+#[derive(Default)]
+pub struct ThermalStore {
+    open: Mutex<Option<OpenStore>>,
+}
+
+impl ThermalStore {
+    // Open (or create) the database and start a monitoring session
+    pub fn open(&self, path: &Path, retention: RetentionPolicy, machine: MachineInfo) -> Result<StoreStatus, String> {
+        retention.validate()?;
+        self.close();
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let conn = Connection::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")
+            .map_err(|e| format!("Failed to initialise {}: {}", path.display(), e))?;
+        let store = OpenStore::new(conn, path.display().to_string(), retention, machine)?;
+
+        let status = store.status.clone();
+        *self.open.lock() = Some(store);
+        Ok(status)
+    }
+
+    pub fn close(&self) -> Option<StoreStatus> {
+        let mut store = self.open.lock().take()?;
+        let now = sampling::now_millis();
+        if let Err(e) = store.end_session(now).and_then(|_| store.maintain(now)) {
+            store.status.error = Some(e);
+        }
+        store.status.open = false;
+        store.status.session = None;
+        Some(store.status)
+    }
+
+    pub fn status(&self) -> Option<StoreStatus> {
+        self.open.lock().as_ref().map(|store| store.status.clone())
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.lock().is_some()
+    }
+
+    // End the current session and start a named one, e.g. for a stress test or plan run
+    pub fn begin_session(&self, name: &str, kind: &str) -> Result<SessionInfo, String> {
+        let mut guard = self.open.lock();
+        let store = guard.as_mut().ok_or("The history database is not open")?;
+        store.end_session(sampling::now_millis())?;
+        let session = insert_session(&store.conn, name, kind, &store.machine)?;
+        store.session = session.clone();
+        store.status.session = Some(session.clone());
+        Ok(session)
+    }
+
+    // End the current session and go back to plain monitoring
+    pub fn end_session(&self) -> Result<Option<SessionInfo>, String> {
+        let mut guard = self.open.lock();
+        let Some(store) = guard.as_mut() else {
+            return Ok(None);
+        };
+        let now = sampling::now_millis();
+        store.end_session(now)?;
+        let mut ended = store.session.clone();
+        ended.ended_at = Some(now);
+
+        let session = insert_session(&store.conn, "Monitoring", "monitor", &store.machine)?;
+        store.session = session.clone();
+        store.status.session = Some(session);
+        Ok(Some(ended))
+    }
+
+    // Sampler sink. Failures are kept in the status rather than closing the store.
+    pub fn record(&self, snapshot: &Snapshot) {
+        let mut guard = self.open.lock();
+        let Some(store) = guard.as_mut() else {
+            return;
+        };
+
+        let result = store.insert(snapshot).and_then(|_| {
+            if snapshot.timestamp >= store.last_maintenance + MAINTENANCE_INTERVAL_MS {
+                store.maintain(snapshot.timestamp)?;
+            }
+            Ok(())
+        });
+        match result {
+            Ok(()) => store.status.samples_written += 1,
+            Err(e) => store.status.error = Some(e),
+        }
+    }
+
+    // Roll up and expire now rather than waiting for the next scheduled pass
+    pub fn compact(&self) -> Result<(), String> {
+        let mut guard = self.open.lock();
+        let store = guard.as_mut().ok_or("The history database is not open")?;
+        store.maintain(sampling::now_millis())?;
+        store
+            .conn
+            .execute_batch("VACUUM")
+            .map_err(|e| format!("Failed to vacuum the history database: {}", e))
+    }
+
+    // Newest first, optionally only one kind and only sessions started since `since`
+    pub fn sessions(&self, kind: Option<&str>, since: Option<u64>, limit: Option<usize>) -> Result<Vec<SessionInfo>, String> {
+        let guard = self.open.lock();
+        let store = guard.as_ref().ok_or("The history database is not open")?;
+        let mut stmt = store
+            .conn
+            .prepare(
+                "SELECT id, name, kind, started_at, ended_at, machine FROM sessions
+                 WHERE (?1 IS NULL OR kind = ?1) AND started_at >= ?2
+                 ORDER BY started_at DESC LIMIT ?3",
+            )
+            .map_err(|e| e.to_string())?;
+        let limit = limit.map_or(-1, |limit| limit as i64);
+        let rows = stmt
+            .query_map(params![kind, since.unwrap_or(0) as i64, limit], session_from_row)
+            .map_err(|e| format!("Failed to list sessions: {}", e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    pub fn sensors(&self) -> Result<Vec<SensorInfo>, String> {
+        let guard = self.open.lock();
+        let store = guard.as_ref().ok_or("The history database is not open")?;
+        let mut stmt = store
+            .conn
+            .prepare("SELECT name, kind, unit FROM sensors ORDER BY name")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(SensorInfo {
+                    name: row.get(0)?,
+                    kind: row.get(1)?,
+                    unit: row.get(2)?,
+                })
+            })
+            .map_err(|e| format!("Failed to list sensors: {}", e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    // One sensor summarised per session, oldest first: how a test has drifted across runs
+    pub fn session_trend(&self, sensor: &str, kind: Option<&str>, since: Option<u64>) -> Result<Vec<SessionTrend>, String> {
+        let guard = self.open.lock();
+        let store = guard.as_ref().ok_or("The history database is not open")?;
+        let Some(&sensor_id) = store.sensor_ids.get(sensor) else {
+            return Ok(Vec::new());
+        };
+
+        let sql = format!(
+            "SELECT s.id, s.name, s.kind, s.started_at, s.ended_at, s.machine,
+                    SUM(a.total) / SUM(a.n), MIN(a.lo), MAX(a.hi), SUM(a.n)
+             FROM sessions s JOIN ({}) a ON a.session_id = s.id
+             WHERE (?2 IS NULL OR s.kind = ?2) AND s.started_at >= ?3
+             GROUP BY s.id ORDER BY s.started_at",
+            COMBINED
+        );
+        let mut stmt = store.conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![sensor_id, kind, since.unwrap_or(0) as i64],
+                |row| {
+                    Ok(SessionTrend {
+                        session: session_from_row(row)?,
+                        avg: row.get(6)?,
+                        min: row.get(7)?,
+                        max: row.get(8)?,
+                        count: row.get::<_, i64>(9)? as u64,
+                    })
+                },
+            )
+            .map_err(|e| format!("Failed to query the {} trend: {}", sensor, e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    // One sensor averaged into fixed time buckets (e.g. a day) across all sessions
+    pub fn time_trend(&self, sensor: &str, since: Option<u64>, until: Option<u64>, bucket_secs: u64) -> Result<Vec<TrendPoint>, String> {
+        if bucket_secs == 0 {
+            return Err("Trend buckets must be at least one second wide".to_string());
+        }
+        let guard = self.open.lock();
+        let store = guard.as_ref().ok_or("The history database is not open")?;
+        let Some(&sensor_id) = store.sensor_ids.get(sensor) else {
+            return Ok(Vec::new());
+        };
+
+        let sql = format!(
+            "SELECT (a.ts / ?2) * ?2 AS bucket, SUM(a.total) / SUM(a.n), MIN(a.lo), MAX(a.hi), SUM(a.n)
+             FROM ({}) a WHERE a.ts >= ?3 AND a.ts < ?4
+             GROUP BY bucket ORDER BY bucket",
+            COMBINED
+        );
+        let mut stmt = store.conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                params![
+                    sensor_id,
+                    (bucket_secs * 1000) as i64,
+                    since.unwrap_or(0) as i64,
+                    until.map_or(i64::MAX, |until| until as i64),
+                ],
+                |row| {
+                    Ok(TrendPoint {
+                        bucket: row.get::<_, i64>(0)? as u64,
+                        avg: row.get(1)?,
+                        min: row.get(2)?,
+                        max: row.get(3)?,
+                        count: row.get::<_, i64>(4)? as u64,
+                    })
+                },
+            )
+            .map_err(|e| format!("Failed to query the {} trend: {}", sensor, e))?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+}
+
+// Rolled-up buckets plus the raw samples not rolled up yet, for sensor ?1
+const COMBINED: &str = "
+    SELECT session_id, bucket AS ts, avg * count AS total, min AS lo, max AS hi, count AS n
+    FROM rollups WHERE sensor_id = ?1
+    UNION ALL
+    SELECT session_id, ts, value, value, value, 1
+    FROM samples WHERE sensor_id = ?1 AND rolled = 0";
+
+impl OpenStore {
+    // Set up the schema on an open connection and start a monitoring session
+    fn new(conn: Connection, path: String, retention: RetentionPolicy, machine: MachineInfo) -> Result<OpenStore, String> {
+        conn.execute_batch(SCHEMA)
+            .map_err(|e| format!("Failed to initialise {}: {}", path, e))?;
+
+        let mut sensor_ids = HashMap::new();
+        {
+            let mut stmt = conn.prepare("SELECT name, id FROM sensors").map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+                .map_err(|e| e.to_string())?;
+            for row in rows {
+                let (name, id) = row.map_err(|e| e.to_string())?;
+                sensor_ids.insert(name, id);
+            }
+        }
+
+        // Sessions left open by a crash end at their last sample
+        conn.execute(
+            "UPDATE sessions SET ended_at = COALESCE(
+                (SELECT MAX(ts) FROM samples WHERE session_id = sessions.id),
+                (SELECT MAX(bucket) FROM rollups WHERE session_id = sessions.id),
+                started_at)
+             WHERE ended_at IS NULL",
+            [],
+        )
+        .map_err(|e| format!("Failed to close stale sessions: {}", e))?;
+
+        let session = insert_session(&conn, "Monitoring", "monitor", &machine)?;
+        let mut store = OpenStore {
+            conn,
+            sensor_ids,
+            machine,
+            session: session.clone(),
+            last_maintenance: 0,
+            status: StoreStatus {
+                open: true,
+                path,
+                retention,
+                session: Some(session),
+                samples_written: 0,
+                error: None,
+            },
+        };
+        store.maintain(sampling::now_millis())?;
+        Ok(store)
+    }
+
+    fn insert(&mut self, snapshot: &Snapshot) -> Result<(), String> {
+        // New sensor ids only go into the cache once the transaction has committed, so a failed
+        // write can't leave ids behind that were rolled back
+        let mut added = Vec::new();
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+        {
+            let mut insert = tx
+                .prepare_cached("INSERT INTO samples (session_id, sensor_id, ts, value) VALUES (?1, ?2, ?3, ?4)")
+                .map_err(|e| e.to_string())?;
+            for (sensor, &value) in &snapshot.values {
+                let sensor_id = match self.sensor_ids.get(sensor) {
+                    Some(&id) => id,
+                    None => {
+                        let (kind, unit) = sampling::channel_kind(sensor);
+                        tx.execute(
+                            "INSERT INTO sensors (name, kind, unit) VALUES (?1, ?2, ?3)",
+                            params![sensor, kind, unit],
+                        )
+                        .map_err(|e| format!("Failed to add sensor {}: {}", sensor, e))?;
+                        let id = tx.last_insert_rowid();
+                        added.push((sensor.clone(), id));
+                        id
+                    }
+                };
+                insert
+                    .execute(params![self.session.id, sensor_id, snapshot.timestamp as i64, value])
+                    .map_err(|e| format!("Failed to store a sample: {}", e))?;
+            }
+        }
+        tx.commit().map_err(|e| format!("Failed to store a sample: {}", e))?;
+        self.sensor_ids.extend(added);
+        Ok(())
+    }
+
+    fn end_session(&mut self, now: u64) -> Result<(), String> {
+        self.conn
+            .execute("UPDATE sessions SET ended_at = ?1 WHERE id = ?2", params![now as i64, self.session.id])
+            .map_err(|e| format!("Failed to end session {}: {}", self.session.id, e))?;
+        Ok(())
+    }
+
+    // Average complete buckets of raw samples into rollups, then expire whatever is past retention
+    fn maintain(&mut self, now: u64) -> Result<(), String> {
+        let policy = self.status.retention.clone();
+        let width = (policy.rollup_secs * 1000) as i64;
+        let to = (now as i64 / width) * width;
+
+        // Only buckets that can't receive more samples are rolled up. A bucket that already has a
+        // rollup (samples arriving late, or a width change) is merged rather than replaced.
+        let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO rollups (session_id, sensor_id, bucket, avg, min, max, count)
+             SELECT session_id, sensor_id, (ts / ?1) * ?1 AS b, AVG(value), MIN(value), MAX(value), COUNT(*)
+             FROM samples WHERE rolled = 0 AND ts < ?2
+             GROUP BY session_id, sensor_id, b
+             ON CONFLICT (session_id, sensor_id, bucket) DO UPDATE SET
+                avg = (avg * count + excluded.avg * excluded.count) / (count + excluded.count),
+                min = MIN(min, excluded.min),
+                max = MAX(max, excluded.max),
+                count = count + excluded.count",
+            params![width, to],
+        )
+        .map_err(|e| format!("Failed to roll up samples: {}", e))?;
+        tx.execute("UPDATE samples SET rolled = 1 WHERE rolled = 0 AND ts < ?1", params![to])
+            .map_err(|e| format!("Failed to roll up samples: {}", e))?;
+
+        // Only rolled-up samples may expire, whatever the policy says
+        if let Some(days) = policy.raw_days {
+            let cutoff = now.saturating_sub(days as u64 * DAY_MS) as i64;
+            tx.execute("DELETE FROM samples WHERE rolled = 1 AND ts < ?1", params![cutoff])
+                .map_err(|e| format!("Failed to expire raw samples: {}", e))?;
+        }
+        if let Some(days) = policy.rollup_days {
+            let cutoff = now.saturating_sub(days as u64 * DAY_MS) as i64;
+            tx.execute("DELETE FROM rollups WHERE bucket < ?1", params![cutoff])
+                .map_err(|e| format!("Failed to expire averages: {}", e))?;
+            tx.execute(
+                "DELETE FROM sessions WHERE ended_at < ?1
+                 AND NOT EXISTS (SELECT 1 FROM rollups WHERE session_id = sessions.id)
+                 AND NOT EXISTS (SELECT 1 FROM samples WHERE session_id = sessions.id)",
+                params![cutoff],
+            )
+            .map_err(|e| format!("Failed to expire sessions: {}", e))?;
+        }
+        tx.commit().map_err(|e| format!("Failed to maintain the history database: {}", e))?;
+
+        self.last_maintenance = now;
+        Ok(())
+    }
+}
+
+fn insert_session(conn: &Connection, name: &str, kind: &str, machine: &MachineInfo) -> Result<SessionInfo, String> {
+    let started_at = sampling::now_millis();
+    let machine_json = serde_json::to_string(machine).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO sessions (name, kind, started_at, machine) VALUES (?1, ?2, ?3, ?4)",
+        params![name, kind, started_at as i64, machine_json],
+    )
+    .map_err(|e| format!("Failed to start a session: {}", e))?;
+
+    Ok(SessionInfo {
+        id: conn.last_insert_rowid(),
+        name: name.to_string(),
+        kind: kind.to_string(),
+        started_at,
+        ended_at: None,
+        machine: Some(machine.clone()),
+    })
+}
+
+// Columns 0-5: id, name, kind, started_at, ended_at, machine
+fn session_from_row(row: &rusqlite::Row) -> rusqlite::Result<SessionInfo> {
+    let machine: Option<String> = row.get(5)?;
+    Ok(SessionInfo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        kind: row.get(2)?,
+        started_at: row.get::<_, i64>(3)? as u64,
+        ended_at: row.get::<_, Option<i64>>(4)?.map(|t| t as u64),
+        machine: machine.and_then(|json| serde_json::from_str(&json).ok()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_store(retention: RetentionPolicy) -> OpenStore {
+        let conn = Connection::open_in_memory().unwrap();
+        OpenStore::new(conn, ":memory:".to_string(), retention, MachineInfo::default()).unwrap()
+    }
+
+    fn snapshot(timestamp: u64, values: &[(&str, f64)]) -> Snapshot {
+        Snapshot {
+            timestamp,
+            values: values.iter().map(|&(name, value)| (name.to_string(), value)).collect(),
+        }
+    }
+
+    fn count(store: &OpenStore, sql: &str) -> i64 {
+        store.conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn rolls_up_complete_buckets() {
+        let mut store = memory_store(RetentionPolicy { raw_days: None, ..Default::default() });
+        store.insert(&snapshot(1_000, &[("cpu_temp", 50.0)])).unwrap();
+        store.insert(&snapshot(2_000, &[("cpu_temp", 60.0)])).unwrap();
+        store.insert(&snapshot(61_000, &[("cpu_temp", 90.0)])).unwrap();
+
+        // The second bucket is still open at 65s, so it stays raw
+        store.maintain(65_000).unwrap();
+        let (avg, min, max, n): (f64, f64, f64, i64) = store
+            .conn
+            .query_row("SELECT avg, min, max, count FROM rollups WHERE bucket = 0", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap();
+        assert_eq!((avg, min, max, n), (55.0, 50.0, 60.0, 2));
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM rollups"), 1);
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM samples WHERE rolled = 0"), 1);
+
+        // A late sample is merged into the existing bucket
+        store.insert(&snapshot(3_000, &[("cpu_temp", 70.0)])).unwrap();
+        store.maintain(65_000).unwrap();
+        let (avg, max, n): (f64, f64, i64) = store
+            .conn
+            .query_row("SELECT avg, max, count FROM rollups WHERE bucket = 0", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((avg, max, n), (60.0, 70.0, 3));
+    }
+
+    #[test]
+    fn expires_past_retention() {
+        let mut store = memory_store(RetentionPolicy {
+            raw_days: Some(1),
+            rollup_secs: 60,
+            rollup_days: Some(2),
+        });
+        let now = 10 * DAY_MS + 30_000;
+        store.insert(&snapshot(now - 3 * DAY_MS, &[("cpu_temp", 40.0)])).unwrap();
+        store.insert(&snapshot(now - 36 * 3_600_000, &[("cpu_temp", 50.0)])).unwrap();
+        store.insert(&snapshot(now - 1_000, &[("cpu_temp", 60.0)])).unwrap();
+        let stale = insert_session(&store.conn, "Old", "plan", &store.machine).unwrap();
+        store
+            .conn
+            .execute("UPDATE sessions SET ended_at = ?1 WHERE id = ?2", params![(now - 5 * DAY_MS) as i64, stale.id])
+            .unwrap();
+
+        store.maintain(now).unwrap();
+
+        // Only the sample from the still-open bucket is left raw, and only it is younger than a day
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM samples"), 1);
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM samples WHERE rolled = 0"), 1);
+        // The three-day-old average is gone, the day-and-a-half-old one is kept
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM rollups"), 1);
+        // The empty, long-ended session expires; the current one stays
+        let sessions = count(&store, &format!("SELECT COUNT(*) FROM sessions WHERE id = {}", stale.id));
+        assert_eq!(sessions, 0);
+        let current = count(&store, &format!("SELECT COUNT(*) FROM sessions WHERE id = {}", store.session.id));
+        assert_eq!(current, 1);
+    }
+
+    #[test]
+    fn trends_combine_rollups_and_raw_samples() {
+        let store = ThermalStore {
+            open: Mutex::new(Some(memory_store(RetentionPolicy { raw_days: None, ..Default::default() }))),
+        };
+        {
+            let mut guard = store.open.lock();
+            let open = guard.as_mut().unwrap();
+            open.insert(&snapshot(1_000, &[("cpu_temp", 50.0)])).unwrap();
+            open.insert(&snapshot(2_000, &[("cpu_temp", 70.0)])).unwrap();
+            open.maintain(60_000).unwrap();
+        }
+        // Keep the two sessions' start times apart so their order is certain
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let run = store.begin_session("Soak", "plan").unwrap();
+        {
+            let mut guard = store.open.lock();
+            let open = guard.as_mut().unwrap();
+            open.insert(&snapshot(121_000, &[("cpu_temp", 80.0), ("gpu_temp", 40.0)])).unwrap();
+            open.insert(&snapshot(122_000, &[("cpu_temp", 90.0)])).unwrap();
+        }
+
+        let trend = store.session_trend("cpu_temp", None, None).unwrap();
+        let summary: Vec<_> = trend.iter().map(|t| (t.session.kind.as_str(), t.avg, t.min, t.max, t.count)).collect();
+        assert_eq!(summary, vec![("monitor", 60.0, 50.0, 70.0, 2), ("plan", 85.0, 80.0, 90.0, 2)]);
+
+        let plans = store.session_trend("cpu_temp", Some("plan"), None).unwrap();
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].session.id, run.id);
+
+        let points = store.time_trend("cpu_temp", None, None, 60).unwrap();
+        let buckets: Vec<_> = points.iter().map(|p| (p.bucket, p.avg, p.count)).collect();
+        assert_eq!(buckets, vec![(0, 60.0, 2), (120_000, 85.0, 2)]);
+        let later = store.time_trend("cpu_temp", Some(60_000), None, 60).unwrap();
+        assert_eq!(later.len(), 1);
+
+        assert!(store.session_trend("fan_0", None, None).unwrap().is_empty());
+        assert!(store.time_trend("cpu_temp", None, None, 0).is_err());
+    }
+
+    #[test]
+    fn failed_write_leaves_no_sensor_id_behind() {
+        let mut store = memory_store(RetentionPolicy::default());
+        store
+            .conn
+            .execute_batch("CREATE TRIGGER full BEFORE INSERT ON samples BEGIN SELECT RAISE(ABORT, 'disk full'); END;")
+            .unwrap();
+        assert!(store.insert(&snapshot(1_000, &[("cpu_temp", 50.0)])).is_err());
+        assert!(store.sensor_ids.is_empty());
+        assert_eq!(count(&store, "SELECT COUNT(*) FROM sensors"), 0);
+
+        store.conn.execute_batch("DROP TRIGGER full").unwrap();
+        store.insert(&snapshot(2_000, &[("cpu_temp", 50.0)])).unwrap();
+        let id = store.sensor_ids["cpu_temp"];
+        let stored: i64 = store
+            .conn
+            .query_row("SELECT id FROM sensors WHERE name = 'cpu_temp'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(id, stored);
+    }
+}
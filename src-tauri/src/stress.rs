@@ -0,0 +1,890 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::hint::black_box;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::gpu_stress::GpuIntensity;
+use crate::sensors;
+use crate::watchdog::{SafetyMonitor, Watchdog, WATCHDOG_INTERVAL};
+
+// Iteration counts per batch, tuned so one batch takes a few milliseconds.
+// The worker checks the stop flag between batches.
+const FLOAT_ITERATIONS: usize = 100_000;
+const INTEGER_ITERATIONS: usize = 1_000_000;
+const SIMD_ITERATIONS: usize = 200_000;
+const SIMD_LANES: usize = 64;
+const CACHE_ITERATIONS: usize = 1_000_000;
+
+// Batches cycle through this many seeds whose results are computed before the run,
+// so every batch can be checked against a known answer
+const KNOWN_ANSWER_SEEDS: u64 = 8;
+
+// Length of one busy/idle slice when running below 100% load
+const DUTY_PERIOD: Duration = Duration::from_millis(100);
+
+// How often the supervisor checks the deadline and the workers
+const SUPERVISOR_TICK: Duration = Duration::from_millis(100);
+
+// Buffer sizes for the memory-bound workloads
+const MEMORY_BUFFER_BYTES: usize = 64 * 1024 * 1024;  // Far larger than any LLC
+const CACHE_BUFFER_BYTES: usize = 256 * 1024;          // Fits in L2 on every supported CPU
+
+// CPU stress workloads, each loading a different part of the core
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CpuWorkload {
+    Float,    // Scalar floating point (transcendental functions)
+    Integer,  // Integer ALU, multiplier, divider and bitwise ops
+    Simd,     // Wide vector multiply-add across independent lanes
+    Memory,   // Streaming read/write over a buffer larger than the caches (like large FFT)
+    Cache,    // Random access inside an L2-resident buffer (like small FFT)
+}
+
+impl CpuWorkload {
+    pub fn parse(name: Option<&str>) -> Result<Self, String> {
+        let name = name.map(|n| n.trim().to_ascii_lowercase());
+        match name.as_deref() {
+            // "cpu" and "both" are what the stress test card sends
+            None | Some("") | Some("cpu") | Some("both") | Some("float") | Some("fp") => Ok(CpuWorkload::Float),
+            Some("integer") | Some("int") => Ok(CpuWorkload::Integer),
+            Some("simd") | Some("fma") => Ok(CpuWorkload::Simd),
+            Some("memory") | Some("large") => Ok(CpuWorkload::Memory),
+            Some("cache") | Some("small") => Ok(CpuWorkload::Cache),
+            Some(other) => Err(format!("Unknown stress workload: {}", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CpuWorkload::Float => "float",
+            CpuWorkload::Integer => "integer",
+            CpuWorkload::Simd => "simd",
+            CpuWorkload::Memory => "memory",
+            CpuWorkload::Cache => "cache",
+        }
+    }
+}
+
+// Per-thread kernel state. Buffers are allocated once per worker and reused across batches.
+pub struct WorkloadKernel {
+    workload: CpuWorkload,
+    buffer: Vec<u64>,
+}
+
+impl WorkloadKernel {
+    pub fn new(workload: CpuWorkload) -> Self {
+        let buffer_len = match workload {
+            CpuWorkload::Memory => MEMORY_BUFFER_BYTES / mem::size_of::<u64>(),
+            CpuWorkload::Cache => CACHE_BUFFER_BYTES / mem::size_of::<u64>(),
+            _ => 0,
+        };
+
+        // Allocate up front so page faults don't count as load
+        WorkloadKernel { workload, buffer: vec![0; buffer_len] }
+    }
+
+    // Amount of work in one batch: inner-loop iterations, or words touched for the memory workload
+    pub fn ops_per_batch(&self) -> u64 {
+        match self.workload {
+            CpuWorkload::Float => FLOAT_ITERATIONS as u64,
+            CpuWorkload::Integer => INTEGER_ITERATIONS as u64,
+            CpuWorkload::Simd => (SIMD_ITERATIONS * SIMD_LANES) as u64,
+            CpuWorkload::Memory => 2 * self.buffer.len() as u64,
+            CpuWorkload::Cache => CACHE_ITERATIONS as u64,
+        }
+    }
+
+    // Run one batch of work and return a checksum of the result.
+    // The checksum depends only on the workload and the seed.
+    pub fn run_batch(&mut self, seed: u64) -> u64 {
+        match self.workload {
+            CpuWorkload::Float => float_batch(seed),
+            CpuWorkload::Integer => integer_batch(seed),
+            CpuWorkload::Simd => simd_batch(seed),
+            CpuWorkload::Memory => memory_batch(&mut self.buffer, seed),
+            CpuWorkload::Cache => cache_batch(&mut self.buffer, seed),
+        }
+    }
+}
+
+fn float_batch(seed: u64) -> u64 {
+    let mut x = (seed % 1000) as f64 / 1000.0 + 0.1;
+    let mut acc = 0.0f64;
+
+    for _ in 0..FLOAT_ITERATIONS {
+        // sin() keeps x in [-1, 1], so the chain never degenerates into NaN or infinity
+        x = (x * 1.618_033_988_749 + 0.5).sin();
+        acc += x.cos() * x.abs().sqrt();
+    }
+
+    acc.to_bits() ^ x.to_bits()
+}
+
+fn integer_batch(seed: u64) -> u64 {
+    // Odd, so xorshift never starts from zero, and distinct for every seed
+    let mut x = (seed << 1) | 1;
+    let mut acc = 0u64;
+
+    for i in 0..INTEGER_ITERATIONS {
+        // xorshift64 keeps the sequence from settling into a constant
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        acc = acc.wrapping_add(x.wrapping_mul(0x9E37_79B9_7F4A_7C15).rotate_left((i & 63) as u32));
+        acc ^= (x % 1_000_003) << (x.count_ones() & 31);
+    }
+
+    acc
+}
+
+fn simd_batch(seed: u64) -> u64 {
+    let mut a: [f32; SIMD_LANES] = std::array::from_fn(|i| ((seed as usize + i) % 97) as f32 / 97.0);
+    let b: [f32; SIMD_LANES] = std::array::from_fn(|i| 0.999 - (i as f32) * 0.0001);
+    let c: [f32; SIMD_LANES] = std::array::from_fn(|i| 0.001 * (i as f32 + 1.0));
+
+    let mut sum = [0.0f32; SIMD_LANES];
+
+    // Independent lanes with no cross-lane dependencies, so the loop is vectorized
+    // and keeps every vector multiply-add port busy. |b| < 1 keeps the values bounded.
+    // `a` converges to the same fixed point for every seed, so the running sum, which
+    // keeps the seed-dependent start, is what makes the result checkable.
+    for _ in 0..SIMD_ITERATIONS {
+        for (((a, b), c), sum) in a.iter_mut().zip(&b).zip(&c).zip(&mut sum) {
+            *a = *a * b + c;
+            *sum += *a;
+        }
+        a = black_box(a);
+    }
+
+    a.iter().chain(&sum).fold(0u64, |acc, v| acc.rotate_left(5) ^ v.to_bits() as u64)
+}
+
+fn memory_batch(buffer: &mut [u64], seed: u64) -> u64 {
+    // Streaming write pass over the whole buffer...
+    for (i, word) in buffer.iter_mut().enumerate() {
+        *word = (i as u64 ^ seed).wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+    // ...then a streaming read pass that folds everything back into a checksum
+    buffer.iter().fold(seed, |acc, word| acc.rotate_left(1) ^ word)
+}
+
+fn cache_batch(buffer: &mut [u64], seed: u64) -> u64 {
+    for (i, word) in buffer.iter_mut().enumerate() {
+        *word = (i as u64 ^ seed).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    }
+
+    let mask = buffer.len() - 1;  // Buffer length is a power of two
+    let mut index = (seed as usize) & mask;
+    let mut acc = seed;
+
+    // Data-dependent random walk: each load decides the next address,
+    // so the accesses can't be prefetched and stay within L1/L2
+    for _ in 0..CACHE_ITERATIONS {
+        let value = buffer[index];
+        buffer[index] = value.rotate_left(7) ^ acc;
+        acc = acc.wrapping_add(value);
+        index = (value ^ acc) as usize & mask;
+    }
+
+    acc
+}
+
+// Compute the reference checksum for each known-answer seed
+pub fn known_answers(workload: CpuWorkload) -> Vec<u64> {
+    let mut kernel = WorkloadKernel::new(workload);
+    (0..KNOWN_ANSWER_SEEDS).map(|seed| kernel.run_batch(seed)).collect()
+}
+
+// Work and verification counters of one worker thread
+#[derive(Default)]
+pub struct WorkerCounters {
+    pub ops: AtomicU64,
+    pub batches: AtomicU64,     // Batches whose result was verified
+    pub mismatches: AtomicU64,  // Batches whose result differed from the known answer
+    error: Mutex<Option<String>>,
+}
+
+impl WorkerCounters {
+    pub fn record(&self, ops: u64, matched: bool) {
+        self.ops.fetch_add(ops, Ordering::Relaxed);
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        if !matched {
+            self.mismatches.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // Report an error that ended the worker; the run is stopped as failed
+    pub fn fail(&self, error: String) {
+        self.error.lock().get_or_insert(error);
+    }
+
+    fn error(&self) -> Option<String> {
+        self.error.lock().clone()
+    }
+}
+
+// A stress worker thread and its counters
+pub struct Worker {
+    handle: Option<JoinHandle<()>>,
+    counters: Arc<WorkerCounters>,
+}
+
+impl Worker {
+    pub fn spawn<F>(body: F) -> Self
+    where
+        F: FnOnce(&WorkerCounters) + Send + 'static,
+    {
+        let counters = Arc::new(WorkerCounters::default());
+        let thread_counters = counters.clone();
+        let handle = thread::spawn(move || {
+            if panic::catch_unwind(AssertUnwindSafe(|| body(&thread_counters))).is_err() {
+                thread_counters.fail("Stress worker thread panicked".to_string());
+            }
+        });
+        Worker { handle: Some(handle), counters }
+    }
+
+    // Wait for the thread to exit; the caller clears the stop flag first
+    pub fn join(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// Validated settings for one CPU stress run
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuStressOptions {
+    pub workload: CpuWorkload,
+    pub threads: usize,
+    pub load_percent: u32,
+    pub cpu_set: Option<Vec<usize>>,
+}
+
+impl CpuStressOptions {
+    pub fn new(
+        workload: CpuWorkload,
+        threads: Option<usize>,
+        load_percent: Option<u32>,
+        cpu_set: Option<Vec<usize>>,
+    ) -> Result<Self, String> {
+        let logical_cpus = num_cpus::get();
+
+        let cpu_set = match cpu_set {
+            Some(set) if set.is_empty() => None,
+            Some(set) => {
+                if let Some(cpu) = set.iter().find(|&&cpu| cpu >= logical_cpus) {
+                    return Err(format!("CPU {} does not exist (found {} logical CPUs)", cpu, logical_cpus));
+                }
+                Some(set)
+            }
+            None => None,
+        };
+
+        // Default to one thread per pinned CPU, or per logical CPU. Pinning always uses the real
+        // CPUs, but a simulated machine sets the default thread count.
+        let threads = threads.unwrap_or_else(|| cpu_set.as_ref().map_or_else(sensors::logical_cpus, |set| set.len()));
+        if threads == 0 {
+            return Err("Thread count must be at least 1".to_string());
+        }
+
+        let load_percent = load_percent.unwrap_or(100);
+        if load_percent == 0 || load_percent > 100 {
+            return Err(format!("Target load must be between 1 and 100%, got {}", load_percent));
+        }
+
+        Ok(CpuStressOptions { workload, threads, load_percent, cpu_set })
+    }
+}
+
+// Spawn the worker threads for a run. `load` holds the target duty cycle in percent
+// and is re-read every slice, so it can be changed while the run is in progress.
+// `answers` are the workload's known answers, from `known_answers`.
+pub fn spawn_workers(
+    options: &CpuStressOptions,
+    running: Arc<AtomicBool>,
+    load: Arc<AtomicU32>,
+    answers: Arc<Vec<u64>>,
+) -> Vec<Worker> {
+    (0..options.threads)
+        .map(|index| {
+            let running = running.clone();
+            let load = load.clone();
+            let answers = answers.clone();
+            let workload = options.workload;
+            let cpu = options.cpu_set.as_ref().map(|set| set[index % set.len()]);
+
+            Worker::spawn(move |counters| {
+                if let Some(cpu) = cpu {
+                    // On macOS this is only an affinity hint; the scheduler may still move the thread
+                    if !core_affinity::set_for_current(core_affinity::CoreId { id: cpu }) {
+                        println!("Failed to pin stress thread {} to CPU {}", index, cpu);
+                    }
+                }
+                run_worker(index, workload, &answers, &running, &load, counters);
+            })
+        })
+        .collect()
+}
+
+// A CPU stress run that was just started
+pub struct CpuRun {
+    pub id: u64,                // Run id in its slot
+    pub load: Arc<AtomicU32>,   // Duty-cycle handle, which can be changed while the run is in progress
+}
+
+// Start a CPU stress run in `slot`
+pub fn start_cpu(
+    slot: &'static StressSlot,
+    options: &CpuStressOptions,
+    duration: Option<Duration>,
+    watchdog: Option<Watchdog>,
+) -> Result<CpuRun, String> {
+    let load = Arc::new(AtomicU32::new(options.load_percent));
+    // Reference results are computed once, before the CPU heats up, and outside the
+    // slot's lock so status queries don't wait for them
+    let answers = Arc::new(known_answers(options.workload));
+
+    let id = slot.start(options.workload.name(), options.threads, duration, watchdog, |running| {
+        Ok(spawn_workers(options, running, load.clone(), answers))
+    })?;
+    slot.attach_load(load.clone());
+
+    Ok(CpuRun { id, load })
+}
+
+// Worker thread body: run batches in busy/idle slices until the stop flag is cleared
+fn run_worker(
+    index: usize,
+    workload: CpuWorkload,
+    answers: &[u64],
+    running: &AtomicBool,
+    load: &AtomicU32,
+    counters: &WorkerCounters,
+) {
+    let mut kernel = WorkloadKernel::new(workload);
+    let ops_per_batch = kernel.ops_per_batch();
+    // Start each thread at a different seed so threads don't run in lockstep
+    let mut seed = index as u64;
+
+    while running.load(Ordering::Relaxed) {
+        let percent = load.load(Ordering::Relaxed).min(100);
+        let busy = DUTY_PERIOD * percent / 100;
+        let slice_start = Instant::now();
+
+        while running.load(Ordering::Relaxed) && slice_start.elapsed() < busy {
+            let answer_index = (seed % KNOWN_ANSWER_SEEDS) as usize;
+            let checksum = kernel.run_batch(black_box(answer_index as u64));
+            let matched = checksum == answers[answer_index];
+            if !matched {
+                println!(
+                    "Stress thread {} computed a wrong {} result: {:#018x} (expected {:#018x})",
+                    index, workload.name(), checksum, answers[answer_index]
+                );
+            }
+            counters.record(ops_per_batch, matched);
+            seed = seed.wrapping_add(1);
+        }
+
+        if percent < 100 {
+            let idle = DUTY_PERIOD.saturating_sub(slice_start.elapsed());
+            if !idle.is_zero() && running.load(Ordering::Relaxed) {
+                thread::sleep(idle);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StressState {
+    Idle,       // Never started
+    Running,
+    Completed,  // Stopped automatically after the requested duration
+    Stopped,    // Stopped by the user
+    Aborted,    // Stopped by the safety watchdog
+    Failed,     // A worker reported an error
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StressStatus {
+    pub state: StressState,
+    pub elapsed_secs: f64,
+    pub remaining_secs: Option<f64>,
+    pub threads: usize,
+    pub workload: Option<String>,
+    pub load_percent: Option<u32>,  // Current duty cycle of duty-cycled (CPU) workers
+    pub gpu_intensity: Option<GpuIntensity>,  // Intensity of GPU workers
+    pub started_at: Option<u64>,  // Unix timestamp in milliseconds
+    pub abort_reason: Option<String>,
+    pub error: Option<String>,
+    pub ops_per_sec: f64,         // Total over all workers
+    pub verified_batches: u64,
+    pub mismatches: u64,          // Results that differed from the known answer
+    pub workers: Vec<WorkerStatus>,
+}
+
+// Throughput and verification results of one worker thread
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerStatus {
+    pub ops_per_sec: f64,
+    pub verified_batches: u64,
+    pub mismatches: u64,
+}
+
+impl StressStatus {
+    fn idle() -> Self {
+        StressStatus {
+            state: StressState::Idle,
+            elapsed_secs: 0.0,
+            remaining_secs: None,
+            threads: 0,
+            workload: None,
+            load_percent: None,
+            gpu_intensity: None,
+            started_at: None,
+            abort_reason: None,
+            error: None,
+            ops_per_sec: 0.0,
+            verified_batches: 0,
+            mismatches: 0,
+            workers: Vec::new(),
+        }
+    }
+}
+
+struct ActiveRun {
+    id: u64,
+    running: Arc<AtomicBool>,
+    workers: Vec<Worker>,
+    workload: String,
+    threads: usize,
+    load: Option<Arc<AtomicU32>>,
+    gpu_intensity: Option<GpuIntensity>,
+    duration: Option<Duration>,
+    started_at: SystemTime,
+    start: Instant,
+}
+
+impl ActiveRun {
+    fn status(&self, state: StressState, elapsed: Duration) -> StressStatus {
+        let remaining = match state {
+            StressState::Running => self.duration.map(|d| d.saturating_sub(elapsed).as_secs_f64()),
+            _ => None,
+        };
+
+        let elapsed_secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        let workers: Vec<WorkerStatus> = self.workers.iter()
+            .map(|worker| WorkerStatus {
+                ops_per_sec: worker.counters.ops.load(Ordering::Relaxed) as f64 / elapsed_secs,
+                verified_batches: worker.counters.batches.load(Ordering::Relaxed),
+                mismatches: worker.counters.mismatches.load(Ordering::Relaxed),
+            })
+            .collect();
+
+        StressStatus {
+            state,
+            elapsed_secs: elapsed.as_secs_f64(),
+            remaining_secs: remaining,
+            threads: self.threads,
+            workload: Some(self.workload.clone()),
+            load_percent: self.load.as_ref().map(|load| load.load(Ordering::Relaxed)),
+            gpu_intensity: self.gpu_intensity,
+            started_at: self.started_at
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_millis() as u64),
+            abort_reason: None,
+            error: None,
+            ops_per_sec: workers.iter().map(|w| w.ops_per_sec).sum(),
+            verified_batches: workers.iter().map(|w| w.verified_batches).sum(),
+            mismatches: workers.iter().map(|w| w.mismatches).sum(),
+            workers,
+        }
+    }
+}
+
+struct SlotInner {
+    next_id: u64,
+    current: Option<ActiveRun>,
+    starting: Option<Arc<AtomicBool>>,  // Stop flag of a run whose workers are still being set up
+    last: Option<StressStatus>,
+}
+
+// Lifecycle of one kind of stress test: at most one run at a time, an optional
+// automatic stop after a duration, and the outcome of the previous run
+pub struct StressSlot {
+    name: &'static str,
+    inner: Mutex<SlotInner>,
+}
+
+impl StressSlot {
+    pub fn new(name: &'static str) -> Self {
+        StressSlot {
+            name,
+            inner: Mutex::new(SlotInner { next_id: 0, current: None, starting: None, last: None }),
+        }
+    }
+
+    // Start a run and return its id. `spawn` receives the stop flag and returns the workers to
+    // join on stop. With a watchdog, the run is aborted when one of its safety limits is violated.
+    pub fn start<F>(
+        &'static self,
+        workload: &str,
+        threads: usize,
+        duration: Option<Duration>,
+        watchdog: Option<Watchdog>,
+        spawn: F,
+    ) -> Result<u64, String>
+    where
+        F: FnOnce(Arc<AtomicBool>) -> Result<Vec<Worker>, String>,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        {
+            let mut inner = self.inner.lock();
+            if inner.current.is_some() || inner.starting.is_some() {
+                return Err(format!("{} is already running", self.name));
+            }
+            inner.starting = Some(running.clone());
+        }
+
+        // Set up outside the lock: GPU setup compiles shaders, and status queries and stop
+        // shouldn't wait for that. The reservation above keeps a second run out meanwhile.
+        let spawned = spawn(running.clone());
+        let mut inner = self.inner.lock();
+        inner.starting = None;
+        let mut workers = spawned?;
+        if !running.load(Ordering::SeqCst) {
+            drop(inner);
+            for worker in &mut workers {
+                worker.join();
+            }
+            return Err(format!("{} was stopped while starting", self.name));
+        }
+
+        let supervised: Vec<Arc<WorkerCounters>> = workers.iter()
+            .map(|worker| worker.counters.clone())
+            .collect();
+
+        inner.next_id += 1;
+        let id = inner.next_id;
+        inner.current = Some(ActiveRun {
+            id,
+            running: running.clone(),
+            workers,
+            workload: workload.to_string(),
+            threads,
+            load: None,
+            gpu_intensity: None,
+            duration,
+            started_at: SystemTime::now(),
+            start: Instant::now(),
+        });
+        drop(inner);
+
+        // Supervisor: stop at the deadline, or as soon as a worker fails
+        let supervisor_running = running.clone();
+        thread::spawn(move || {
+            let deadline = duration.map(|duration| Instant::now() + duration);
+            while supervisor_running.load(Ordering::Relaxed) {
+                if let Some(error) = supervised.iter().find_map(|counters| counters.error()) {
+                    println!("{} failed: {}", self.name, error);
+                    self.finish(Some(id), StressState::Failed, Some(error));
+                    break;
+                }
+
+                let now = Instant::now();
+                if deadline.is_some_and(|deadline| now >= deadline) {
+                    self.finish(Some(id), StressState::Completed, None);
+                    break;
+                }
+                let wait = deadline.map_or(SUPERVISOR_TICK, |deadline| (deadline - now).min(SUPERVISOR_TICK));
+                thread::sleep(wait);
+            }
+        });
+
+        if let Some(watchdog) = watchdog {
+            thread::spawn(move || {
+                let mut monitor = SafetyMonitor::new(watchdog.limits);
+                while running.load(Ordering::Relaxed) {
+                    let sample = (watchdog.read)();
+                    if let Some(reason) = monitor.check(&sample, Instant::now()) {
+                        println!("{} aborted: {}", self.name, reason);
+                        self.finish(Some(id), StressState::Aborted, Some(reason));
+                        break;
+                    }
+                    thread::sleep(WATCHDOG_INTERVAL);
+                }
+            });
+        }
+
+        Ok(id)
+    }
+
+    // Report the duty-cycle handle of the current run in its status
+    pub fn attach_load(&self, load: Arc<AtomicU32>) {
+        if let Some(run) = self.inner.lock().current.as_mut() {
+            run.load = Some(load);
+        }
+    }
+
+    // Report the intensity of the current GPU run in its status
+    pub fn attach_gpu_intensity(&self, intensity: GpuIntensity) {
+        if let Some(run) = self.inner.lock().current.as_mut() {
+            run.gpu_intensity = Some(intensity);
+        }
+    }
+
+    // Stop the current run, if any, and wait for its workers to exit
+    pub fn stop(&self) -> Option<StressStatus> {
+        self.finish(None, StressState::Stopped, None)
+    }
+
+    // End run `id` as completed, for runs whose length a controller decides (load profiles).
+    // Does nothing if that run already ended.
+    pub fn complete(&self, id: u64) -> Option<StressStatus> {
+        self.finish(Some(id), StressState::Completed, None)
+    }
+
+    // Whether run `id` is the one in progress
+    pub fn is_active(&self, id: u64) -> bool {
+        self.inner.lock().current.as_ref().is_some_and(|run| run.id == id)
+    }
+
+    pub fn status(&self) -> StressStatus {
+        let inner = self.inner.lock();
+        match (&inner.current, &inner.last) {
+            (Some(run), _) => run.status(StressState::Running, run.start.elapsed()),
+            (None, Some(last)) => last.clone(),
+            (None, None) => StressStatus::idle(),
+        }
+    }
+
+    // Finish the run with the given id (or whichever run is active when `id` is None)
+    // `reason` is the abort reason or the error, depending on the state.
+    fn finish(&self, id: Option<u64>, state: StressState, reason: Option<String>) -> Option<StressStatus> {
+        let mut run = {
+            let mut inner = self.inner.lock();
+            match &inner.current {
+                Some(run) if id.is_none_or(|id| id == run.id) => inner.current.take()?,
+                // A stop while the workers are being set up cancels the start
+                None if id.is_none() => {
+                    if let Some(starting) = &inner.starting {
+                        starting.store(false, Ordering::SeqCst);
+                    }
+                    return None;
+                }
+                _ => return None,
+            }
+        };
+
+        run.running.store(false, Ordering::SeqCst);
+
+        let elapsed = run.start.elapsed();
+
+        // Join outside the lock so status queries don't block on slow workers
+        for worker in &mut run.workers {
+            worker.join();
+        }
+
+        let mut status = run.status(state, elapsed);
+        match state {
+            StressState::Failed => status.error = reason,
+            _ => status.abort_reason = reason,
+        }
+
+        self.inner.lock().last = Some(status.clone());
+        Some(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKLOADS: [CpuWorkload; 5] =
+        [CpuWorkload::Float, CpuWorkload::Integer, CpuWorkload::Simd, CpuWorkload::Memory, CpuWorkload::Cache];
+
+    fn slot() -> &'static StressSlot {
+        Box::leak(Box::new(StressSlot::new("Test stress test")))
+    }
+
+    // A run with one worker that records a matching batch every millisecond
+    fn start_ticking(slot: &'static StressSlot, duration: Option<Duration>) -> Result<u64, String> {
+        slot.start("ticking", 1, duration, None, |running| {
+            Ok(vec![Worker::spawn(move |counters| {
+                while running.load(Ordering::Relaxed) {
+                    counters.record(1, true);
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })])
+        })
+    }
+
+    // Poll the slot until `done` holds for its status, or fail after a few seconds
+    fn wait_for(slot: &StressSlot, done: impl Fn(&StressStatus) -> bool) -> StressStatus {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let status = slot.status();
+            if done(&status) {
+                return status;
+            }
+            assert!(Instant::now() < deadline, "timed out in state {:?}", status.state);
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn every_workload_reproduces_its_known_answers() {
+        for workload in WORKLOADS {
+            let answers = known_answers(workload);
+            assert_eq!(answers.len(), KNOWN_ANSWER_SEEDS as usize);
+
+            // A fresh kernel, as each worker has, computes the same results in any order
+            let mut kernel = WorkloadKernel::new(workload);
+            for seed in [KNOWN_ANSWER_SEEDS - 1, 0] {
+                assert_eq!(kernel.run_batch(seed), answers[seed as usize], "{} seed {}", workload.name(), seed);
+            }
+
+            // Different seeds give different answers, so a stuck result can't pass
+            let mut distinct = answers.clone();
+            distinct.sort_unstable();
+            distinct.dedup();
+            assert_eq!(distinct.len(), answers.len(), "{}", workload.name());
+        }
+    }
+
+    #[test]
+    fn wrong_answers_are_counted_as_mismatches() {
+        let answers: Vec<u64> = known_answers(CpuWorkload::Integer).iter().map(|answer| answer ^ 1).collect();
+        let running = Arc::new(AtomicBool::new(true));
+        let load = AtomicU32::new(100);
+        let counters = WorkerCounters::default();
+
+        let stopper = {
+            let running = running.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                running.store(false, Ordering::Relaxed);
+            })
+        };
+        run_worker(0, CpuWorkload::Integer, &answers, &running, &load, &counters);
+        stopper.join().unwrap();
+
+        let batches = counters.batches.load(Ordering::Relaxed);
+        assert!(batches > 0);
+        assert_eq!(counters.mismatches.load(Ordering::Relaxed), batches);
+    }
+
+    #[test]
+    fn runs_until_stopped() {
+        let slot = slot();
+        start_ticking(slot, None).unwrap();
+        assert!(start_ticking(slot, None).is_err(), "a second run must be refused");
+
+        wait_for(slot, |status| status.verified_batches >= 3);
+        let status = slot.stop().unwrap();
+        assert_eq!(status.state, StressState::Stopped);
+        assert_eq!(status.mismatches, 0);
+        assert_eq!(slot.status().state, StressState::Stopped);
+        assert!(slot.stop().is_none());
+    }
+
+    #[test]
+    fn completes_after_duration() {
+        let slot = slot();
+        start_ticking(slot, Some(Duration::from_millis(50))).unwrap();
+        let status = wait_for(slot, |status| status.state == StressState::Completed);
+        assert!(status.verified_batches > 0);
+        assert!(status.remaining_secs.is_none());
+    }
+
+    #[test]
+    fn complete_ends_the_run_as_completed() {
+        let slot = slot();
+        let id = start_ticking(slot, None).unwrap();
+        assert!(slot.is_active(id));
+        assert_eq!(slot.complete(id).unwrap().state, StressState::Completed);
+        assert_eq!(slot.status().state, StressState::Completed);
+        assert!(!slot.is_active(id));
+        // Nothing left to complete or stop
+        assert!(slot.complete(id).is_none());
+        assert!(slot.stop().is_none());
+    }
+
+    #[test]
+    fn complete_leaves_a_newer_run_alone() {
+        let slot = slot();
+        let old = start_ticking(slot, None).unwrap();
+        slot.stop();
+        let new = start_ticking(slot, None).unwrap();
+        assert_ne!(old, new);
+        assert!(!slot.is_active(old));
+        assert!(slot.complete(old).is_none());
+        assert_eq!(slot.status().state, StressState::Running);
+        assert_eq!(slot.stop().unwrap().state, StressState::Stopped);
+    }
+
+    #[test]
+    fn worker_error_fails_the_run() {
+        let slot = slot();
+        slot.start("failing", 1, None, None, |_| Ok(vec![Worker::spawn(|counters| counters.fail("broken".to_string()))]))
+            .unwrap();
+        let status = wait_for(slot, |status| status.state == StressState::Failed);
+        assert_eq!(status.error.as_deref(), Some("broken"));
+
+        // A panicking worker fails the run too
+        slot.start("panicking", 1, None, None, |_| Ok(vec![Worker::spawn(|_| panic!("boom"))])).unwrap();
+        let status = wait_for(slot, |status| status.state == StressState::Failed && status.workload.as_deref() == Some("panicking"));
+        assert_eq!(status.error.as_deref(), Some("Stress worker thread panicked"));
+    }
+
+    #[test]
+    fn setup_runs_outside_the_lock() {
+        use std::sync::mpsc;
+
+        let slot = slot();
+        let (entered_tx, entered_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let starter = thread::spawn(move || {
+            slot.start("slow setup", 1, None, None, move |running| {
+                entered_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+                Ok(vec![Worker::spawn(move |_| {
+                    while running.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                })])
+            })
+        });
+        entered_rx.recv().unwrap();
+
+        // Status and stop answer straight away; a second start is refused
+        assert_eq!(slot.status().state, StressState::Idle);
+        assert!(start_ticking(slot, None).is_err());
+        assert!(slot.stop().is_none());
+
+        // The stop during setup cancels the start
+        release_tx.send(()).unwrap();
+        let result = starter.join().unwrap();
+        assert_eq!(result, Err("Test stress test was stopped while starting".to_string()));
+        assert_eq!(slot.status().state, StressState::Idle);
+        start_ticking(slot, None).unwrap();
+        slot.stop();
+    }
+
+    #[test]
+    fn spawn_error_leaves_the_slot_free() {
+        let slot = slot();
+        assert_eq!(slot.start("broken", 1, None, None, |_| Err("no device".to_string())), Err("no device".to_string()));
+        assert_eq!(slot.status().state, StressState::Idle);
+        start_ticking(slot, None).unwrap();
+        slot.stop();
+    }
+}
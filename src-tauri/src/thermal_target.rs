@@ -0,0 +1,446 @@
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::sampling::{self, SensorReader};
+use crate::stress::{CpuRun, StressSlot};
+
+// How often the controller re-checks the stop flag between updates
+const TARGET_STOP_TICK: Duration = Duration::from_millis(100);
+
+// Errors within this band count as "on target" in the tracking stats
+const ON_TARGET_BAND: f64 = 1.0;
+
+// Controller update interval limits. Faster than the sensors can be read is pointless,
+// and slower than a minute can't follow a CPU heating up.
+const MIN_INTERVAL_SECS: f64 = 0.1;
+const MAX_INTERVAL_SECS: f64 = 60.0;
+
+// Closed-loop settings. The gains map °C of error to percent of load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetOptions {
+    pub target_celsius: f64,
+    pub sensor: String,  // Channel to track (see `SensorSample::value`)
+    pub kp: f64,         // % per °C
+    pub ki: f64,         // % per °C·s
+    pub kd: f64,         // % per °C/s
+    pub interval_secs: f64,
+    pub min_load: u32,
+    pub max_load: u32,
+}
+
+impl Default for TargetOptions {
+    fn default() -> Self {
+        TargetOptions {
+            target_celsius: 85.0,
+            sensor: "cpu_temp".to_string(),
+            kp: 4.0,
+            ki: 0.1,
+            kd: 0.0,
+            interval_secs: 1.0,
+            min_load: 1,
+            max_load: 100,
+        }
+    }
+}
+
+impl TargetOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !sampling::is_channel(&self.sensor) {
+            return Err(format!("Unknown sensor: {}", self.sensor));
+        }
+        if !(1..=100).contains(&self.min_load) || !(self.min_load..=100).contains(&self.max_load) {
+            return Err("Load limits must satisfy 1 <= min_load <= max_load <= 100".to_string());
+        }
+        if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&self.interval_secs) {
+            return Err(format!(
+                "Controller interval must be between {} and {} seconds",
+                MIN_INTERVAL_SECS, MAX_INTERVAL_SECS
+            ));
+        }
+        // NaN fails every comparison, so check finiteness explicitly
+        if ![self.kp, self.ki, self.kd].iter().all(|gain| gain.is_finite() && *gain >= 0.0) {
+            return Err("Controller gains must be finite and not negative".to_string());
+        }
+        if !self.target_celsius.is_finite() {
+            return Err("Target temperature must be a number".to_string());
+        }
+        Ok(())
+    }
+}
+
+// PID with output clamping. Anti-windup stops integrating while the output is
+// saturated in the direction the error pushes; the derivative acts on the
+// measurement so a target change doesn't kick the output.
+struct Pid {
+    kp: f64,
+    ki: f64,
+    kd: f64,
+    min: f64,
+    max: f64,
+    integral: f64,
+    last_measurement: Option<f64>,
+}
+
+impl Pid {
+    // `initial` is the output to start from, so the switch to closed loop is bumpless
+    fn new(options: &TargetOptions, initial: f64) -> Self {
+        Pid {
+            kp: options.kp,
+            ki: options.ki,
+            kd: options.kd,
+            min: options.min_load as f64,
+            max: options.max_load as f64,
+            integral: if options.ki > 0.0 { initial / options.ki } else { 0.0 },
+            last_measurement: None,
+        }
+    }
+
+    fn update(&mut self, setpoint: f64, measurement: f64, dt: f64) -> f64 {
+        let error = setpoint - measurement;
+        let derivative = match self.last_measurement {
+            Some(last) if dt > 0.0 => -(measurement - last) / dt,
+            _ => 0.0,
+        };
+        self.last_measurement = Some(measurement);
+
+        let unclamped = self.kp * error + self.ki * (self.integral + error * dt) + self.kd * derivative;
+        let output = unclamped.clamp(self.min, self.max);
+
+        let saturated = (unclamped > self.max && error > 0.0) || (unclamped < self.min && error < 0.0);
+        if !saturated {
+            self.integral += error * dt;
+        }
+
+        output
+    }
+}
+
+// Tracking error, counted from the first time the sensor reaches the target
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrackingStats {
+    pub time_to_target_secs: Option<f64>,
+    pub samples: usize,
+    pub mean_abs_error: Option<f64>,
+    pub rms_error: Option<f64>,
+    pub max_abs_error: Option<f64>,
+    pub on_target_percent: Option<f64>,  // Share of samples within ±1 °C
+    #[serde(skip)]
+    sum_abs: f64,
+    #[serde(skip)]
+    sum_sq: f64,
+    #[serde(skip)]
+    on_target: usize,
+    #[serde(skip)]
+    initial_sign: Option<f64>,
+}
+
+impl TrackingStats {
+    fn add(&mut self, elapsed: f64, error: f64) {
+        if self.time_to_target_secs.is_none() {
+            // Reached once the error is small or has changed sign
+            let sign = *self.initial_sign.get_or_insert(error.signum());
+            if error.abs() > ON_TARGET_BAND / 2.0 && error.signum() == sign {
+                return;
+            }
+            self.time_to_target_secs = Some(elapsed);
+        }
+
+        self.samples += 1;
+        self.sum_abs += error.abs();
+        self.sum_sq += error * error;
+        if error.abs() <= ON_TARGET_BAND {
+            self.on_target += 1;
+        }
+
+        let n = self.samples as f64;
+        self.mean_abs_error = Some(self.sum_abs / n);
+        self.rms_error = Some((self.sum_sq / n).sqrt());
+        self.max_abs_error = Some(self.max_abs_error.map_or(error.abs(), |max| max.max(error.abs())));
+        self.on_target_percent = Some(self.on_target as f64 / n * 100.0);
+    }
+}
+
+// One controller update: sensor reading in, load percent out
+pub struct TargetLoop {
+    options: TargetOptions,
+    pid: Pid,
+    load: u32,
+    pub stats: TrackingStats,
+}
+
+impl TargetLoop {
+    pub fn new(options: TargetOptions, initial_load: u32) -> Self {
+        let load = initial_load.clamp(options.min_load, options.max_load);
+        TargetLoop {
+            pid: Pid::new(&options, load as f64),
+            options,
+            load,
+            stats: TrackingStats::default(),
+        }
+    }
+
+    // Without a reading the load is held where it is
+    pub fn step(&mut self, elapsed: f64, temp: Option<f64>, dt: f64) -> u32 {
+        if let Some(temp) = temp {
+            self.load = self.pid.update(self.options.target_celsius, temp, dt).round() as u32;
+            self.stats.add(elapsed, self.options.target_celsius - temp);
+        }
+        self.load
+    }
+}
+
+// First-order thermal model: dT/dt = (ambient + gain·load/100 - T) / τ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThermalPlant {
+    pub ambient: f64,       // °C at idle
+    pub gain: f64,          // °C above ambient at 100% load
+    pub tau_secs: f64,
+    pub temp: f64,          // Starting temperature
+}
+
+impl Default for ThermalPlant {
+    fn default() -> Self {
+        ThermalPlant {
+            ambient: 40.0,
+            gain: 60.0,
+            tau_secs: 30.0,
+            temp: 40.0,
+        }
+    }
+}
+
+impl ThermalPlant {
+    pub fn step(&mut self, load_percent: f64, dt: f64) -> f64 {
+        let steady = self.ambient + self.gain * load_percent / 100.0;
+        self.temp += (steady - self.temp) * (1.0 - (-dt / self.tau_secs).exp());
+        self.temp
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationResult {
+    pub tracking: TrackingStats,
+    pub trace: Vec<(f64, f64, u32)>,  // (seconds, temperature, load percent)
+}
+
+// Run the controller against a simulated plant, e.g. to tune gains without heating a machine
+pub fn simulate(options: &TargetOptions, mut plant: ThermalPlant, duration_secs: f64, initial_load: u32) -> SimulationResult {
+    let dt = options.interval_secs;
+    let mut control = TargetLoop::new(options.clone(), initial_load);
+    let mut load = control.load;
+    let mut trace = Vec::new();
+
+    let mut t = 0.0;
+    while t < duration_secs {
+        let temp = plant.step(load as f64, dt);
+        t += dt;
+        load = control.step(t, Some(temp), dt);
+        trace.push((t, temp, load));
+    }
+
+    SimulationResult { tracking: control.stats, trace }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetStatus {
+    pub active: bool,
+    pub target_celsius: f64,
+    pub sensor: String,
+    pub current_temp: Option<f64>,
+    pub load_percent: u32,
+    pub elapsed_secs: f64,
+    pub tracking: TrackingStats,
+}
+
+#[derive(Default)]
+struct ControllerInner {
+    stop: Option<Arc<AtomicBool>>,
+    thread: Option<JoinHandle<()>>,
+    status: Option<TargetStatus>,
+}
+
+// Drives the load of a running CPU stress test towards a target temperature
+#[derive(Default)]
+pub struct TargetController {
+    inner: Mutex<ControllerInner>,
+}
+
+impl TargetController {
+    // `run` is the CPU stress test just started in `slot`; the controller only drives that run
+    pub fn start(
+        &'static self,
+        slot: &'static StressSlot,
+        run: CpuRun,
+        options: TargetOptions,
+        read: SensorReader,
+    ) {
+        self.stop();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut inner = self.inner.lock();
+        inner.stop = Some(stop.clone());
+        inner.status = Some(TargetStatus {
+            active: true,
+            target_celsius: options.target_celsius,
+            sensor: options.sensor.clone(),
+            current_temp: None,
+            load_percent: run.load.load(Ordering::Relaxed),
+            elapsed_secs: 0.0,
+            tracking: TrackingStats::default(),
+        });
+        inner.thread = Some(thread::spawn(move || self.run(slot, run, options, read, stop)));
+    }
+
+    pub fn stop(&self) {
+        let (stop, thread) = {
+            let mut inner = self.inner.lock();
+            (inner.stop.take(), inner.thread.take())
+        };
+
+        if let Some(stop) = stop {
+            stop.store(true, Ordering::SeqCst);
+        }
+        if let Some(thread) = thread {
+            let _ = thread.join();
+        }
+    }
+
+    pub fn status(&self) -> Option<TargetStatus> {
+        self.inner.lock().status.clone()
+    }
+
+    fn run(&self, slot: &StressSlot, run: CpuRun, options: TargetOptions, read: SensorReader, stop: Arc<AtomicBool>) {
+        let CpuRun { id, load } = run;
+        let interval = Duration::from_secs_f64(options.interval_secs);
+        let sensor = options.sensor.clone();
+        let mut control = TargetLoop::new(options, load.load(Ordering::Relaxed));
+        let start = Instant::now();
+        let mut last = start;
+
+        // The controller lives as long as the stress test it drives, not a run started after it
+        while !stop.load(Ordering::SeqCst) && slot.is_active(id) {
+            let now = Instant::now();
+            let temp = read().value(&sensor);
+            let percent = control.step(start.elapsed().as_secs_f64(), temp, now.duration_since(last).as_secs_f64());
+            last = now;
+            load.store(percent, Ordering::Relaxed);
+
+            if let Some(status) = self.inner.lock().status.as_mut() {
+                status.current_temp = temp;
+                status.load_percent = percent;
+                status.elapsed_secs = start.elapsed().as_secs_f64();
+                status.tracking = control.stats.clone();
+            }
+
+            let next = now + interval;
+            while Instant::now() < next && !stop.load(Ordering::SeqCst) {
+                thread::sleep(TARGET_STOP_TICK.min(next.saturating_duration_since(Instant::now())));
+            }
+        }
+
+        if let Some(status) = self.inner.lock().status.as_mut() {
+            status.active = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_rejects_bad_intervals_and_gains() {
+        assert!(TargetOptions::default().validate().is_ok());
+
+        for interval_secs in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e12] {
+            let options = TargetOptions { interval_secs, ..Default::default() };
+            assert!(options.validate().is_err(), "interval {} accepted", interval_secs);
+        }
+        for kp in [-1.0, f64::NAN, f64::INFINITY] {
+            assert!(TargetOptions { kp, ..Default::default() }.validate().is_err(), "kp {} accepted", kp);
+        }
+        assert!(TargetOptions { kd: f64::NAN, ..Default::default() }.validate().is_err());
+        assert!(TargetOptions { target_celsius: f64::NAN, ..Default::default() }.validate().is_err());
+        assert!(TargetOptions { min_load: 60, max_load: 50, ..Default::default() }.validate().is_err());
+        assert!(TargetOptions { sensor: "core_temp_x".to_string(), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn pid_starts_bumpless() {
+        let mut pid = Pid::new(&TargetOptions::default(), 50.0);
+        assert!((pid.update(85.0, 85.0, 1.0) - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pid_does_not_wind_up_while_saturated() {
+        let options = TargetOptions { kp: 1.0, ki: 0.5, ..Default::default() };
+        let mut pid = Pid::new(&options, 100.0);
+        // Far below target for a long time: pinned at the maximum
+        for _ in 0..1000 {
+            assert_eq!(pid.update(85.0, 40.0, 1.0), 100.0);
+        }
+        // Once above target the output comes off the limit straight away
+        assert!(pid.update(85.0, 90.0, 1.0) < 100.0);
+    }
+
+    #[test]
+    fn derivative_ignores_target_changes() {
+        let options = TargetOptions { kp: 0.0, ki: 0.0, kd: 10.0, min_load: 1, max_load: 100, ..Default::default() };
+        let mut pid = Pid::new(&options, 50.0);
+        pid.update(80.0, 70.0, 1.0);
+        // Same measurement, new target: no derivative kick
+        assert_eq!(pid.update(90.0, 70.0, 1.0), 1.0);
+        // Rising temperature pushes the load down
+        assert_eq!(pid.update(90.0, 65.0, 1.0), 50.0);
+    }
+
+    #[test]
+    fn plant_approaches_its_steady_temperature() {
+        let mut plant = ThermalPlant::default();
+        let after_tau = plant.step(100.0, 30.0);
+        // One time constant covers 63% of the way from 40 to 100 °C
+        assert!((after_tau - (40.0 + 60.0 * (1.0 - (-1.0f64).exp()))).abs() < 1e-9);
+        for _ in 0..100 {
+            plant.step(100.0, 30.0);
+        }
+        assert!((plant.temp - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn simulation_settles_on_target() {
+        let result = simulate(&TargetOptions::default(), ThermalPlant::default(), 600.0, 50);
+        let tracking = &result.tracking;
+
+        assert_eq!(result.trace.len(), 600);
+        assert!(tracking.time_to_target_secs.is_some_and(|secs| secs < 120.0), "{:?}", tracking);
+        let &(_, final_temp, final_load) = result.trace.last().unwrap();
+        assert!((final_temp - 85.0).abs() < 0.5, "ended at {} °C", final_temp);
+        // 85 °C needs 75% load on the default plant
+        assert!((74..=76).contains(&final_load), "ended at {}%", final_load);
+        assert!(result.trace.iter().all(|&(_, _, load)| (1..=100).contains(&load)));
+        assert!(tracking.on_target_percent.unwrap() > 50.0, "{:?}", tracking);
+    }
+
+    #[test]
+    fn unreachable_target_holds_full_load() {
+        let plant = ThermalPlant { gain: 30.0, ..Default::default() };
+        let result = simulate(&TargetOptions::default(), plant, 300.0, 50);
+        assert!(result.tracking.time_to_target_secs.is_none());
+        assert_eq!(result.tracking.samples, 0);
+        assert!(result.trace[10..].iter().all(|&(_, _, load)| load == 100));
+    }
+
+    #[test]
+    fn missing_readings_hold_the_load() {
+        let mut control = TargetLoop::new(TargetOptions::default(), 40);
+        assert_eq!(control.step(1.0, None, 1.0), 40);
+        assert_eq!(control.stats.samples, 0);
+        assert!(control.step(2.0, Some(95.0), 1.0) < 40);
+    }
+}
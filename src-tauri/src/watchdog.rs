@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::sampling::{SensorReader, SensorSample};
+
+// How often the watchdog samples the sensors during a run
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+// Safety limits for a stress run. A `None` limit disables that check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafetyLimits {
+    pub cpu_temp_limit: Option<f64>,  // °C
+    pub gpu_temp_limit: Option<f64>,  // °C
+    pub over_limit_secs: u64,         // How long a limit may be exceeded before aborting
+    pub fan_stall_secs: Option<u64>,  // How long a previously spinning fan may read 0 RPM
+}
+
+impl Default for SafetyLimits {
+    fn default() -> Self {
+        SafetyLimits {
+            cpu_temp_limit: Some(100.0),
+            gpu_temp_limit: Some(100.0),
+            over_limit_secs: 10,
+            fan_stall_secs: Some(5),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Watchdog {
+    pub limits: SafetyLimits,
+    pub read: SensorReader,
+}
+
+// Tracks how long each limit has been violated and decides when to abort
+pub struct SafetyMonitor {
+    limits: SafetyLimits,
+    cpu_over_since: Option<Instant>,
+    gpu_over_since: Option<Instant>,
+    fans_seen_spinning: Vec<usize>,
+    fan_stopped_since: HashMap<usize, Instant>,
+}
+
+impl SafetyMonitor {
+    pub fn new(limits: SafetyLimits) -> Self {
+        SafetyMonitor {
+            limits,
+            cpu_over_since: None,
+            gpu_over_since: None,
+            fans_seen_spinning: Vec::new(),
+            fan_stopped_since: HashMap::new(),
+        }
+    }
+
+    // Feed one sample; returns the abort reason once a limit has been violated for too long
+    pub fn check(&mut self, sample: &SensorSample, now: Instant) -> Option<String> {
+        let hold = Duration::from_secs(self.limits.over_limit_secs);
+
+        if let Some(reason) = check_temp("CPU", sample.cpu_temp, self.limits.cpu_temp_limit, &mut self.cpu_over_since, hold, now) {
+            return Some(reason);
+        }
+        if let Some(reason) = check_temp("GPU", sample.gpu_temp, self.limits.gpu_temp_limit, &mut self.gpu_over_since, hold, now) {
+            return Some(reason);
+        }
+
+        let stall_limit = Duration::from_secs(self.limits.fan_stall_secs?);
+        for &(fan, rpm) in &sample.fans {
+            if rpm > 0.0 {
+                if !self.fans_seen_spinning.contains(&fan) {
+                    self.fans_seen_spinning.push(fan);
+                }
+                self.fan_stopped_since.remove(&fan);
+                continue;
+            }
+
+            // Fanless machines and fans that never spun up are not stalls
+            if !self.fans_seen_spinning.contains(&fan) {
+                continue;
+            }
+
+            let since = *self.fan_stopped_since.entry(fan).or_insert(now);
+            if now.duration_since(since) >= stall_limit {
+                return Some(format!(
+                    "Fan {} stalled: 0 RPM for {}s under load",
+                    fan,
+                    now.duration_since(since).as_secs()
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+fn check_temp(
+    name: &str,
+    temp: Option<f64>,
+    limit: Option<f64>,
+    over_since: &mut Option<Instant>,
+    hold: Duration,
+    now: Instant,
+) -> Option<String> {
+    let (temp, limit) = match (temp, limit) {
+        (Some(temp), Some(limit)) => (temp, limit),
+        _ => {
+            *over_since = None;
+            return None;
+        }
+    };
+
+    if temp <= limit {
+        *over_since = None;
+        return None;
+    }
+
+    let since = *over_since.get_or_insert(now);
+    if now.duration_since(since) >= hold {
+        Some(format!(
+            "{} temperature {:.1}°C exceeded the {:.1}°C limit for {}s",
+            name,
+            temp,
+            limit,
+            now.duration_since(since).as_secs()
+        ))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One reading `secs` into the run
+    struct Reading {
+        secs: u64,
+        cpu: Option<f64>,
+        gpu: Option<f64>,
+        fans: &'static [(usize, f64)],
+    }
+
+    const fn at(secs: u64, cpu: f64) -> Reading {
+        Reading { secs, cpu: Some(cpu), gpu: None, fans: &[] }
+    }
+
+    const fn gpu_at(secs: u64, gpu: f64) -> Reading {
+        Reading { secs, cpu: None, gpu: Some(gpu), fans: &[] }
+    }
+
+    const fn fans_at(secs: u64, fans: &'static [(usize, f64)]) -> Reading {
+        Reading { secs, cpu: Some(60.0), gpu: None, fans }
+    }
+
+    // A case name, its readings, and the index of the aborting reading with part of its reason
+    type Case = (&'static str, Vec<Reading>, Option<(usize, &'static str)>);
+
+    // Feed the readings in order; returns the index of the reading that aborted and the reason
+    fn run(limits: SafetyLimits, readings: &[Reading]) -> Option<(usize, String)> {
+        let mut monitor = SafetyMonitor::new(limits);
+        let start = Instant::now();
+        readings.iter().enumerate().find_map(|(index, reading)| {
+            let sample = SensorSample {
+                cpu_temp: reading.cpu,
+                gpu_temp: reading.gpu,
+                fans: reading.fans.to_vec(),
+                ..Default::default()
+            };
+            monitor.check(&sample, start + Duration::from_secs(reading.secs)).map(|reason| (index, reason))
+        })
+    }
+
+    #[test]
+    fn safety_monitor_cases() {
+        let cases: Vec<Case> = vec![
+            ("under the limit", vec![at(0, 95.0), at(30, 100.0)], None),
+            ("over for less than the hold", vec![at(0, 105.0), at(9, 105.0), at(10, 90.0)], None),
+            ("over for the hold", vec![at(0, 105.0), at(5, 106.0), at(10, 107.0)], Some((2, "CPU temperature 107.0°C"))),
+            (
+                "dropping under the limit resets the hold",
+                vec![at(0, 105.0), at(8, 105.0), at(9, 99.0), at(10, 105.0), at(19, 105.0), at(20, 105.0)],
+                Some((5, "for 10s")),
+            ),
+            ("a missing reading resets the hold", vec![at(0, 105.0), Reading { secs: 5, cpu: None, gpu: None, fans: &[] }, at(10, 105.0)], None),
+            ("GPU over for the hold", vec![gpu_at(0, 101.0), gpu_at(10, 101.0)], Some((1, "GPU temperature"))),
+            ("fan stalls after spinning", vec![fans_at(0, &[(0, 1500.0)]), fans_at(1, &[(0, 0.0)]), fans_at(6, &[(0, 0.0)])], Some((2, "Fan 0 stalled"))),
+            ("fan stopped briefly", vec![fans_at(0, &[(0, 1500.0)]), fans_at(1, &[(0, 0.0)]), fans_at(5, &[(0, 1200.0)]), fans_at(9, &[(0, 0.0)])], None),
+            ("fan that never spun", vec![fans_at(0, &[(1, 0.0)]), fans_at(60, &[(1, 0.0)])], None),
+        ];
+
+        for (case, readings, expected) in cases {
+            let outcome = run(SafetyLimits::default(), &readings);
+            match (outcome, expected) {
+                (None, None) => {}
+                (Some((index, reason)), Some((expected_index, expected_reason))) => {
+                    assert_eq!(index, expected_index, "{}: {}", case, reason);
+                    assert!(reason.contains(expected_reason), "{}: {}", case, reason);
+                }
+                (outcome, expected) => panic!("{}: got {:?}, expected {:?}", case, outcome, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn disabled_limits_never_abort() {
+        let limits = SafetyLimits { cpu_temp_limit: None, gpu_temp_limit: None, over_limit_secs: 0, fan_stall_secs: None };
+        let readings = [
+            Reading { secs: 0, cpu: Some(150.0), gpu: Some(150.0), fans: &[(0, 1500.0)] },
+            Reading { secs: 60, cpu: Some(150.0), gpu: Some(150.0), fans: &[(0, 0.0)] },
+        ];
+        assert!(run(limits, &readings).is_none());
+
+        // Without a hold, the first reading over the limit aborts
+        let limits = SafetyLimits { over_limit_secs: 0, ..Default::default() };
+        assert_eq!(run(limits, &[at(0, 100.5)]).map(|(index, _)| index), Some(0));
+    }
+}